@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+
+DROP INDEX IF EXISTS public.follows_following_user_id_idx;
+DROP INDEX IF EXISTS public.posts_user_id_created_at_idx;
@@ -0,0 +1,7 @@
+-- Your SQL goes here
+
+CREATE INDEX IF NOT EXISTS posts_user_id_created_at_idx
+    ON public.posts USING btree (user_id, created_at DESC, id DESC);
+
+CREATE INDEX IF NOT EXISTS follows_following_user_id_idx
+    ON public.follows USING btree (following_user_id);
@@ -0,0 +1,5 @@
+-- This file should undo anything in `up.sql`
+
+DROP TABLE IF EXISTS public.tag_follows;
+DROP TABLE IF EXISTS public.post_tags;
+DROP TABLE IF EXISTS public.tags;
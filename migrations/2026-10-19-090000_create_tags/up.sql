@@ -0,0 +1,57 @@
+-- Your SQL goes here
+
+CREATE TABLE IF NOT EXISTS public.tags
+(
+    id bigint NOT NULL GENERATED BY DEFAULT AS IDENTITY ( INCREMENT 1 START 1 MINVALUE 1 MAXVALUE 9223372036854775807 CACHE 1 ),
+    created_at timestamp with time zone NOT NULL DEFAULT now(),
+    name VARCHAR(64) NOT NULL CHECK (name ~ '^[a-z0-9_]+$'),
+    CONSTRAINT tags_pkey PRIMARY KEY (id),
+    CONSTRAINT tags_name_key UNIQUE (name)
+);
+
+CREATE TABLE IF NOT EXISTS public.post_tags
+(
+    id bigint NOT NULL GENERATED BY DEFAULT AS IDENTITY ( INCREMENT 1 START 1 MINVALUE 1 MAXVALUE 9223372036854775807 CACHE 1 ),
+    created_at timestamp with time zone NOT NULL DEFAULT now(),
+    post_id bigint NOT NULL,
+    tag_id bigint NOT NULL,
+    CONSTRAINT post_tags_pkey PRIMARY KEY (id),
+    CONSTRAINT post_tags_unique_post_tag UNIQUE (post_id, tag_id)
+);
+
+ALTER TABLE IF EXISTS public.post_tags
+    ADD CONSTRAINT post_tags_post_id_fkey FOREIGN KEY (post_id)
+    REFERENCES public.posts (id) MATCH SIMPLE
+    ON UPDATE NO ACTION
+    ON DELETE CASCADE;
+
+ALTER TABLE IF EXISTS public.post_tags
+    ADD CONSTRAINT post_tags_tag_id_fkey FOREIGN KEY (tag_id)
+    REFERENCES public.tags (id) MATCH SIMPLE
+    ON UPDATE NO ACTION
+    ON DELETE CASCADE;
+
+CREATE INDEX IF NOT EXISTS post_tags_tag_id_created_at_idx
+    ON public.post_tags USING btree (tag_id, created_at DESC);
+
+CREATE TABLE IF NOT EXISTS public.tag_follows
+(
+    id bigint NOT NULL GENERATED BY DEFAULT AS IDENTITY ( INCREMENT 1 START 1 MINVALUE 1 MAXVALUE 9223372036854775807 CACHE 1 ),
+    created_at timestamp with time zone NOT NULL DEFAULT now(),
+    user_id bigint NOT NULL,
+    tag_id bigint NOT NULL,
+    CONSTRAINT tag_follows_pkey PRIMARY KEY (id),
+    CONSTRAINT tag_follows_unique_user_tag UNIQUE (user_id, tag_id)
+);
+
+ALTER TABLE IF EXISTS public.tag_follows
+    ADD CONSTRAINT tag_follows_user_id_fkey FOREIGN KEY (user_id)
+    REFERENCES public.users (id) MATCH SIMPLE
+    ON UPDATE NO ACTION
+    ON DELETE CASCADE;
+
+ALTER TABLE IF EXISTS public.tag_follows
+    ADD CONSTRAINT tag_follows_tag_id_fkey FOREIGN KEY (tag_id)
+    REFERENCES public.tags (id) MATCH SIMPLE
+    ON UPDATE NO ACTION
+    ON DELETE CASCADE;
@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+
+DROP TABLE IF EXISTS public.notifications;
+DROP TABLE IF EXISTS public.mentions;
@@ -0,0 +1,61 @@
+-- Your SQL goes here
+
+CREATE TABLE IF NOT EXISTS public.mentions
+(
+    id bigint NOT NULL GENERATED BY DEFAULT AS IDENTITY ( INCREMENT 1 START 1 MINVALUE 1 MAXVALUE 9223372036854775807 CACHE 1 ),
+    created_at timestamp with time zone NOT NULL DEFAULT now(),
+    post_id bigint NOT NULL,
+    user_id bigint NOT NULL,
+    start_offset integer NOT NULL,
+    end_offset integer NOT NULL,
+    CONSTRAINT mentions_pkey PRIMARY KEY (id),
+    CONSTRAINT mentions_unique_post_offset UNIQUE (post_id, start_offset)
+);
+
+ALTER TABLE IF EXISTS public.mentions
+    ADD CONSTRAINT mentions_post_id_fkey FOREIGN KEY (post_id)
+    REFERENCES public.posts (id) MATCH SIMPLE
+    ON UPDATE NO ACTION
+    ON DELETE CASCADE;
+
+ALTER TABLE IF EXISTS public.mentions
+    ADD CONSTRAINT mentions_user_id_fkey FOREIGN KEY (user_id)
+    REFERENCES public.users (id) MATCH SIMPLE
+    ON UPDATE NO ACTION
+    ON DELETE CASCADE;
+
+CREATE INDEX IF NOT EXISTS mentions_user_id_idx
+    ON public.mentions USING btree (user_id);
+
+CREATE TABLE IF NOT EXISTS public.notifications
+(
+    id bigint NOT NULL GENERATED BY DEFAULT AS IDENTITY ( INCREMENT 1 START 1 MINVALUE 1 MAXVALUE 9223372036854775807 CACHE 1 ),
+    created_at timestamp with time zone NOT NULL DEFAULT now(),
+    user_id bigint NOT NULL,
+    actor_user_id bigint NOT NULL,
+    kind character varying COLLATE pg_catalog."default" NOT NULL,
+    post_id bigint,
+    is_read boolean NOT NULL DEFAULT false,
+    CONSTRAINT notifications_pkey PRIMARY KEY (id)
+);
+
+ALTER TABLE IF EXISTS public.notifications
+    ADD CONSTRAINT notifications_user_id_fkey FOREIGN KEY (user_id)
+    REFERENCES public.users (id) MATCH SIMPLE
+    ON UPDATE NO ACTION
+    ON DELETE CASCADE;
+
+ALTER TABLE IF EXISTS public.notifications
+    ADD CONSTRAINT notifications_actor_user_id_fkey FOREIGN KEY (actor_user_id)
+    REFERENCES public.users (id) MATCH SIMPLE
+    ON UPDATE NO ACTION
+    ON DELETE CASCADE;
+
+ALTER TABLE IF EXISTS public.notifications
+    ADD CONSTRAINT notifications_post_id_fkey FOREIGN KEY (post_id)
+    REFERENCES public.posts (id) MATCH SIMPLE
+    ON UPDATE NO ACTION
+    ON DELETE CASCADE;
+
+CREATE INDEX IF NOT EXISTS notifications_user_id_created_at_idx
+    ON public.notifications USING btree (user_id, created_at DESC, id DESC);
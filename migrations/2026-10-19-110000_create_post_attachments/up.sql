@@ -0,0 +1,24 @@
+-- Your SQL goes here
+
+CREATE TABLE IF NOT EXISTS public.post_attachments
+(
+    id bigint NOT NULL GENERATED BY DEFAULT AS IDENTITY ( INCREMENT 1 START 1 MINVALUE 1 MAXVALUE 9223372036854775807 CACHE 1 ),
+    created_at timestamp with time zone NOT NULL DEFAULT now(),
+    post_id bigint NOT NULL,
+    "position" integer NOT NULL,
+    url character varying COLLATE pg_catalog."default" NOT NULL,
+    storage_key character varying COLLATE pg_catalog."default" NOT NULL,
+    mime_type character varying COLLATE pg_catalog."default" NOT NULL,
+    size_bytes bigint NOT NULL,
+    width integer,
+    height integer,
+    file_name character varying COLLATE pg_catalog."default",
+    CONSTRAINT post_attachments_pkey PRIMARY KEY (id),
+    CONSTRAINT post_attachments_unique_post_position UNIQUE (post_id, "position")
+);
+
+ALTER TABLE IF EXISTS public.post_attachments
+    ADD CONSTRAINT post_attachments_post_id_fkey FOREIGN KEY (post_id)
+    REFERENCES public.posts (id) MATCH SIMPLE
+    ON UPDATE NO ACTION
+    ON DELETE CASCADE;
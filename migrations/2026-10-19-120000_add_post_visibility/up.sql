@@ -0,0 +1,5 @@
+-- Your SQL goes here
+
+ALTER TABLE IF EXISTS public.posts
+    ADD COLUMN visibility character varying COLLATE pg_catalog."default" NOT NULL DEFAULT 'public'
+    CONSTRAINT posts_visibility_check CHECK (visibility IN ('public', 'followers', 'private'));
@@ -0,0 +1,8 @@
+-- This file should undo anything in `up.sql`
+
+DROP INDEX IF EXISTS public.posts_user_id_status_idx;
+DROP INDEX IF EXISTS public.posts_scheduled_publish_at_idx;
+ALTER TABLE IF EXISTS public.posts
+    DROP CONSTRAINT IF EXISTS posts_scheduled_publish_at_check,
+    DROP COLUMN IF EXISTS publish_at,
+    DROP COLUMN IF EXISTS status;
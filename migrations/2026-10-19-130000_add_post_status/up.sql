@@ -0,0 +1,15 @@
+-- Your SQL goes here
+
+ALTER TABLE IF EXISTS public.posts
+    ADD COLUMN status character varying COLLATE pg_catalog."default" NOT NULL DEFAULT 'published'
+    CONSTRAINT posts_status_check CHECK (status IN ('draft', 'scheduled', 'published')),
+    ADD COLUMN publish_at timestamp with time zone,
+    ADD CONSTRAINT posts_scheduled_publish_at_check CHECK (status <> 'scheduled' OR publish_at IS NOT NULL);
+
+CREATE INDEX IF NOT EXISTS posts_scheduled_publish_at_idx
+    ON public.posts USING btree (publish_at)
+    WHERE status = 'scheduled';
+
+CREATE INDEX IF NOT EXISTS posts_user_id_status_idx
+    ON public.posts USING btree (user_id, created_at DESC, id DESC)
+    WHERE status <> 'published';
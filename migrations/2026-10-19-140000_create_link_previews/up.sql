@@ -0,0 +1,45 @@
+-- Your SQL goes here
+
+CREATE TABLE IF NOT EXISTS public.link_previews
+(
+    id bigint NOT NULL GENERATED BY DEFAULT AS IDENTITY ( INCREMENT 1 START 1 MINVALUE 1 MAXVALUE 9223372036854775807 CACHE 1 ),
+    created_at timestamp with time zone NOT NULL DEFAULT now(),
+    url character varying COLLATE pg_catalog."default" NOT NULL,
+    status character varying COLLATE pg_catalog."default" NOT NULL DEFAULT 'pending',
+    title character varying COLLATE pg_catalog."default",
+    description character varying COLLATE pg_catalog."default",
+    image_url character varying COLLATE pg_catalog."default",
+    site_name character varying COLLATE pg_catalog."default",
+    attempts integer NOT NULL DEFAULT 0,
+    fetched_at timestamp with time zone,
+    CONSTRAINT link_previews_pkey PRIMARY KEY (id),
+    CONSTRAINT link_previews_unique_url UNIQUE (url),
+    CONSTRAINT link_previews_status_check CHECK (status IN ('pending', 'ready', 'failed'))
+);
+
+CREATE INDEX IF NOT EXISTS link_previews_pending_idx
+    ON public.link_previews (created_at)
+    WHERE status = 'pending';
+
+CREATE TABLE IF NOT EXISTS public.post_links
+(
+    id bigint NOT NULL GENERATED BY DEFAULT AS IDENTITY ( INCREMENT 1 START 1 MINVALUE 1 MAXVALUE 9223372036854775807 CACHE 1 ),
+    created_at timestamp with time zone NOT NULL DEFAULT now(),
+    post_id bigint NOT NULL,
+    link_preview_id bigint NOT NULL,
+    "position" integer NOT NULL,
+    CONSTRAINT post_links_pkey PRIMARY KEY (id),
+    CONSTRAINT post_links_unique_post_link UNIQUE (post_id, link_preview_id)
+);
+
+ALTER TABLE IF EXISTS public.post_links
+    ADD CONSTRAINT post_links_post_id_fkey FOREIGN KEY (post_id)
+    REFERENCES public.posts (id) MATCH SIMPLE
+    ON UPDATE NO ACTION
+    ON DELETE CASCADE;
+
+ALTER TABLE IF EXISTS public.post_links
+    ADD CONSTRAINT post_links_link_preview_id_fkey FOREIGN KEY (link_preview_id)
+    REFERENCES public.link_previews (id) MATCH SIMPLE
+    ON UPDATE NO ACTION
+    ON DELETE CASCADE;
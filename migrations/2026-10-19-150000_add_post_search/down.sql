@@ -0,0 +1,6 @@
+-- This file should undo anything in `up.sql`
+
+DROP INDEX IF EXISTS public.posts_search_vector_idx;
+
+ALTER TABLE IF EXISTS public.posts
+    DROP COLUMN IF EXISTS search_vector;
@@ -0,0 +1,10 @@
+-- Your SQL goes here
+
+-- Only read through SQL fragments in the search endpoint. It is left out of
+-- `src/schema.rs` so that `posts::all_columns` keeps matching `Post`.
+ALTER TABLE IF EXISTS public.posts
+    ADD COLUMN search_vector tsvector
+    GENERATED ALWAYS AS (to_tsvector('english', body)) STORED;
+
+CREATE INDEX IF NOT EXISTS posts_search_vector_idx
+    ON public.posts USING GIN (search_vector);
@@ -0,0 +1,5 @@
+-- This file should undo anything in `up.sql`
+
+DROP INDEX IF EXISTS public.follows_followed_user_id_created_at_idx;
+DROP TABLE IF EXISTS public.post_stats_daily;
+DROP TABLE IF EXISTS public.post_impressions;
@@ -0,0 +1,44 @@
+-- Your SQL goes here
+
+-- One row per viewer, post and kind within a time window; the unique constraint
+-- does the deduplication. Rows are rolled up into post_stats_daily and pruned.
+CREATE TABLE IF NOT EXISTS public.post_impressions
+(
+    id bigint NOT NULL GENERATED BY DEFAULT AS IDENTITY ( INCREMENT 1 START 1 MINVALUE 1 MAXVALUE 9223372036854775807 CACHE 1 ),
+    created_at timestamp with time zone NOT NULL DEFAULT now(),
+    post_id bigint NOT NULL,
+    kind character varying COLLATE pg_catalog."default" NOT NULL,
+    viewer_key character varying COLLATE pg_catalog."default" NOT NULL,
+    window_start timestamp with time zone NOT NULL,
+    CONSTRAINT post_impressions_pkey PRIMARY KEY (id),
+    CONSTRAINT post_impressions_unique_viewer_window UNIQUE (post_id, kind, viewer_key, window_start),
+    CONSTRAINT post_impressions_kind_check CHECK (kind IN ('view', 'impression'))
+);
+
+CREATE INDEX IF NOT EXISTS post_impressions_window_start_idx
+    ON public.post_impressions USING btree (window_start);
+
+ALTER TABLE IF EXISTS public.post_impressions
+    ADD CONSTRAINT post_impressions_post_id_fkey FOREIGN KEY (post_id)
+    REFERENCES public.posts (id) MATCH SIMPLE
+    ON UPDATE NO ACTION
+    ON DELETE CASCADE;
+
+CREATE TABLE IF NOT EXISTS public.post_stats_daily
+(
+    post_id bigint NOT NULL,
+    day date NOT NULL,
+    views bigint NOT NULL DEFAULT 0,
+    unique_viewers bigint NOT NULL DEFAULT 0,
+    impressions bigint NOT NULL DEFAULT 0,
+    CONSTRAINT post_stats_daily_pkey PRIMARY KEY (post_id, day)
+);
+
+ALTER TABLE IF EXISTS public.post_stats_daily
+    ADD CONSTRAINT post_stats_daily_post_id_fkey FOREIGN KEY (post_id)
+    REFERENCES public.posts (id) MATCH SIMPLE
+    ON UPDATE NO ACTION
+    ON DELETE CASCADE;
+
+CREATE INDEX IF NOT EXISTS follows_followed_user_id_created_at_idx
+    ON public.follows USING btree (followed_user_id, created_at);
@@ -0,0 +1,26 @@
+-- Your SQL goes here
+
+CREATE TABLE IF NOT EXISTS public.bookmarks
+(
+    id bigint NOT NULL GENERATED BY DEFAULT AS IDENTITY ( INCREMENT 1 START 1 MINVALUE 1 MAXVALUE 9223372036854775807 CACHE 1 ),
+    created_at timestamp with time zone NOT NULL DEFAULT now(),
+    user_id bigint NOT NULL,
+    post_id bigint NOT NULL,
+    CONSTRAINT bookmarks_pkey PRIMARY KEY (id),
+    CONSTRAINT bookmarks_unique_user_post UNIQUE (user_id, post_id)
+);
+
+CREATE INDEX IF NOT EXISTS bookmarks_user_id_created_at_idx
+    ON public.bookmarks USING btree (user_id, created_at DESC, id DESC);
+
+ALTER TABLE IF EXISTS public.bookmarks
+    ADD CONSTRAINT bookmarks_user_id_fkey FOREIGN KEY (user_id)
+    REFERENCES public.users (id) MATCH SIMPLE
+    ON UPDATE NO ACTION
+    ON DELETE CASCADE;
+
+ALTER TABLE IF EXISTS public.bookmarks
+    ADD CONSTRAINT bookmarks_post_id_fkey FOREIGN KEY (post_id)
+    REFERENCES public.posts (id) MATCH SIMPLE
+    ON UPDATE NO ACTION
+    ON DELETE CASCADE;
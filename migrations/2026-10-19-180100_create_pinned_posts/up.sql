@@ -0,0 +1,24 @@
+-- Your SQL goes here
+
+CREATE TABLE IF NOT EXISTS public.pinned_posts
+(
+    id bigint NOT NULL GENERATED BY DEFAULT AS IDENTITY ( INCREMENT 1 START 1 MINVALUE 1 MAXVALUE 9223372036854775807 CACHE 1 ),
+    created_at timestamp with time zone NOT NULL DEFAULT now(),
+    post_id bigint NOT NULL,
+    user_id bigint NOT NULL,
+    "position" integer NOT NULL,
+    CONSTRAINT pinned_posts_pkey PRIMARY KEY (id),
+    CONSTRAINT pinned_posts_unique_user_post UNIQUE (user_id, post_id)
+);
+
+ALTER TABLE IF EXISTS public.pinned_posts
+    ADD CONSTRAINT pinned_posts_post_id_fkey FOREIGN KEY (post_id)
+    REFERENCES public.posts (id) MATCH SIMPLE
+    ON UPDATE NO ACTION
+    ON DELETE CASCADE;
+
+ALTER TABLE IF EXISTS public.pinned_posts
+    ADD CONSTRAINT pinned_posts_user_id_fkey FOREIGN KEY (user_id)
+    REFERENCES public.users (id) MATCH SIMPLE
+    ON UPDATE NO ACTION
+    ON DELETE CASCADE;
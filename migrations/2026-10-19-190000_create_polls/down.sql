@@ -0,0 +1,8 @@
+-- This file should undo anything in `up.sql`
+
+DROP TABLE IF EXISTS public.poll_votes;
+DROP TABLE IF EXISTS public.poll_options;
+DROP TABLE IF EXISTS public.polls;
+
+ALTER TABLE IF EXISTS public.posts
+    DROP COLUMN IF EXISTS kind;
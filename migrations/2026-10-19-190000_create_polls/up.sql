@@ -0,0 +1,70 @@
+-- Your SQL goes here
+
+ALTER TABLE IF EXISTS public.posts
+    ADD COLUMN kind character varying COLLATE pg_catalog."default" NOT NULL DEFAULT 'text'
+    CONSTRAINT posts_kind_check CHECK (kind IN ('text', 'poll'));
+
+CREATE TABLE IF NOT EXISTS public.polls
+(
+    id bigint NOT NULL GENERATED BY DEFAULT AS IDENTITY ( INCREMENT 1 START 1 MINVALUE 1 MAXVALUE 9223372036854775807 CACHE 1 ),
+    created_at timestamp with time zone NOT NULL DEFAULT now(),
+    post_id bigint NOT NULL,
+    closes_at timestamp with time zone NOT NULL,
+    CONSTRAINT polls_pkey PRIMARY KEY (id),
+    CONSTRAINT polls_unique_post UNIQUE (post_id)
+);
+
+ALTER TABLE IF EXISTS public.polls
+    ADD CONSTRAINT polls_post_id_fkey FOREIGN KEY (post_id)
+    REFERENCES public.posts (id) MATCH SIMPLE
+    ON UPDATE NO ACTION
+    ON DELETE CASCADE;
+
+CREATE TABLE IF NOT EXISTS public.poll_options
+(
+    id bigint NOT NULL GENERATED BY DEFAULT AS IDENTITY ( INCREMENT 1 START 1 MINVALUE 1 MAXVALUE 9223372036854775807 CACHE 1 ),
+    created_at timestamp with time zone NOT NULL DEFAULT now(),
+    poll_id bigint NOT NULL,
+    "position" integer NOT NULL,
+    label character varying(100) COLLATE pg_catalog."default" NOT NULL,
+    CONSTRAINT poll_options_pkey PRIMARY KEY (id),
+    CONSTRAINT poll_options_unique_poll_position UNIQUE (poll_id, "position")
+);
+
+ALTER TABLE IF EXISTS public.poll_options
+    ADD CONSTRAINT poll_options_poll_id_fkey FOREIGN KEY (poll_id)
+    REFERENCES public.polls (id) MATCH SIMPLE
+    ON UPDATE NO ACTION
+    ON DELETE CASCADE;
+
+CREATE TABLE IF NOT EXISTS public.poll_votes
+(
+    id bigint NOT NULL GENERATED BY DEFAULT AS IDENTITY ( INCREMENT 1 START 1 MINVALUE 1 MAXVALUE 9223372036854775807 CACHE 1 ),
+    created_at timestamp with time zone NOT NULL DEFAULT now(),
+    poll_id bigint NOT NULL,
+    poll_option_id bigint NOT NULL,
+    user_id bigint NOT NULL,
+    CONSTRAINT poll_votes_pkey PRIMARY KEY (id),
+    CONSTRAINT poll_votes_unique_poll_user UNIQUE (poll_id, user_id)
+);
+
+CREATE INDEX IF NOT EXISTS poll_votes_poll_option_id_idx
+    ON public.poll_votes USING btree (poll_option_id);
+
+ALTER TABLE IF EXISTS public.poll_votes
+    ADD CONSTRAINT poll_votes_poll_id_fkey FOREIGN KEY (poll_id)
+    REFERENCES public.polls (id) MATCH SIMPLE
+    ON UPDATE NO ACTION
+    ON DELETE CASCADE;
+
+ALTER TABLE IF EXISTS public.poll_votes
+    ADD CONSTRAINT poll_votes_poll_option_id_fkey FOREIGN KEY (poll_option_id)
+    REFERENCES public.poll_options (id) MATCH SIMPLE
+    ON UPDATE NO ACTION
+    ON DELETE CASCADE;
+
+ALTER TABLE IF EXISTS public.poll_votes
+    ADD CONSTRAINT poll_votes_user_id_fkey FOREIGN KEY (user_id)
+    REFERENCES public.users (id) MATCH SIMPLE
+    ON UPDATE NO ACTION
+    ON DELETE CASCADE;
@@ -0,0 +1,13 @@
+-- This file should undo anything in `up.sql`
+
+ALTER TABLE IF EXISTS public.users
+    DROP COLUMN IF EXISTS suspended_at;
+
+ALTER TABLE IF EXISTS public.posts
+    DROP COLUMN IF EXISTS hidden_at;
+
+DROP TABLE IF EXISTS public.moderation_actions;
+
+DROP TABLE IF EXISTS public.reports;
+
+DROP TABLE IF EXISTS public.moderators;
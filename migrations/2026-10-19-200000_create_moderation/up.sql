@@ -0,0 +1,114 @@
+-- Your SQL goes here
+
+-- Users who can work the report queue. Granted by hand; there is no endpoint for it.
+CREATE TABLE IF NOT EXISTS public.moderators
+(
+    id bigint NOT NULL GENERATED BY DEFAULT AS IDENTITY ( INCREMENT 1 START 1 MINVALUE 1 MAXVALUE 9223372036854775807 CACHE 1 ),
+    created_at timestamp with time zone NOT NULL DEFAULT now(),
+    user_id bigint NOT NULL,
+    CONSTRAINT moderators_pkey PRIMARY KEY (id),
+    CONSTRAINT moderators_unique_user UNIQUE (user_id)
+);
+
+ALTER TABLE IF EXISTS public.moderators
+    ADD CONSTRAINT moderators_user_id_fkey FOREIGN KEY (user_id)
+    REFERENCES public.users (id) MATCH SIMPLE
+    ON UPDATE NO ACTION
+    ON DELETE CASCADE;
+
+CREATE TABLE IF NOT EXISTS public.reports
+(
+    id bigint NOT NULL GENERATED BY DEFAULT AS IDENTITY ( INCREMENT 1 START 1 MINVALUE 1 MAXVALUE 9223372036854775807 CACHE 1 ),
+    created_at timestamp with time zone NOT NULL DEFAULT now(),
+    reporter_id bigint NOT NULL,
+    target_type character varying COLLATE pg_catalog."default" NOT NULL
+        CONSTRAINT reports_target_type_check CHECK (target_type IN ('post', 'user')),
+    target_post_id bigint,
+    target_user_id bigint,
+    reason character varying COLLATE pg_catalog."default" NOT NULL
+        CONSTRAINT reports_reason_check CHECK (reason IN ('spam', 'harassment', 'hate', 'violence', 'nudity', 'misinformation', 'other')),
+    details character varying(1000) COLLATE pg_catalog."default",
+    status character varying COLLATE pg_catalog."default" NOT NULL DEFAULT 'open'
+        CONSTRAINT reports_status_check CHECK (status IN ('open', 'actioned', 'dismissed')),
+    resolved_by bigint,
+    resolved_at timestamp with time zone,
+    CONSTRAINT reports_pkey PRIMARY KEY (id),
+    CONSTRAINT reports_target_check CHECK (
+        (target_type = 'post' AND target_post_id IS NOT NULL AND target_user_id IS NULL) OR
+        (target_type = 'user' AND target_user_id IS NOT NULL AND target_post_id IS NULL)
+    )
+);
+
+ALTER TABLE IF EXISTS public.reports
+    ADD CONSTRAINT reports_reporter_id_fkey FOREIGN KEY (reporter_id)
+    REFERENCES public.users (id) MATCH SIMPLE
+    ON UPDATE NO ACTION
+    ON DELETE CASCADE;
+
+ALTER TABLE IF EXISTS public.reports
+    ADD CONSTRAINT reports_target_post_id_fkey FOREIGN KEY (target_post_id)
+    REFERENCES public.posts (id) MATCH SIMPLE
+    ON UPDATE NO ACTION
+    ON DELETE CASCADE;
+
+ALTER TABLE IF EXISTS public.reports
+    ADD CONSTRAINT reports_target_user_id_fkey FOREIGN KEY (target_user_id)
+    REFERENCES public.users (id) MATCH SIMPLE
+    ON UPDATE NO ACTION
+    ON DELETE CASCADE;
+
+ALTER TABLE IF EXISTS public.reports
+    ADD CONSTRAINT reports_resolved_by_fkey FOREIGN KEY (resolved_by)
+    REFERENCES public.users (id) MATCH SIMPLE
+    ON UPDATE NO ACTION
+    ON DELETE SET NULL;
+
+CREATE INDEX IF NOT EXISTS reports_status_created_at_idx
+    ON public.reports USING btree (status, created_at DESC, id DESC);
+
+-- Audit trail of everything moderators do.
+CREATE TABLE IF NOT EXISTS public.moderation_actions
+(
+    id bigint NOT NULL GENERATED BY DEFAULT AS IDENTITY ( INCREMENT 1 START 1 MINVALUE 1 MAXVALUE 9223372036854775807 CACHE 1 ),
+    created_at timestamp with time zone NOT NULL DEFAULT now(),
+    moderator_id bigint,
+    action character varying COLLATE pg_catalog."default" NOT NULL
+        CONSTRAINT moderation_actions_action_check CHECK (action IN ('hide_post', 'unhide_post', 'suspend_user', 'unsuspend_user', 'action_report', 'dismiss_report')),
+    report_id bigint,
+    target_post_id bigint,
+    target_user_id bigint,
+    note character varying(1000) COLLATE pg_catalog."default",
+    CONSTRAINT moderation_actions_pkey PRIMARY KEY (id)
+);
+
+-- Rows outlive what they point at so the trail stays complete.
+ALTER TABLE IF EXISTS public.moderation_actions
+    ADD CONSTRAINT moderation_actions_moderator_id_fkey FOREIGN KEY (moderator_id)
+    REFERENCES public.users (id) MATCH SIMPLE
+    ON UPDATE NO ACTION
+    ON DELETE SET NULL;
+
+ALTER TABLE IF EXISTS public.moderation_actions
+    ADD CONSTRAINT moderation_actions_report_id_fkey FOREIGN KEY (report_id)
+    REFERENCES public.reports (id) MATCH SIMPLE
+    ON UPDATE NO ACTION
+    ON DELETE SET NULL;
+
+ALTER TABLE IF EXISTS public.moderation_actions
+    ADD CONSTRAINT moderation_actions_target_post_id_fkey FOREIGN KEY (target_post_id)
+    REFERENCES public.posts (id) MATCH SIMPLE
+    ON UPDATE NO ACTION
+    ON DELETE SET NULL;
+
+ALTER TABLE IF EXISTS public.moderation_actions
+    ADD CONSTRAINT moderation_actions_target_user_id_fkey FOREIGN KEY (target_user_id)
+    REFERENCES public.users (id) MATCH SIMPLE
+    ON UPDATE NO ACTION
+    ON DELETE SET NULL;
+
+-- Hidden posts and suspended users' posts are left out of every listing.
+ALTER TABLE IF EXISTS public.posts
+    ADD COLUMN hidden_at timestamp with time zone;
+
+ALTER TABLE IF EXISTS public.users
+    ADD COLUMN suspended_at timestamp with time zone;
@@ -0,0 +1,16 @@
+-- This file should undo anything in `up.sql`
+
+DELETE FROM public.moderation_actions WHERE action IN ('approve_post', 'reject_post');
+
+ALTER TABLE IF EXISTS public.moderation_actions
+    DROP CONSTRAINT IF EXISTS moderation_actions_action_check,
+    ADD CONSTRAINT moderation_actions_action_check CHECK (action IN ('hide_post', 'unhide_post', 'suspend_user', 'unsuspend_user', 'action_report', 'dismiss_report'));
+
+DROP INDEX IF EXISTS public.posts_held_created_at_idx;
+
+UPDATE public.posts SET status = 'draft' WHERE status = 'held';
+
+ALTER TABLE IF EXISTS public.posts
+    DROP COLUMN IF EXISTS held_reason,
+    DROP CONSTRAINT IF EXISTS posts_status_check,
+    ADD CONSTRAINT posts_status_check CHECK (status IN ('draft', 'scheduled', 'published'));
@@ -0,0 +1,15 @@
+-- Your SQL goes here
+
+-- Posts the content filter holds back wait in `held` until a moderator decides.
+ALTER TABLE IF EXISTS public.posts
+    DROP CONSTRAINT IF EXISTS posts_status_check,
+    ADD CONSTRAINT posts_status_check CHECK (status IN ('draft', 'scheduled', 'published', 'held')),
+    ADD COLUMN held_reason character varying COLLATE pg_catalog."default";
+
+CREATE INDEX IF NOT EXISTS posts_held_created_at_idx
+    ON public.posts USING btree (created_at, id)
+    WHERE status = 'held';
+
+ALTER TABLE IF EXISTS public.moderation_actions
+    DROP CONSTRAINT IF EXISTS moderation_actions_action_check,
+    ADD CONSTRAINT moderation_actions_action_check CHECK (action IN ('hide_post', 'unhide_post', 'suspend_user', 'unsuspend_user', 'action_report', 'dismiss_report', 'approve_post', 'reject_post'));
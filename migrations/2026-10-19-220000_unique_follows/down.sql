@@ -0,0 +1,8 @@
+-- This file should undo anything in `up.sql`
+
+CREATE INDEX IF NOT EXISTS follows_following_user_id_idx
+    ON public.follows USING btree (following_user_id);
+
+ALTER TABLE IF EXISTS public.follows
+    DROP CONSTRAINT IF EXISTS follows_not_self,
+    DROP CONSTRAINT IF EXISTS follows_unique_following_followed;
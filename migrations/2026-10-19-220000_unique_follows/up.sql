@@ -0,0 +1,17 @@
+-- Your SQL goes here
+
+-- Repeated follow requests used to insert duplicate rows; keep the oldest of each.
+DELETE FROM public.follows f
+    USING public.follows earlier
+    WHERE f.following_user_id = earlier.following_user_id
+      AND f.followed_user_id = earlier.followed_user_id
+      AND (f.created_at, f.id) > (earlier.created_at, earlier.id);
+
+DELETE FROM public.follows WHERE following_user_id = followed_user_id;
+
+ALTER TABLE IF EXISTS public.follows
+    ADD CONSTRAINT follows_unique_following_followed UNIQUE (following_user_id, followed_user_id),
+    ADD CONSTRAINT follows_not_self CHECK (following_user_id <> followed_user_id);
+
+-- The unique index starts with following_user_id and covers the same lookups.
+DROP INDEX IF EXISTS public.follows_following_user_id_idx;
@@ -0,0 +1,6 @@
+-- This file should undo anything in `up.sql`
+
+DROP TABLE IF EXISTS public.follow_requests;
+
+ALTER TABLE IF EXISTS public.users
+    DROP COLUMN IF EXISTS is_private;
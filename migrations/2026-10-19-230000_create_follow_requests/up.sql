@@ -0,0 +1,31 @@
+-- Your SQL goes here
+
+-- Followers of a private account need its approval.
+ALTER TABLE IF EXISTS public.users
+    ADD COLUMN is_private boolean NOT NULL DEFAULT false;
+
+CREATE TABLE IF NOT EXISTS public.follow_requests
+(
+    id bigint NOT NULL GENERATED BY DEFAULT AS IDENTITY ( INCREMENT 1 START 1 MINVALUE 1 MAXVALUE 9223372036854775807 CACHE 1 ),
+    created_at timestamp with time zone NOT NULL DEFAULT now(),
+    requester_id bigint NOT NULL,
+    target_user_id bigint NOT NULL,
+    CONSTRAINT follow_requests_pkey PRIMARY KEY (id),
+    CONSTRAINT follow_requests_unique_requester_target UNIQUE (requester_id, target_user_id),
+    CONSTRAINT follow_requests_not_self CHECK (requester_id <> target_user_id)
+);
+
+ALTER TABLE IF EXISTS public.follow_requests
+    ADD CONSTRAINT follow_requests_requester_id_fkey FOREIGN KEY (requester_id)
+    REFERENCES public.users (id) MATCH SIMPLE
+    ON UPDATE NO ACTION
+    ON DELETE CASCADE;
+
+ALTER TABLE IF EXISTS public.follow_requests
+    ADD CONSTRAINT follow_requests_target_user_id_fkey FOREIGN KEY (target_user_id)
+    REFERENCES public.users (id) MATCH SIMPLE
+    ON UPDATE NO ACTION
+    ON DELETE CASCADE;
+
+CREATE INDEX IF NOT EXISTS follow_requests_target_user_id_idx
+    ON public.follow_requests USING btree (target_user_id, created_at);
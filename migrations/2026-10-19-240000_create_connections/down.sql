@@ -0,0 +1,5 @@
+-- This file should undo anything in `up.sql`
+
+DROP TABLE IF EXISTS public.connections;
+
+DROP TABLE IF EXISTS public.connection_invitations;
@@ -0,0 +1,52 @@
+-- Your SQL goes here
+
+CREATE TABLE IF NOT EXISTS public.connection_invitations
+(
+    id bigint NOT NULL GENERATED BY DEFAULT AS IDENTITY ( INCREMENT 1 START 1 MINVALUE 1 MAXVALUE 9223372036854775807 CACHE 1 ),
+    created_at timestamp with time zone NOT NULL DEFAULT now(),
+    sender_id bigint NOT NULL,
+    recipient_id bigint NOT NULL,
+    note character varying(300) COLLATE pg_catalog."default",
+    CONSTRAINT connection_invitations_pkey PRIMARY KEY (id),
+    CONSTRAINT connection_invitations_unique_sender_recipient UNIQUE (sender_id, recipient_id),
+    CONSTRAINT connection_invitations_not_self CHECK (sender_id <> recipient_id)
+);
+
+ALTER TABLE IF EXISTS public.connection_invitations
+    ADD CONSTRAINT connection_invitations_sender_id_fkey FOREIGN KEY (sender_id)
+    REFERENCES public.users (id) MATCH SIMPLE
+    ON UPDATE NO ACTION
+    ON DELETE CASCADE;
+
+ALTER TABLE IF EXISTS public.connection_invitations
+    ADD CONSTRAINT connection_invitations_recipient_id_fkey FOREIGN KEY (recipient_id)
+    REFERENCES public.users (id) MATCH SIMPLE
+    ON UPDATE NO ACTION
+    ON DELETE CASCADE;
+
+CREATE INDEX IF NOT EXISTS connection_invitations_recipient_id_idx
+    ON public.connection_invitations USING btree (recipient_id, created_at);
+
+-- Each connection is stored twice, once from either side, so lookups only need user_id.
+CREATE TABLE IF NOT EXISTS public.connections
+(
+    id bigint NOT NULL GENERATED BY DEFAULT AS IDENTITY ( INCREMENT 1 START 1 MINVALUE 1 MAXVALUE 9223372036854775807 CACHE 1 ),
+    created_at timestamp with time zone NOT NULL DEFAULT now(),
+    user_id bigint NOT NULL,
+    connected_user_id bigint NOT NULL,
+    CONSTRAINT connections_pkey PRIMARY KEY (id),
+    CONSTRAINT connections_unique_user_connected UNIQUE (user_id, connected_user_id),
+    CONSTRAINT connections_not_self CHECK (user_id <> connected_user_id)
+);
+
+ALTER TABLE IF EXISTS public.connections
+    ADD CONSTRAINT connections_user_id_fkey FOREIGN KEY (user_id)
+    REFERENCES public.users (id) MATCH SIMPLE
+    ON UPDATE NO ACTION
+    ON DELETE CASCADE;
+
+ALTER TABLE IF EXISTS public.connections
+    ADD CONSTRAINT connections_connected_user_id_fkey FOREIGN KEY (connected_user_id)
+    REFERENCES public.users (id) MATCH SIMPLE
+    ON UPDATE NO ACTION
+    ON DELETE CASCADE;
@@ -0,0 +1,5 @@
+-- This file should undo anything in `up.sql`
+
+DROP TABLE IF EXISTS public.user_mutes;
+
+DROP TABLE IF EXISTS public.user_blocks;
@@ -0,0 +1,51 @@
+-- Your SQL goes here
+
+CREATE TABLE IF NOT EXISTS public.user_blocks
+(
+    id bigint NOT NULL GENERATED BY DEFAULT AS IDENTITY ( INCREMENT 1 START 1 MINVALUE 1 MAXVALUE 9223372036854775807 CACHE 1 ),
+    created_at timestamp with time zone NOT NULL DEFAULT now(),
+    blocker_id bigint NOT NULL,
+    blocked_id bigint NOT NULL,
+    CONSTRAINT user_blocks_pkey PRIMARY KEY (id),
+    CONSTRAINT user_blocks_unique_blocker_blocked UNIQUE (blocker_id, blocked_id),
+    CONSTRAINT user_blocks_not_self CHECK (blocker_id <> blocked_id)
+);
+
+ALTER TABLE IF EXISTS public.user_blocks
+    ADD CONSTRAINT user_blocks_blocker_id_fkey FOREIGN KEY (blocker_id)
+    REFERENCES public.users (id) MATCH SIMPLE
+    ON UPDATE NO ACTION
+    ON DELETE CASCADE;
+
+ALTER TABLE IF EXISTS public.user_blocks
+    ADD CONSTRAINT user_blocks_blocked_id_fkey FOREIGN KEY (blocked_id)
+    REFERENCES public.users (id) MATCH SIMPLE
+    ON UPDATE NO ACTION
+    ON DELETE CASCADE;
+
+-- Blocks are checked from both sides.
+CREATE INDEX IF NOT EXISTS user_blocks_blocked_id_idx
+    ON public.user_blocks USING btree (blocked_id);
+
+CREATE TABLE IF NOT EXISTS public.user_mutes
+(
+    id bigint NOT NULL GENERATED BY DEFAULT AS IDENTITY ( INCREMENT 1 START 1 MINVALUE 1 MAXVALUE 9223372036854775807 CACHE 1 ),
+    created_at timestamp with time zone NOT NULL DEFAULT now(),
+    muter_id bigint NOT NULL,
+    muted_id bigint NOT NULL,
+    CONSTRAINT user_mutes_pkey PRIMARY KEY (id),
+    CONSTRAINT user_mutes_unique_muter_muted UNIQUE (muter_id, muted_id),
+    CONSTRAINT user_mutes_not_self CHECK (muter_id <> muted_id)
+);
+
+ALTER TABLE IF EXISTS public.user_mutes
+    ADD CONSTRAINT user_mutes_muter_id_fkey FOREIGN KEY (muter_id)
+    REFERENCES public.users (id) MATCH SIMPLE
+    ON UPDATE NO ACTION
+    ON DELETE CASCADE;
+
+ALTER TABLE IF EXISTS public.user_mutes
+    ADD CONSTRAINT user_mutes_muted_id_fkey FOREIGN KEY (muted_id)
+    REFERENCES public.users (id) MATCH SIMPLE
+    ON UPDATE NO ACTION
+    ON DELETE CASCADE;
@@ -0,0 +1,16 @@
+-- This file should undo anything in `up.sql`
+
+DELETE FROM public.pinned_posts WHERE company_id IS NOT NULL;
+
+ALTER TABLE IF EXISTS public.pinned_posts
+    DROP COLUMN IF EXISTS company_id;
+
+ALTER TABLE IF EXISTS public.pinned_posts
+    ALTER COLUMN user_id SET NOT NULL;
+
+DROP INDEX IF EXISTS public.posts_company_id_created_at_idx;
+
+ALTER TABLE IF EXISTS public.posts
+    DROP COLUMN IF EXISTS company_id;
+
+DROP TABLE IF EXISTS public.company_admins;
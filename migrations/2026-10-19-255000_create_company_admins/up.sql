@@ -0,0 +1,56 @@
+-- Your SQL goes here
+
+CREATE TABLE IF NOT EXISTS public.company_admins
+(
+    id bigint NOT NULL GENERATED BY DEFAULT AS IDENTITY ( INCREMENT 1 START 1 MINVALUE 1 MAXVALUE 9223372036854775807 CACHE 1 ),
+    created_at timestamp with time zone NOT NULL DEFAULT now(),
+    company_id bigint NOT NULL,
+    user_id bigint NOT NULL,
+    CONSTRAINT company_admins_pkey PRIMARY KEY (id),
+    CONSTRAINT company_admins_unique_company_user UNIQUE (company_id, user_id)
+);
+
+ALTER TABLE IF EXISTS public.company_admins
+    ADD CONSTRAINT company_admins_company_id_fkey FOREIGN KEY (company_id)
+    REFERENCES public.company (id) MATCH SIMPLE
+    ON UPDATE NO ACTION
+    ON DELETE CASCADE;
+
+ALTER TABLE IF EXISTS public.company_admins
+    ADD CONSTRAINT company_admins_user_id_fkey FOREIGN KEY (user_id)
+    REFERENCES public.users (id) MATCH SIMPLE
+    ON UPDATE NO ACTION
+    ON DELETE CASCADE;
+
+-- Posts published by a company admin on behalf of the company.
+ALTER TABLE IF EXISTS public.posts
+    ADD COLUMN company_id bigint;
+
+ALTER TABLE IF EXISTS public.posts
+    ADD CONSTRAINT posts_company_id_fkey FOREIGN KEY (company_id)
+    REFERENCES public.company (id) MATCH SIMPLE
+    ON UPDATE NO ACTION
+    ON DELETE SET NULL;
+
+CREATE INDEX IF NOT EXISTS posts_company_id_created_at_idx
+    ON public.posts USING btree (company_id, created_at DESC, id DESC)
+    WHERE company_id IS NOT NULL;
+
+-- A pin now belongs either to a user profile or to a company profile.
+ALTER TABLE IF EXISTS public.pinned_posts
+    ALTER COLUMN user_id DROP NOT NULL;
+
+ALTER TABLE IF EXISTS public.pinned_posts
+    ADD COLUMN company_id bigint;
+
+ALTER TABLE IF EXISTS public.pinned_posts
+    ADD CONSTRAINT pinned_posts_unique_company_post UNIQUE (company_id, post_id);
+
+ALTER TABLE IF EXISTS public.pinned_posts
+    ADD CONSTRAINT pinned_posts_owner_check CHECK ((user_id IS NULL) <> (company_id IS NULL));
+
+ALTER TABLE IF EXISTS public.pinned_posts
+    ADD CONSTRAINT pinned_posts_company_id_fkey FOREIGN KEY (company_id)
+    REFERENCES public.company (id) MATCH SIMPLE
+    ON UPDATE NO ACTION
+    ON DELETE CASCADE;
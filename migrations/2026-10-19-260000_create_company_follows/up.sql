@@ -0,0 +1,26 @@
+-- Your SQL goes here
+
+CREATE TABLE IF NOT EXISTS public.company_follows
+(
+    id bigint NOT NULL GENERATED BY DEFAULT AS IDENTITY ( INCREMENT 1 START 1 MINVALUE 1 MAXVALUE 9223372036854775807 CACHE 1 ),
+    created_at timestamp with time zone NOT NULL DEFAULT now(),
+    user_id bigint NOT NULL,
+    company_id bigint NOT NULL,
+    CONSTRAINT company_follows_pkey PRIMARY KEY (id),
+    CONSTRAINT company_follows_unique_user_company UNIQUE (user_id, company_id)
+);
+
+ALTER TABLE IF EXISTS public.company_follows
+    ADD CONSTRAINT company_follows_user_id_fkey FOREIGN KEY (user_id)
+    REFERENCES public.users (id) MATCH SIMPLE
+    ON UPDATE NO ACTION
+    ON DELETE CASCADE;
+
+ALTER TABLE IF EXISTS public.company_follows
+    ADD CONSTRAINT company_follows_company_id_fkey FOREIGN KEY (company_id)
+    REFERENCES public.company (id) MATCH SIMPLE
+    ON UPDATE NO ACTION
+    ON DELETE CASCADE;
+
+CREATE INDEX IF NOT EXISTS company_follows_company_id_idx
+    ON public.company_follows USING btree (company_id);
@@ -0,0 +1,18 @@
+-- This file should undo anything in `up.sql`
+
+DROP TRIGGER IF EXISTS posts_count ON public.posts;
+DROP TRIGGER IF EXISTS company_follows_count ON public.company_follows;
+DROP TRIGGER IF EXISTS follows_count ON public.follows;
+
+DROP FUNCTION IF EXISTS public.count_posts();
+DROP FUNCTION IF EXISTS public.count_company_follows();
+DROP FUNCTION IF EXISTS public.count_follows();
+
+ALTER TABLE IF EXISTS public.company
+    DROP COLUMN IF EXISTS post_count,
+    DROP COLUMN IF EXISTS follower_count;
+
+ALTER TABLE IF EXISTS public.users
+    DROP COLUMN IF EXISTS post_count,
+    DROP COLUMN IF EXISTS following_count,
+    DROP COLUMN IF EXISTS follower_count;
@@ -0,0 +1,70 @@
+-- Your SQL goes here
+
+-- Counters kept up to date by the triggers below; `skripsi reconcile-counters`
+-- recomputes them if they ever drift. Posts only count once published.
+ALTER TABLE IF EXISTS public.users
+    ADD COLUMN follower_count bigint NOT NULL DEFAULT 0,
+    ADD COLUMN following_count bigint NOT NULL DEFAULT 0,
+    ADD COLUMN post_count bigint NOT NULL DEFAULT 0;
+
+ALTER TABLE IF EXISTS public.company
+    ADD COLUMN follower_count bigint NOT NULL DEFAULT 0,
+    ADD COLUMN post_count bigint NOT NULL DEFAULT 0;
+
+UPDATE public.users u SET
+    follower_count = (SELECT count(*) FROM public.follows f WHERE f.followed_user_id = u.id),
+    following_count = (SELECT count(*) FROM public.follows f WHERE f.following_user_id = u.id),
+    post_count = (SELECT count(*) FROM public.posts p WHERE p.user_id = u.id AND p.status = 'published');
+
+UPDATE public.company c SET
+    follower_count = (SELECT count(*) FROM public.company_follows f WHERE f.company_id = c.id),
+    post_count = (SELECT count(*) FROM public.posts p WHERE p.company_id = c.id AND p.status = 'published');
+
+CREATE OR REPLACE FUNCTION public.count_follows() RETURNS trigger AS $$
+BEGIN
+    IF TG_OP = 'INSERT' THEN
+        UPDATE public.users SET follower_count = follower_count + 1 WHERE id = NEW.followed_user_id;
+        UPDATE public.users SET following_count = following_count + 1 WHERE id = NEW.following_user_id;
+    ELSIF TG_OP = 'DELETE' THEN
+        UPDATE public.users SET follower_count = follower_count - 1 WHERE id = OLD.followed_user_id;
+        UPDATE public.users SET following_count = following_count - 1 WHERE id = OLD.following_user_id;
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER follows_count AFTER INSERT OR DELETE ON public.follows
+    FOR EACH ROW EXECUTE PROCEDURE public.count_follows();
+
+CREATE OR REPLACE FUNCTION public.count_company_follows() RETURNS trigger AS $$
+BEGIN
+    IF TG_OP = 'INSERT' THEN
+        UPDATE public.company SET follower_count = follower_count + 1 WHERE id = NEW.company_id;
+    ELSIF TG_OP = 'DELETE' THEN
+        UPDATE public.company SET follower_count = follower_count - 1 WHERE id = OLD.company_id;
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER company_follows_count AFTER INSERT OR DELETE ON public.company_follows
+    FOR EACH ROW EXECUTE PROCEDURE public.count_company_follows();
+
+-- Takes the old row out of the counts and puts the new one in, so publishing,
+-- unpublishing and deleting are all covered.
+CREATE OR REPLACE FUNCTION public.count_posts() RETURNS trigger AS $$
+BEGIN
+    IF TG_OP IN ('UPDATE', 'DELETE') AND OLD.status = 'published' THEN
+        UPDATE public.users SET post_count = post_count - 1 WHERE id = OLD.user_id;
+        UPDATE public.company SET post_count = post_count - 1 WHERE id = OLD.company_id;
+    END IF;
+    IF TG_OP IN ('INSERT', 'UPDATE') AND NEW.status = 'published' THEN
+        UPDATE public.users SET post_count = post_count + 1 WHERE id = NEW.user_id;
+        UPDATE public.company SET post_count = post_count + 1 WHERE id = NEW.company_id;
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER posts_count AFTER INSERT OR DELETE OR UPDATE OF status, user_id, company_id ON public.posts
+    FOR EACH ROW EXECUTE PROCEDURE public.count_posts();
@@ -0,0 +1,5 @@
+-- This file should undo anything in `up.sql`
+
+DROP TRIGGER IF EXISTS follows_log ON public.follows;
+DROP FUNCTION IF EXISTS public.log_follow_events();
+DROP TABLE IF EXISTS public.follow_events;
@@ -0,0 +1,55 @@
+-- Your SQL goes here
+
+-- Append-only log of follows and unfollows, written by the trigger below so
+-- every path that removes a follow (unfollowing, blocking, disconnecting) is
+-- recorded. Unfollows before this migration were never kept; existing follows
+-- are backfilled as follow events at the time they were made.
+CREATE TABLE IF NOT EXISTS public.follow_events
+(
+    id bigint NOT NULL GENERATED BY DEFAULT AS IDENTITY ( INCREMENT 1 START 1 MINVALUE 1 MAXVALUE 9223372036854775807 CACHE 1 ),
+    created_at timestamp with time zone NOT NULL DEFAULT now(),
+    follower_id bigint NOT NULL,
+    followed_user_id bigint NOT NULL,
+    kind character varying COLLATE pg_catalog."default" NOT NULL,
+    CONSTRAINT follow_events_pkey PRIMARY KEY (id),
+    CONSTRAINT follow_events_kind_check CHECK (kind IN ('follow', 'unfollow'))
+);
+
+ALTER TABLE IF EXISTS public.follow_events
+    ADD CONSTRAINT follow_events_follower_id_fkey FOREIGN KEY (follower_id)
+    REFERENCES public.users (id) MATCH SIMPLE
+    ON UPDATE NO ACTION
+    ON DELETE CASCADE;
+
+ALTER TABLE IF EXISTS public.follow_events
+    ADD CONSTRAINT follow_events_followed_user_id_fkey FOREIGN KEY (followed_user_id)
+    REFERENCES public.users (id) MATCH SIMPLE
+    ON UPDATE NO ACTION
+    ON DELETE CASCADE;
+
+CREATE INDEX IF NOT EXISTS follow_events_followed_user_id_created_at_idx
+    ON public.follow_events USING btree (followed_user_id, created_at);
+
+CREATE INDEX IF NOT EXISTS follow_events_follower_id_idx
+    ON public.follow_events USING btree (follower_id);
+
+INSERT INTO public.follow_events (created_at, follower_id, followed_user_id, kind)
+    SELECT created_at, following_user_id, followed_user_id, 'follow'
+    FROM public.follows
+    ORDER BY created_at, id;
+
+CREATE OR REPLACE FUNCTION public.log_follow_events() RETURNS trigger AS $$
+BEGIN
+    IF TG_OP = 'INSERT' THEN
+        INSERT INTO public.follow_events (created_at, follower_id, followed_user_id, kind)
+            VALUES (NEW.created_at, NEW.following_user_id, NEW.followed_user_id, 'follow');
+    ELSIF TG_OP = 'DELETE' THEN
+        INSERT INTO public.follow_events (follower_id, followed_user_id, kind)
+            VALUES (OLD.following_user_id, OLD.followed_user_id, 'unfollow');
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER follows_log AFTER INSERT OR DELETE ON public.follows
+    FOR EACH ROW EXECUTE PROCEDURE public.log_follow_events();
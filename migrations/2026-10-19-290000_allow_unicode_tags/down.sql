@@ -0,0 +1,9 @@
+-- This file should undo anything in `up.sql`
+
+DELETE FROM public.tags WHERE name !~ '^[a-z0-9_]+$';
+
+ALTER TABLE IF EXISTS public.tags
+    DROP CONSTRAINT IF EXISTS tags_name_check;
+
+ALTER TABLE IF EXISTS public.tags
+    ADD CONSTRAINT tags_name_check CHECK (name ~ '^[a-z0-9_]+$');
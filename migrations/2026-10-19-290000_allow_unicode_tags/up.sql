@@ -0,0 +1,11 @@
+-- Your SQL goes here
+
+-- Hashtags may use letters and digits of any script (see `text::is_valid_tag`).
+-- Character classes only cover ASCII under the C locale, so the check is strict
+-- about ASCII (lowercase letters, digits and underscore) and leaves other
+-- characters to the application.
+ALTER TABLE IF EXISTS public.tags
+    DROP CONSTRAINT IF EXISTS tags_name_check;
+
+ALTER TABLE IF EXISTS public.tags
+    ADD CONSTRAINT tags_name_check CHECK (name ~ '^([a-z0-9_]|[^\x01-\x7f])+$' AND name !~ '^[0-9]+$');
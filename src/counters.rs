@@ -0,0 +1,43 @@
+use crate::db::DbPooled;
+use diesel::{Connection, QueryResult, RunQueryDsl};
+
+/// Each statement recomputes one table's counters and only touches rows where a
+/// stored value differs, so the number of updated rows is the amount of drift.
+const RECONCILE_STATEMENTS: [&str; 2] = [
+    "UPDATE users u SET \
+         follower_count = c.follower_count, \
+         following_count = c.following_count, \
+         post_count = c.post_count \
+     FROM (SELECT id, \
+               (SELECT count(*) FROM follows f WHERE f.followed_user_id = users.id) AS follower_count, \
+               (SELECT count(*) FROM follows f WHERE f.following_user_id = users.id) AS following_count, \
+               (SELECT count(*) FROM posts p WHERE p.user_id = users.id \
+                    AND p.status = 'published') AS post_count \
+           FROM users) c \
+     WHERE u.id = c.id AND (u.follower_count, u.following_count, u.post_count) \
+         IS DISTINCT FROM (c.follower_count, c.following_count, c.post_count)",
+    "UPDATE company co SET \
+         follower_count = c.follower_count, \
+         post_count = c.post_count \
+     FROM (SELECT id, \
+               (SELECT count(*) FROM company_follows f WHERE f.company_id = company.id) AS follower_count, \
+               (SELECT count(*) FROM posts p WHERE p.company_id = company.id \
+                    AND p.status = 'published') AS post_count \
+           FROM company) c \
+     WHERE co.id = c.id AND (co.follower_count, co.post_count) \
+         IS DISTINCT FROM (c.follower_count, c.post_count)",
+];
+
+/// Recomputes the follower, following and post counters of users and companies
+/// from the rows they count, returning how many users and companies were off.
+/// The triggers keep them in step; this is for repairing drift, e.g. after
+/// editing the database by hand.
+pub fn reconcile_counters(conn: &mut DbPooled) -> QueryResult<usize> {
+    conn.transaction(|conn| {
+        let mut fixed = 0;
+        for statement in RECONCILE_STATEMENTS {
+            fixed += diesel::sql_query(statement).execute(conn)?;
+        }
+        Ok(fixed)
+    })
+}
@@ -0,0 +1,482 @@
+use crate::{db::DbPooled, schema::posts, text};
+use actix_web::http::StatusCode;
+use chrono::{Duration, Utc};
+use diesel::{dsl::count_distinct, ExpressionMethods, QueryDsl, QueryResult, RunQueryDsl};
+use std::{env, fs, io};
+use unicode_normalization::{char::is_combining_mark, UnicodeNormalization};
+
+const DEFAULT_MAX_LINKS: usize = 5;
+const DEFAULT_RATE_LIMIT: i64 = 10;
+const DEFAULT_RATE_WINDOW_SECS: i64 = 10 * 60;
+/// A user posting the same thing again within this window is rejected.
+const DUPLICATE_WINDOW_HOURS: i64 = 24;
+/// How many of the user's recent posts are compared for duplicates.
+const DUPLICATE_LOOKBACK: i64 = 50;
+/// The same text posted by this many other users within the hour is held.
+const COPY_PASTE_USERS: i64 = 3;
+/// Short texts ("thanks!") are posted by many people and are never held as copies.
+const COPY_PASTE_MIN_LENGTH: usize = 20;
+
+/// What is being posted. `post_id` is set when an existing post is edited or
+/// published, so checks about posting volume can skip it.
+pub struct Submission<'a> {
+    pub user_id: i64,
+    pub post_id: Option<i64>,
+    pub body: &'a str,
+}
+
+#[derive(Debug)]
+pub struct Rejection {
+    pub status: StatusCode,
+    pub error_code: &'static str,
+    pub message: String,
+}
+
+#[derive(Debug)]
+pub enum Verdict {
+    Allow,
+    /// Keep the post out of sight until a moderator approves it.
+    Hold(String),
+    Reject(Rejection),
+}
+
+/// One step of the pipeline. Filters are independent of each other and may look
+/// at the database, e.g. for the user's earlier posts.
+pub trait ContentFilter: Send + Sync {
+    fn check(&self, conn: &mut DbPooled, submission: &Submission) -> QueryResult<Verdict>;
+}
+
+/// Runs every filter on a submission. The first rejection wins; otherwise the
+/// post is held if any filter asked for it, with all of their reasons.
+pub struct FilterPipeline {
+    filters: Vec<Box<dyn ContentFilter>>,
+}
+
+impl FilterPipeline {
+    pub fn new(filters: Vec<Box<dyn ContentFilter>>) -> Self {
+        FilterPipeline { filters }
+    }
+
+    /// The default pipeline, configured by:
+    /// - `BLOCKED_WORDS_FILE` / `HELD_WORDS_FILE`: words or phrases, one per line,
+    ///   that reject or hold a post (`#` starts a comment);
+    /// - `SPAM_MAX_LINKS` (default 5): posts with more links are held;
+    /// - `POST_RATE_LIMIT` posts (default 10) per `POST_RATE_WINDOW_SECS` (default 600).
+    pub fn from_env() -> io::Result<Self> {
+        let words = WordFilter::new(
+            read_word_list("BLOCKED_WORDS_FILE")?,
+            read_word_list("HELD_WORDS_FILE")?,
+        );
+        Ok(FilterPipeline::new(vec![
+            Box::new(RateFilter {
+                max_posts: env_number("POST_RATE_LIMIT", DEFAULT_RATE_LIMIT),
+                window: Duration::seconds(env_number(
+                    "POST_RATE_WINDOW_SECS",
+                    DEFAULT_RATE_WINDOW_SECS,
+                )),
+            }),
+            Box::new(words),
+            Box::new(LinkFilter {
+                max_links: env_number("SPAM_MAX_LINKS", DEFAULT_MAX_LINKS as i64) as usize,
+            }),
+            Box::new(DuplicateFilter),
+        ]))
+    }
+
+    pub fn run(&self, conn: &mut DbPooled, submission: &Submission) -> QueryResult<Verdict> {
+        combine(self.filters.iter().map(|f| f.check(conn, submission)))
+    }
+}
+
+/// Folds the filters' verdicts, stopping at the first rejection so later filters
+/// are not run.
+fn combine(verdicts: impl Iterator<Item = QueryResult<Verdict>>) -> QueryResult<Verdict> {
+    let mut held = vec![];
+    for verdict in verdicts {
+        match verdict? {
+            Verdict::Allow => {}
+            Verdict::Hold(reason) => held.push(reason),
+            reject => return Ok(reject),
+        }
+    }
+    if held.is_empty() {
+        Ok(Verdict::Allow)
+    } else {
+        Ok(Verdict::Hold(held.join("; ")))
+    }
+}
+
+fn env_number(key: &str, default: i64) -> i64 {
+    env::var(key)
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .filter(|v| *v > 0)
+        .unwrap_or(default)
+}
+
+fn read_word_list(key: &str) -> io::Result<Vec<String>> {
+    let path = match env::var(key) {
+        Ok(p) => p,
+        Err(_) => return Ok(vec![]),
+    };
+    Ok(fs::read_to_string(path)?
+        .lines()
+        .map(|l| l.split('#').next().unwrap_or("").trim().to_string())
+        .filter(|l| !l.is_empty())
+        .collect())
+}
+
+/// Symbols that stand in for letters inside a word.
+const LEET_SYMBOLS: [char; 4] = ['!', '|', '@', '$'];
+
+/// Folds text to what it looks like: lowercase, accents stripped and common
+/// leet-speak substitutions undone, so `Ŝp4m` and `sp@m` compare equal to `spam`.
+/// Substitutions only apply to tokens that also contain a letter, so numbers and
+/// prices like `$455` stay as they are, and symbols only count as letters inside
+/// a word, so `hello!` stays `hello`.
+pub fn normalize(s: &str) -> String {
+    let chars: Vec<char> = s
+        .nfkd()
+        .filter(|c| !is_combining_mark(*c))
+        .flat_map(char::to_lowercase)
+        .collect();
+    let in_token = |c: &char| c.is_alphanumeric() || LEET_SYMBOLS.contains(c);
+    let mut out = String::with_capacity(s.len());
+    let mut start = 0;
+    while start < chars.len() {
+        let end = match chars[start..].iter().position(|c| !in_token(c)) {
+            Some(0) => {
+                out.push(chars[start]);
+                start += 1;
+                continue;
+            }
+            Some(n) => start + n,
+            None => chars.len(),
+        };
+        let token = &chars[start..end];
+        if token.iter().any(|c| c.is_alphabetic()) {
+            out.extend(unleet(token));
+        } else {
+            out.extend(token);
+        }
+        start = end;
+    }
+    out
+}
+
+fn unleet(token: &[char]) -> impl Iterator<Item = char> + '_ {
+    let inside_word = |i: usize| {
+        i > 0
+            && token[i - 1].is_alphanumeric()
+            && token.get(i + 1).is_some_and(|c| c.is_alphanumeric())
+    };
+    token.iter().enumerate().map(move |(i, c)| match c {
+        '0' => 'o',
+        '1' => 'i',
+        '3' => 'e',
+        '4' => 'a',
+        '5' => 's',
+        '7' => 't',
+        '8' => 'b',
+        '9' => 'g',
+        '!' | '|' if inside_word(i) => 'i',
+        '@' if inside_word(i) => 'a',
+        '$' if inside_word(i) => 's',
+        c => *c,
+    })
+}
+
+/// Normalized words of `s`; everything that is not a letter or digit separates words.
+fn words(s: &str) -> Vec<String> {
+    normalize(s)
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Matches whole words and phrases from two lists. Words in the post are also
+/// tried with repeated letters squeezed, so `spaaam` still matches `spam`.
+pub struct WordFilter {
+    blocked: Vec<Vec<String>>,
+    held: Vec<Vec<String>>,
+}
+
+impl WordFilter {
+    pub fn new(blocked: Vec<String>, held: Vec<String>) -> Self {
+        let phrases = |list: Vec<String>| {
+            list.iter()
+                .map(|p| words(p))
+                .filter(|w| !w.is_empty())
+                .collect()
+        };
+        WordFilter {
+            blocked: phrases(blocked),
+            held: phrases(held),
+        }
+    }
+
+    fn find<'a>(
+        list: &'a [Vec<String>],
+        body: &[String],
+        squeezed: &[String],
+    ) -> Option<&'a [String]> {
+        list.iter()
+            .find(|phrase| {
+                [body, squeezed]
+                    .iter()
+                    .any(|words| words.windows(phrase.len()).any(|w| w == phrase.as_slice()))
+            })
+            .map(|p| p.as_slice())
+    }
+}
+
+fn squeeze(word: &str) -> String {
+    let mut out = String::with_capacity(word.len());
+    for c in word.chars() {
+        if !out.ends_with(c) {
+            out.push(c);
+        }
+    }
+    out
+}
+
+impl ContentFilter for WordFilter {
+    fn check(&self, _: &mut DbPooled, submission: &Submission) -> QueryResult<Verdict> {
+        let body = words(submission.body);
+        let squeezed: Vec<String> = body.iter().map(|w| squeeze(w)).collect();
+        if WordFilter::find(&self.blocked, &body, &squeezed).is_some() {
+            return Ok(Verdict::Reject(Rejection {
+                status: StatusCode::UNPROCESSABLE_ENTITY,
+                error_code: "blocked_content",
+                message: "Post contains words that are not allowed".to_string(),
+            }));
+        }
+        match WordFilter::find(&self.held, &body, &squeezed) {
+            Some(p) => Ok(Verdict::Hold(format!("contains \"{}\"", p.join(" ")))),
+            None => Ok(Verdict::Allow),
+        }
+    }
+}
+
+/// Holds posts with more links than people usually share at once.
+pub struct LinkFilter {
+    pub max_links: usize,
+}
+
+impl ContentFilter for LinkFilter {
+    fn check(&self, _: &mut DbPooled, submission: &Submission) -> QueryResult<Verdict> {
+        let links = text::extract_urls(submission.body).len();
+        if links > self.max_links {
+            return Ok(Verdict::Hold(format!("{} links", links)));
+        }
+        Ok(Verdict::Allow)
+    }
+}
+
+/// Rejects a user posting the same text twice, and holds text that several other
+/// users have just posted word for word.
+pub struct DuplicateFilter;
+
+impl ContentFilter for DuplicateFilter {
+    fn check(&self, conn: &mut DbPooled, submission: &Submission) -> QueryResult<Verdict> {
+        let body = words(submission.body);
+        if body.is_empty() {
+            return Ok(Verdict::Allow);
+        }
+        let mut own = posts::table
+            .filter(posts::user_id.eq(submission.user_id))
+            .filter(posts::created_at.ge(Utc::now() - Duration::hours(DUPLICATE_WINDOW_HOURS)))
+            .select(posts::body)
+            .order(posts::created_at.desc())
+            .limit(DUPLICATE_LOOKBACK)
+            .into_boxed();
+        if let Some(p) = submission.post_id {
+            own = own.filter(posts::id.ne(p));
+        }
+        if own.load::<String>(conn)?.iter().any(|b| words(b) == body) {
+            return Ok(Verdict::Reject(Rejection {
+                status: StatusCode::CONFLICT,
+                error_code: "duplicate_post",
+                message: "You have already posted this".to_string(),
+            }));
+        }
+
+        let trimmed = submission.body.trim();
+        if trimmed.chars().count() < COPY_PASTE_MIN_LENGTH {
+            return Ok(Verdict::Allow);
+        }
+        let copies = posts::table
+            .filter(posts::user_id.ne(submission.user_id))
+            .filter(posts::created_at.ge(Utc::now() - Duration::hours(1)))
+            .filter(posts::body.eq(trimmed))
+            .select(count_distinct(posts::user_id))
+            .first::<i64>(conn)?;
+        if copies >= COPY_PASTE_USERS {
+            return Ok(Verdict::Hold(format!(
+                "same text posted by {} other users",
+                copies
+            )));
+        }
+        Ok(Verdict::Allow)
+    }
+}
+
+/// Limits how many posts a user can create in a sliding window. Edits are not
+/// counted.
+pub struct RateFilter {
+    pub max_posts: i64,
+    pub window: Duration,
+}
+
+impl ContentFilter for RateFilter {
+    fn check(&self, conn: &mut DbPooled, submission: &Submission) -> QueryResult<Verdict> {
+        if submission.post_id.is_some() {
+            return Ok(Verdict::Allow);
+        }
+        let recent = posts::table
+            .filter(posts::user_id.eq(submission.user_id))
+            .filter(posts::created_at.ge(Utc::now() - self.window))
+            .count()
+            .get_result::<i64>(conn)?;
+        if recent >= self.max_posts {
+            return Ok(Verdict::Reject(Rejection {
+                status: StatusCode::TOO_MANY_REQUESTS,
+                error_code: "post_rate_limited",
+                message: "You are posting too often; try again later".to_string(),
+            }));
+        }
+        Ok(Verdict::Allow)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reject(error_code: &'static str) -> Verdict {
+        Verdict::Reject(Rejection {
+            status: StatusCode::UNPROCESSABLE_ENTITY,
+            error_code,
+            message: String::new(),
+        })
+    }
+
+    fn hold(reason: &str) -> Verdict {
+        Verdict::Hold(reason.to_string())
+    }
+
+    /// What the word filter decides about `body`: `Some(true)` if blocked,
+    /// `Some(false)` if held.
+    fn word_verdict(filter: &WordFilter, body: &str) -> Option<bool> {
+        let body = words(body);
+        let squeezed: Vec<String> = body.iter().map(|w| squeeze(w)).collect();
+        if WordFilter::find(&filter.blocked, &body, &squeezed).is_some() {
+            Some(true)
+        } else if WordFilter::find(&filter.held, &body, &squeezed).is_some() {
+            Some(false)
+        } else {
+            None
+        }
+    }
+
+    #[test]
+    fn normalizes() {
+        let cases = [
+            ("Spam", "spam"),
+            ("Ŝpäm", "spam"),
+            ("sp4m", "spam"),
+            ("sp@m", "spam"),
+            ("h3ll0!", "hello!"),
+            ("w|n a pr!ze", "win a prize"),
+            ("$ave", "$ave"),
+            ("I owe you $455", "i owe you $455"),
+            ("call 555 0199", "call 555 0199"),
+            ("2024: r3sults", "2024: results"),
+            ("ｓｐａｍ", "spam"),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(normalize(input), expected, "input: {:?}", input);
+        }
+    }
+
+    #[test]
+    fn splits_words() {
+        let cases: &[(&str, &[&str])] = &[
+            ("Hello, World!", &["hello", "world"]),
+            ("fr33 m0ney...now", &["free", "money", "now"]),
+            ("I owe you $455", &["i", "owe", "you", "455"]),
+            ("  ", &[]),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(words(input), *expected, "input: {:?}", input);
+        }
+    }
+
+    #[test]
+    fn squeezes_repeated_letters() {
+        let cases = [("spaaam", "spam"), ("boot", "bot"), ("a", "a"), ("", "")];
+        for (input, expected) in cases {
+            assert_eq!(squeeze(input), expected, "input: {:?}", input);
+        }
+    }
+
+    #[test]
+    fn finds_words_and_phrases() {
+        let filter = WordFilter::new(
+            vec!["spam".to_string(), "buy now".to_string()],
+            vec!["crypto".to_string(), "get rich quick".to_string()],
+        );
+        let cases = [
+            ("this is spam", Some(true)),
+            ("SP4M!", Some(true)),
+            ("spaaaam", Some(true)),
+            ("Buy   now, friends", Some(true)),
+            ("buy it now", None),
+            ("spammer", None),
+            ("crypto news", Some(false)),
+            ("cryptography", None),
+            ("G3t r1ch quick!", Some(false)),
+            ("I owe you $455", None),
+        ];
+        for (body, expected) in cases {
+            assert_eq!(word_verdict(&filter, body), expected, "body: {:?}", body);
+        }
+    }
+
+    #[test]
+    fn rejection_wins_over_holds() {
+        let cases = [
+            (vec![], None),
+            (vec![Verdict::Allow, Verdict::Allow], None),
+            (vec![hold("a"), Verdict::Allow, hold("b")], Some("a; b")),
+            (
+                vec![hold("a"), reject("first"), reject("second")],
+                Some("first"),
+            ),
+            (vec![reject("first"), hold("a")], Some("first")),
+        ];
+        for (verdicts, expected) in cases {
+            let verdict = combine(verdicts.into_iter().map(Ok)).unwrap();
+            let got = match &verdict {
+                Verdict::Allow => None,
+                Verdict::Hold(reason) => Some(reason.as_str()),
+                Verdict::Reject(r) => Some(r.error_code),
+            };
+            assert_eq!(got, expected, "verdict: {:?}", verdict);
+        }
+    }
+
+    #[test]
+    fn stops_at_the_first_rejection() {
+        let mut checked = 0;
+        let verdicts = [hold("a"), reject("r"), Verdict::Allow]
+            .into_iter()
+            .map(|v| {
+                checked += 1;
+                Ok(v)
+            });
+        assert!(matches!(combine(verdicts), Ok(Verdict::Reject(_))));
+        assert_eq!(checked, 2);
+    }
+}
@@ -1,24 +1,83 @@
 #[macro_use]
 mod logger;
+mod counters;
 mod db;
+mod filter;
+mod markdown;
+mod media;
 mod models;
+mod pagination;
+mod preview;
 mod response;
 mod routes;
+mod scheduler;
 mod schema;
+mod storage;
+#[cfg(test)]
+mod testing;
+mod text;
 
 use actix_web::{web::Data, App, HttpServer};
 use dotenv::dotenv;
+use filter::FilterPipeline;
 use listenfd;
-use std::env;
+use preview::HttpFetcher;
+use std::{env, process, sync::Arc};
+use storage::{LocalStorage, Storage};
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
     dotenv().ok();
 
+    // `skripsi reconcile-counters` fixes drifted counters and exits, with a non-zero
+    // status if it could not.
+    let reconcile = env::args().nth(1).as_deref() == Some("reconcile-counters");
+
     let db = match db::establish_connection() {
         Ok(db) => db,
         Err(e) => {
             error!("Failed to establish connection to database: {}", e);
+            if reconcile {
+                process::exit(1);
+            }
+            return Ok(());
+        }
+    };
+
+    if reconcile {
+        let mut conn = match db.get() {
+            Ok(conn) => conn,
+            Err(e) => {
+                error!("Failed to get db connection from pool: {}", e);
+                process::exit(1);
+            }
+        };
+        match counters::reconcile_counters(&mut conn) {
+            Ok(n) => info!("Fixed counters on {} row(s)", n),
+            Err(e) => {
+                error!("Failed to reconcile counters: {}", e);
+                process::exit(1);
+            }
+        }
+        return Ok(());
+    }
+
+    scheduler::spawn_publisher(db.clone());
+    scheduler::spawn_analytics_rollup(db.clone());
+    scheduler::spawn_link_previews(db.clone(), Arc::new(HttpFetcher::new()));
+
+    let local_storage = match LocalStorage::from_env() {
+        Ok(s) => s,
+        Err(e) => {
+            error!("Failed to prepare upload directory: {}", e);
+            return Ok(());
+        }
+    };
+    let storage: Arc<dyn Storage> = Arc::new(local_storage);
+    let filters = match FilterPipeline::from_env() {
+        Ok(f) => Data::new(f),
+        Err(e) => {
+            error!("Failed to read content filter word lists: {}", e);
             return Ok(());
         }
     };
@@ -27,6 +86,8 @@ async fn main() -> std::io::Result<()> {
     let mut server = HttpServer::new(move || {
         App::new()
             .app_data(Data::new(db.clone()))
+            .app_data(Data::from(storage.clone()))
+            .app_data(filters.clone())
             .configure(routes::init)
     });
 
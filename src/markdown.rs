@@ -0,0 +1,256 @@
+use ammonia::Builder;
+use linkify::{LinkFinder, LinkKind};
+use pulldown_cmark::{html, CowStr, Event, LinkType, Options, Parser, Tag, TagEnd};
+use std::collections::{HashMap, HashSet};
+
+/// Maximum post body length, in characters.
+pub const MAX_BODY_LENGTH: usize = 5000;
+
+const ALLOWED_TAGS: [&str; 12] = [
+    "a",
+    "blockquote",
+    "br",
+    "code",
+    "del",
+    "em",
+    "li",
+    "ol",
+    "p",
+    "pre",
+    "strong",
+    "ul",
+];
+
+/// Renders a post body to HTML that is safe to embed as-is.
+///
+/// Only a small subset of Markdown is honoured: emphasis, strikethrough, code,
+/// quotes, lists and links. Headings become paragraphs, images become links (files
+/// belong in attachments), raw HTML is shown as text, and bare URLs are linked.
+/// The output is sanitized again afterwards so a parser quirk cannot leak markup.
+pub fn render(body: &str) -> String {
+    let finder = {
+        let mut f = LinkFinder::new();
+        f.kinds(&[LinkKind::Url]).url_must_have_scheme(false);
+        f
+    };
+    let mut events = vec![];
+    let mut in_link = false;
+    let mut in_code = false;
+    for event in Parser::new_ext(body, Options::ENABLE_STRIKETHROUGH) {
+        match event {
+            Event::Start(Tag::Heading { .. }) => events.push(Event::Start(Tag::Paragraph)),
+            Event::End(TagEnd::Heading(_)) => events.push(Event::End(TagEnd::Paragraph)),
+            Event::Start(Tag::Image {
+                link_type,
+                dest_url,
+                title,
+                id,
+            }) => {
+                in_link = true;
+                events.push(Event::Start(Tag::Link {
+                    link_type,
+                    dest_url,
+                    title,
+                    id,
+                }));
+            }
+            Event::End(TagEnd::Image) => {
+                in_link = false;
+                events.push(Event::End(TagEnd::Link));
+            }
+            Event::Start(Tag::Link { .. }) => {
+                in_link = true;
+                events.push(event);
+            }
+            Event::End(TagEnd::Link) => {
+                in_link = false;
+                events.push(event);
+            }
+            Event::Start(Tag::CodeBlock(_)) => {
+                in_code = true;
+                events.push(event);
+            }
+            Event::End(TagEnd::CodeBlock) => {
+                in_code = false;
+                events.push(event);
+            }
+            Event::Html(h) | Event::InlineHtml(h) => events.push(Event::Text(h)),
+            Event::Rule => {}
+            Event::Text(text) if !in_link && !in_code => {
+                let mut last = 0;
+                for link in finder.links(&text) {
+                    if link.start() > last {
+                        events.push(Event::Text(CowStr::from(
+                            text[last..link.start()].to_string(),
+                        )));
+                    }
+                    let href = if link.as_str().contains("://") {
+                        link.as_str().to_string()
+                    } else {
+                        format!("https://{}", link.as_str())
+                    };
+                    events.push(Event::Start(Tag::Link {
+                        link_type: LinkType::Autolink,
+                        dest_url: CowStr::from(href),
+                        title: CowStr::from(""),
+                        id: CowStr::from(""),
+                    }));
+                    events.push(Event::Text(CowStr::from(link.as_str().to_string())));
+                    events.push(Event::End(TagEnd::Link));
+                    last = link.end();
+                }
+                if last < text.len() {
+                    events.push(Event::Text(CowStr::from(text[last..].to_string())));
+                }
+            }
+            _ => events.push(event),
+        }
+    }
+
+    let mut unsafe_html = String::new();
+    html::push_html(&mut unsafe_html, events.into_iter());
+    sanitize(&unsafe_html)
+}
+
+/// Keeps only `ALLOWED_TAGS`, links with an http, https or mailto `href`, and
+/// marks links `nofollow`.
+fn sanitize(html: &str) -> String {
+    // `Builder::empty` only clears the tags; attributes and URL schemes are
+    // replaced outright so none of ammonia's defaults are let through.
+    Builder::empty()
+        .add_tags(ALLOWED_TAGS)
+        .generic_attributes(HashSet::new())
+        .tag_attributes(HashMap::from([("a", HashSet::from(["href"]))]))
+        .url_schemes(HashSet::from(["http", "https", "mailto"]))
+        .link_rel(Some("nofollow noopener noreferrer"))
+        .clean(html)
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const REL: &str = "rel=\"nofollow noopener noreferrer\"";
+
+    #[test]
+    fn raw_html_is_shown_as_text() {
+        let cases = [
+            (
+                "hi <script>alert(1)</script> there",
+                "<p>hi &lt;script&gt;alert(1)&lt;/script&gt; there</p>\n",
+            ),
+            (
+                "<script>alert(1)</script>",
+                "&lt;script&gt;alert(1)&lt;/script&gt;",
+            ),
+            (
+                "<img src=x onerror=alert(1)>",
+                "&lt;img src=x onerror=alert(1)&gt;",
+            ),
+            (
+                "<div onclick=\"x\">text</div>",
+                "&lt;div onclick=\"x\"&gt;text&lt;/div&gt;",
+            ),
+        ];
+        for (body, expected) in cases {
+            assert_eq!(render(body), expected, "body: {:?}", body);
+        }
+    }
+
+    #[test]
+    fn unsafe_link_schemes_lose_their_href() {
+        for body in [
+            "[x](javascript:alert(1))",
+            "[x](JaVaScRiPt:alert(1))",
+            "[x](data:text/html,hi)",
+            "[x](tel:123)",
+            "![x](javascript:alert(1))",
+        ] {
+            assert_eq!(
+                render(body),
+                format!("<p><a {}>x</a></p>\n", REL),
+                "body: {:?}",
+                body
+            );
+        }
+    }
+
+    #[test]
+    fn links() {
+        let cases = [
+            (
+                "[x](https://example.com \"title\")",
+                format!("<p><a href=\"https://example.com\" {}>x</a></p>\n", REL),
+            ),
+            (
+                "[m](mailto:a@b.com)",
+                format!("<p><a href=\"mailto:a@b.com\" {}>m</a></p>\n", REL),
+            ),
+            (
+                "see example.com",
+                format!(
+                    "<p>see <a href=\"https://example.com\" {}>example.com</a></p>\n",
+                    REL
+                ),
+            ),
+            (
+                "`example.com` and [example.com](https://other.com)",
+                format!(
+                    "<p><code>example.com</code> and \
+                     <a href=\"https://other.com\" {}>example.com</a></p>\n",
+                    REL
+                ),
+            ),
+        ];
+        for (body, expected) in cases {
+            assert_eq!(render(body), expected, "body: {:?}", body);
+        }
+    }
+
+    #[test]
+    fn images_become_links() {
+        assert_eq!(
+            render("![alt](https://example.com/a.png)"),
+            format!(
+                "<p><a href=\"https://example.com/a.png\" {}>alt</a></p>\n",
+                REL
+            )
+        );
+    }
+
+    #[test]
+    fn supported_markdown() {
+        let cases = [
+            (
+                "**bold** _em_ ~~del~~ `code`",
+                "<p><strong>bold</strong> <em>em</em> <del>del</del> <code>code</code></p>\n",
+            ),
+            ("# Heading", "<p>Heading</p>\n"),
+            ("> quote", "<blockquote>\n<p>quote</p>\n</blockquote>\n"),
+            ("- a\n- b", "<ul>\n<li>a</li>\n<li>b</li>\n</ul>\n"),
+            ("---", ""),
+        ];
+        for (body, expected) in cases {
+            assert_eq!(render(body), expected, "body: {:?}", body);
+        }
+    }
+
+    #[test]
+    fn sanitize_strips_attributes_and_tags() {
+        let cases = [
+            (
+                "<a href=\"https://e.com\" title=\"t\" onclick=\"x\" style=\"y\">a</a>",
+                format!("<a href=\"https://e.com\" {}>a</a>", REL),
+            ),
+            ("<p lang=\"en\" class=\"c\">x</p>", "<p>x</p>".to_string()),
+            ("<img src=\"https://e.com/a.png\">", String::new()),
+            ("<script>alert(1)</script>ok", "ok".to_string()),
+            ("<iframe src=\"https://e.com\"></iframe>", String::new()),
+            ("<h1>t</h1>", "t".to_string()),
+        ];
+        for (html, expected) in cases {
+            assert_eq!(sanitize(html), expected, "html: {:?}", html);
+        }
+    }
+}
@@ -0,0 +1,38 @@
+use imagesize::ImageType;
+
+pub const MAX_ATTACHMENTS: usize = 4;
+pub const MAX_ATTACHMENT_SIZE: usize = 10 * 1024 * 1024;
+
+pub struct MediaInfo {
+    pub mime_type: &'static str,
+    pub extension: &'static str,
+    pub width: Option<i32>,
+    pub height: Option<i32>,
+}
+
+/// Identifies an upload from its content rather than the client-provided
+/// content type. Returns `None` for anything we do not accept.
+pub fn inspect(bytes: &[u8]) -> Option<MediaInfo> {
+    if bytes.starts_with(b"%PDF-") {
+        return Some(MediaInfo {
+            mime_type: "application/pdf",
+            extension: "pdf",
+            width: None,
+            height: None,
+        });
+    }
+    let (mime_type, extension) = match imagesize::image_type(bytes).ok()? {
+        ImageType::Png => ("image/png", "png"),
+        ImageType::Jpeg => ("image/jpeg", "jpg"),
+        ImageType::Gif => ("image/gif", "gif"),
+        ImageType::Webp => ("image/webp", "webp"),
+        _ => return None,
+    };
+    let size = imagesize::blob_size(bytes).ok()?;
+    Some(MediaInfo {
+        mime_type,
+        extension,
+        width: i32::try_from(size.width).ok(),
+        height: i32::try_from(size.height).ok(),
+    })
+}
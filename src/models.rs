@@ -1,13 +1,74 @@
 #![allow(unused)]
 
-use crate::schema::{company, company_position, follows, position, posts, users};
+use crate::schema::{
+    bookmarks, company, company_admins, company_follows, company_position, connection_invitations,
+    connections, follow_events, follow_requests, follows, link_previews, mentions,
+    moderation_actions, moderators, notifications, pinned_posts, poll_options, poll_votes, polls,
+    position, post_attachments, post_impressions, post_links, post_stats_daily, post_tags, posts,
+    reports, tag_follows, tags, user_blocks, user_mutes, users,
+};
 use chrono::offset::Utc;
-use chrono::DateTime;
+use chrono::{DateTime, NaiveDate};
 use diesel::{
+    deserialize::{self, FromSql},
+    pg::{Pg, PgValue},
     prelude::{Associations, Identifiable},
-    Insertable, Queryable, Selectable,
+    serialize::{self, IsNull, Output, ToSql},
+    sql_types::Text,
+    AsExpression, FromSqlRow, Insertable, Queryable, Selectable,
 };
 use serde::{Deserialize, Serialize};
+use std::{io::Write, str::FromStr};
+
+/// Maps a fieldless enum to the strings stored in a `varchar` column with a
+/// matching `CHECK` constraint.
+macro_rules! text_enum {
+    ($name:ident { $($variant:ident => $text:literal),+ $(,)? }) => {
+        impl $name {
+            pub fn as_str(&self) -> &'static str {
+                match self {
+                    $($name::$variant => $text,)+
+                }
+            }
+        }
+
+        impl FromStr for $name {
+            type Err = String;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                match s {
+                    $($text => Ok($name::$variant),)+
+                    _ => Err(format!("Unknown {}: {}", stringify!($name), s)),
+                }
+            }
+        }
+
+        impl ToSql<Text, Pg> for $name {
+            fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, Pg>) -> serialize::Result {
+                out.write_all(self.as_str().as_bytes())?;
+                Ok(IsNull::No)
+            }
+        }
+
+        impl FromSql<Text, Pg> for $name {
+            fn from_sql(bytes: PgValue<'_>) -> deserialize::Result<Self> {
+                Ok(std::str::from_utf8(bytes.as_bytes())?.parse()?)
+            }
+        }
+    };
+}
+
+#[derive(Insertable, Queryable, Debug, Serialize, Deserialize, Default, Selectable)]
+#[diesel(primary_key(id))]
+#[diesel(table_name = bookmarks)]
+pub struct Bookmark {
+    #[diesel(deserialize_as = i64)]
+    pub id: Option<i64>,
+    #[diesel(deserialize_as = DateTime<Utc>)]
+    pub created_at: Option<DateTime<Utc>>,
+    pub user_id: i64,
+    pub post_id: i64,
+}
 
 #[derive(Insertable, Queryable, Debug, Serialize, Deserialize, Default, Selectable)]
 #[diesel(primary_key(id))]
@@ -18,6 +79,35 @@ pub struct Company {
     #[diesel(deserialize_as = DateTime<Utc>)]
     pub created_at: Option<DateTime<Utc>>,
     pub name: String,
+    /// Kept up to date by database triggers, like the counters on `User`.
+    pub follower_count: i64,
+    /// Published posts made on behalf of the company.
+    pub post_count: i64,
+}
+
+#[derive(Insertable, Queryable, Debug, Serialize, Deserialize, Default, Selectable)]
+#[diesel(primary_key(id))]
+#[diesel(table_name = company_admins)]
+pub struct CompanyAdmin {
+    #[diesel(deserialize_as = i64)]
+    pub id: Option<i64>,
+    #[diesel(deserialize_as = DateTime<Utc>)]
+    pub created_at: Option<DateTime<Utc>>,
+    pub company_id: i64,
+    pub user_id: i64,
+}
+
+/// `user_id` sees posts made on behalf of `company_id` in their feed.
+#[derive(Insertable, Queryable, Debug, Serialize, Deserialize, Default)]
+#[diesel(primary_key(id))]
+#[diesel(table_name = company_follows)]
+pub struct CompanyFollow {
+    #[diesel(deserialize_as = i64)]
+    pub id: Option<i64>,
+    #[diesel(deserialize_as = DateTime<Utc>)]
+    pub created_at: Option<DateTime<Utc>>,
+    pub user_id: i64,
+    pub company_id: i64,
 }
 
 #[derive(
@@ -47,6 +137,271 @@ pub struct Follow {
     pub following_user_id: i64,
     pub followed_user_id: i64,
 }
+/// A pending connection, turned into two `UserConnection`s when accepted.
+#[derive(Insertable, Queryable, Debug, Serialize, Deserialize, Default)]
+#[diesel(primary_key(id))]
+#[diesel(table_name = connection_invitations)]
+pub struct ConnectionInvitation {
+    #[diesel(deserialize_as = i64)]
+    pub id: Option<i64>,
+    #[diesel(deserialize_as = DateTime<Utc>)]
+    pub created_at: Option<DateTime<Utc>>,
+    pub sender_id: i64,
+    pub recipient_id: i64,
+    pub note: Option<String>,
+}
+/// One side of a mutual connection; the other side is stored with the ids swapped.
+#[derive(Insertable, Queryable, Debug, Serialize, Deserialize, Default)]
+#[diesel(primary_key(id))]
+#[diesel(table_name = connections)]
+pub struct UserConnection {
+    #[diesel(deserialize_as = i64)]
+    pub id: Option<i64>,
+    #[diesel(deserialize_as = DateTime<Utc>)]
+    pub created_at: Option<DateTime<Utc>>,
+    pub user_id: i64,
+    pub connected_user_id: i64,
+}
+/// `blocker_id` blocked `blocked_id`; neither sees the other's posts or can follow them.
+#[derive(Insertable, Queryable, Debug, Serialize, Deserialize, Default)]
+#[diesel(primary_key(id))]
+#[diesel(table_name = user_blocks)]
+pub struct UserBlock {
+    #[diesel(deserialize_as = i64)]
+    pub id: Option<i64>,
+    #[diesel(deserialize_as = DateTime<Utc>)]
+    pub created_at: Option<DateTime<Utc>>,
+    pub blocker_id: i64,
+    pub blocked_id: i64,
+}
+/// `muter_id` no longer sees posts of `muted_id` in their feed.
+#[derive(Insertable, Queryable, Debug, Serialize, Deserialize, Default)]
+#[diesel(primary_key(id))]
+#[diesel(table_name = user_mutes)]
+pub struct UserMute {
+    #[diesel(deserialize_as = i64)]
+    pub id: Option<i64>,
+    #[diesel(deserialize_as = DateTime<Utc>)]
+    pub created_at: Option<DateTime<Utc>>,
+    pub muter_id: i64,
+    pub muted_id: i64,
+}
+/// A pending follow of a private account, turned into a `Follow` on approval.
+#[derive(Insertable, Queryable, Debug, Serialize, Deserialize, Default)]
+#[diesel(primary_key(id))]
+#[diesel(table_name = follow_requests)]
+pub struct FollowRequest {
+    #[diesel(deserialize_as = i64)]
+    pub id: Option<i64>,
+    #[diesel(deserialize_as = DateTime<Utc>)]
+    pub created_at: Option<DateTime<Utc>>,
+    pub requester_id: i64,
+    pub target_user_id: i64,
+}
+
+/// A follow or unfollow, logged by a trigger on `follows`.
+#[derive(Insertable, Queryable, Debug, Serialize, Deserialize, Default, Selectable)]
+#[diesel(primary_key(id))]
+#[diesel(table_name = follow_events)]
+pub struct FollowEvent {
+    #[diesel(deserialize_as = i64)]
+    pub id: Option<i64>,
+    #[diesel(deserialize_as = DateTime<Utc>)]
+    pub created_at: Option<DateTime<Utc>>,
+    pub follower_id: i64,
+    pub followed_user_id: i64,
+    pub kind: FollowEventKind,
+}
+
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, AsExpression, FromSqlRow,
+)]
+#[diesel(sql_type = Text)]
+#[serde(rename_all = "snake_case")]
+pub enum FollowEventKind {
+    #[default]
+    Follow,
+    Unfollow,
+}
+
+text_enum!(FollowEventKind {
+    Follow => "follow",
+    Unfollow => "unfollow",
+});
+#[derive(Insertable, Queryable, Debug, Serialize, Deserialize, Default, Selectable)]
+#[diesel(primary_key(id))]
+#[diesel(table_name = link_previews)]
+pub struct LinkPreview {
+    #[diesel(deserialize_as = i64)]
+    pub id: Option<i64>,
+    #[diesel(deserialize_as = DateTime<Utc>)]
+    pub created_at: Option<DateTime<Utc>>,
+    pub url: String,
+    pub status: PreviewStatus,
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub image_url: Option<String>,
+    pub site_name: Option<String>,
+    pub attempts: i32,
+    pub fetched_at: Option<DateTime<Utc>>,
+}
+
+/// Fetch state of a link preview. Only `Ready` previews are shown on posts.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, AsExpression, FromSqlRow,
+)]
+#[diesel(sql_type = Text)]
+#[serde(rename_all = "snake_case")]
+pub enum PreviewStatus {
+    #[default]
+    Pending,
+    Ready,
+    Failed,
+}
+
+text_enum!(PreviewStatus {
+    Pending => "pending",
+    Ready => "ready",
+    Failed => "failed",
+});
+
+#[derive(Insertable, Queryable, Debug, Serialize, Deserialize, Default, Selectable)]
+#[diesel(primary_key(id))]
+#[diesel(table_name = mentions)]
+pub struct Mention {
+    #[diesel(deserialize_as = i64)]
+    pub id: Option<i64>,
+    #[diesel(deserialize_as = DateTime<Utc>)]
+    pub created_at: Option<DateTime<Utc>>,
+    pub post_id: i64,
+    pub user_id: i64,
+    pub start_offset: i32,
+    pub end_offset: i32,
+}
+
+#[derive(Insertable, Queryable, Debug, Serialize, Deserialize, Default, Selectable)]
+#[diesel(primary_key(id))]
+#[diesel(table_name = moderation_actions)]
+pub struct ModerationAction {
+    #[diesel(deserialize_as = i64)]
+    pub id: Option<i64>,
+    #[diesel(deserialize_as = DateTime<Utc>)]
+    pub created_at: Option<DateTime<Utc>>,
+    /// `None` once the moderator's account is deleted.
+    pub moderator_id: Option<i64>,
+    pub action: ModerationActionKind,
+    pub report_id: Option<i64>,
+    pub target_post_id: Option<i64>,
+    pub target_user_id: Option<i64>,
+    pub note: Option<String>,
+}
+
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, AsExpression, FromSqlRow,
+)]
+#[diesel(sql_type = Text)]
+#[serde(rename_all = "snake_case")]
+pub enum ModerationActionKind {
+    #[default]
+    HidePost,
+    UnhidePost,
+    SuspendUser,
+    UnsuspendUser,
+    ActionReport,
+    DismissReport,
+    ApprovePost,
+    RejectPost,
+}
+
+text_enum!(ModerationActionKind {
+    HidePost => "hide_post",
+    UnhidePost => "unhide_post",
+    SuspendUser => "suspend_user",
+    UnsuspendUser => "unsuspend_user",
+    ActionReport => "action_report",
+    DismissReport => "dismiss_report",
+    ApprovePost => "approve_post",
+    RejectPost => "reject_post",
+});
+
+#[derive(Insertable, Queryable, Debug, Serialize, Deserialize, Default, Selectable)]
+#[diesel(primary_key(id))]
+#[diesel(table_name = moderators)]
+pub struct Moderator {
+    #[diesel(deserialize_as = i64)]
+    pub id: Option<i64>,
+    #[diesel(deserialize_as = DateTime<Utc>)]
+    pub created_at: Option<DateTime<Utc>>,
+    pub user_id: i64,
+}
+
+#[derive(Insertable, Queryable, Debug, Serialize, Deserialize, Default, Selectable)]
+#[diesel(primary_key(id))]
+#[diesel(table_name = notifications)]
+pub struct Notification {
+    #[diesel(deserialize_as = i64)]
+    pub id: Option<i64>,
+    #[diesel(deserialize_as = DateTime<Utc>)]
+    pub created_at: Option<DateTime<Utc>>,
+    pub user_id: i64,
+    pub actor_user_id: i64,
+    pub kind: String,
+    pub post_id: Option<i64>,
+    pub is_read: bool,
+}
+
+#[derive(Insertable, Queryable, Debug, Serialize, Deserialize, Default, Selectable)]
+#[diesel(primary_key(id))]
+#[diesel(table_name = pinned_posts)]
+pub struct PinnedPost {
+    #[diesel(deserialize_as = i64)]
+    pub id: Option<i64>,
+    #[diesel(deserialize_as = DateTime<Utc>)]
+    pub created_at: Option<DateTime<Utc>>,
+    pub post_id: i64,
+    pub user_id: Option<i64>,
+    pub position: i32,
+    pub company_id: Option<i64>,
+}
+
+#[derive(Insertable, Queryable, Debug, Serialize, Deserialize, Default, Selectable)]
+#[diesel(primary_key(id))]
+#[diesel(table_name = polls)]
+pub struct Poll {
+    #[diesel(deserialize_as = i64)]
+    pub id: Option<i64>,
+    #[diesel(deserialize_as = DateTime<Utc>)]
+    pub created_at: Option<DateTime<Utc>>,
+    pub post_id: i64,
+    pub closes_at: DateTime<Utc>,
+}
+
+#[derive(Insertable, Queryable, Debug, Serialize, Deserialize, Default, Selectable)]
+#[diesel(primary_key(id))]
+#[diesel(table_name = poll_options)]
+pub struct PollOption {
+    #[diesel(deserialize_as = i64)]
+    pub id: Option<i64>,
+    #[diesel(deserialize_as = DateTime<Utc>)]
+    pub created_at: Option<DateTime<Utc>>,
+    pub poll_id: i64,
+    pub position: i32,
+    pub label: String,
+}
+
+#[derive(Insertable, Queryable, Debug, Serialize, Deserialize, Default, Selectable)]
+#[diesel(primary_key(id))]
+#[diesel(table_name = poll_votes)]
+pub struct PollVote {
+    #[diesel(deserialize_as = i64)]
+    pub id: Option<i64>,
+    #[diesel(deserialize_as = DateTime<Utc>)]
+    pub created_at: Option<DateTime<Utc>>,
+    pub poll_id: i64,
+    pub poll_option_id: i64,
+    pub user_id: i64,
+}
+
 #[derive(Insertable, Queryable, Debug, Serialize, Deserialize, Default, Selectable)]
 #[diesel(primary_key(id))]
 #[diesel(table_name = position)]
@@ -68,6 +423,264 @@ pub struct Post {
     pub created_at: Option<DateTime<Utc>>,
     pub body: String,
     pub user_id: i64,
+    pub visibility: Visibility,
+    pub status: PostStatus,
+    pub publish_at: Option<DateTime<Utc>>,
+    pub kind: PostKind,
+    /// Set while a moderator has hidden the post.
+    pub hidden_at: Option<DateTime<Utc>>,
+    /// Why the content filter held the post for review.
+    pub held_reason: Option<String>,
+    /// Set when an admin posted on behalf of a company.
+    pub company_id: Option<i64>,
+}
+
+/// What a post carries besides its body.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, AsExpression, FromSqlRow,
+)]
+#[diesel(sql_type = Text)]
+#[serde(rename_all = "snake_case")]
+pub enum PostKind {
+    #[default]
+    Text,
+    Poll,
+}
+
+text_enum!(PostKind {
+    Text => "text",
+    Poll => "poll",
+});
+
+/// Who can read a post. `Followers` also includes the author.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, AsExpression, FromSqlRow,
+)]
+#[diesel(sql_type = Text)]
+#[serde(rename_all = "snake_case")]
+pub enum Visibility {
+    #[default]
+    Public,
+    Followers,
+    Private,
+}
+
+text_enum!(Visibility {
+    Public => "public",
+    Followers => "followers",
+    Private => "private",
+});
+
+/// Publication state of a post. Only `Published` posts are shown to readers;
+/// `Held` posts wait for a moderator after the content filter flagged them.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, AsExpression, FromSqlRow,
+)]
+#[diesel(sql_type = Text)]
+#[serde(rename_all = "snake_case")]
+pub enum PostStatus {
+    Draft,
+    Scheduled,
+    #[default]
+    Published,
+    Held,
+}
+
+text_enum!(PostStatus {
+    Draft => "draft",
+    Scheduled => "scheduled",
+    Published => "published",
+    Held => "held",
+});
+
+#[derive(Insertable, Queryable, Debug, Serialize, Deserialize, Default, Selectable)]
+#[diesel(primary_key(id))]
+#[diesel(table_name = post_attachments)]
+pub struct PostAttachment {
+    #[diesel(deserialize_as = i64)]
+    pub id: Option<i64>,
+    #[diesel(deserialize_as = DateTime<Utc>)]
+    pub created_at: Option<DateTime<Utc>>,
+    pub post_id: i64,
+    pub position: i32,
+    pub url: String,
+    #[serde(skip_serializing)]
+    pub storage_key: String,
+    pub mime_type: String,
+    pub size_bytes: i64,
+    pub width: Option<i32>,
+    pub height: Option<i32>,
+    pub file_name: Option<String>,
+}
+
+#[derive(Insertable, Queryable, Debug, Serialize, Deserialize, Default, Selectable)]
+#[diesel(primary_key(id))]
+#[diesel(table_name = post_impressions)]
+pub struct PostImpression {
+    #[diesel(deserialize_as = i64)]
+    pub id: Option<i64>,
+    #[diesel(deserialize_as = DateTime<Utc>)]
+    pub created_at: Option<DateTime<Utc>>,
+    pub post_id: i64,
+    pub kind: ImpressionKind,
+    pub viewer_key: String,
+    pub window_start: DateTime<Utc>,
+}
+
+/// `View` is a post opened on its own, `Impression` a post shown in a list.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, AsExpression, FromSqlRow,
+)]
+#[diesel(sql_type = Text)]
+#[serde(rename_all = "snake_case")]
+pub enum ImpressionKind {
+    #[default]
+    View,
+    Impression,
+}
+
+text_enum!(ImpressionKind {
+    View => "view",
+    Impression => "impression",
+});
+
+#[derive(Insertable, Queryable, Debug, Serialize, Deserialize, Default, Selectable)]
+#[diesel(primary_key(id))]
+#[diesel(table_name = post_links)]
+pub struct PostLink {
+    #[diesel(deserialize_as = i64)]
+    pub id: Option<i64>,
+    #[diesel(deserialize_as = DateTime<Utc>)]
+    pub created_at: Option<DateTime<Utc>>,
+    pub post_id: i64,
+    pub link_preview_id: i64,
+    pub position: i32,
+}
+
+#[derive(Insertable, Queryable, Debug, Serialize, Deserialize, Default, Selectable)]
+#[diesel(primary_key(post_id, day))]
+#[diesel(table_name = post_stats_daily)]
+pub struct PostStatsDaily {
+    pub post_id: i64,
+    pub day: NaiveDate,
+    pub views: i64,
+    pub unique_viewers: i64,
+    pub impressions: i64,
+}
+
+#[derive(Insertable, Queryable, Debug, Serialize, Deserialize, Default, Selectable)]
+#[diesel(primary_key(id))]
+#[diesel(table_name = post_tags)]
+pub struct PostTag {
+    #[diesel(deserialize_as = i64)]
+    pub id: Option<i64>,
+    #[diesel(deserialize_as = DateTime<Utc>)]
+    pub created_at: Option<DateTime<Utc>>,
+    pub post_id: i64,
+    pub tag_id: i64,
+}
+
+#[derive(Insertable, Queryable, Debug, Serialize, Deserialize, Default, Selectable)]
+#[diesel(primary_key(id))]
+#[diesel(table_name = reports)]
+pub struct Report {
+    #[diesel(deserialize_as = i64)]
+    pub id: Option<i64>,
+    #[diesel(deserialize_as = DateTime<Utc>)]
+    pub created_at: Option<DateTime<Utc>>,
+    pub reporter_id: i64,
+    pub target_type: ReportTargetType,
+    pub target_post_id: Option<i64>,
+    pub target_user_id: Option<i64>,
+    pub reason: ReportReason,
+    pub details: Option<String>,
+    pub status: ReportStatus,
+    pub resolved_by: Option<i64>,
+    pub resolved_at: Option<DateTime<Utc>>,
+}
+
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, AsExpression, FromSqlRow,
+)]
+#[diesel(sql_type = Text)]
+#[serde(rename_all = "snake_case")]
+pub enum ReportTargetType {
+    #[default]
+    Post,
+    User,
+}
+
+text_enum!(ReportTargetType {
+    Post => "post",
+    User => "user",
+});
+
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, AsExpression, FromSqlRow,
+)]
+#[diesel(sql_type = Text)]
+#[serde(rename_all = "snake_case")]
+pub enum ReportReason {
+    Spam,
+    Harassment,
+    Hate,
+    Violence,
+    Nudity,
+    Misinformation,
+    #[default]
+    Other,
+}
+
+text_enum!(ReportReason {
+    Spam => "spam",
+    Harassment => "harassment",
+    Hate => "hate",
+    Violence => "violence",
+    Nudity => "nudity",
+    Misinformation => "misinformation",
+    Other => "other",
+});
+
+/// Reports start `Open` and are closed by a moderator either way.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, AsExpression, FromSqlRow,
+)]
+#[diesel(sql_type = Text)]
+#[serde(rename_all = "snake_case")]
+pub enum ReportStatus {
+    #[default]
+    Open,
+    Actioned,
+    Dismissed,
+}
+
+text_enum!(ReportStatus {
+    Open => "open",
+    Actioned => "actioned",
+    Dismissed => "dismissed",
+});
+
+#[derive(Insertable, Queryable, Debug, Serialize, Deserialize, Default, Selectable)]
+#[diesel(primary_key(id))]
+#[diesel(table_name = tags)]
+pub struct Tag {
+    #[diesel(deserialize_as = i64)]
+    pub id: Option<i64>,
+    #[diesel(deserialize_as = DateTime<Utc>)]
+    pub created_at: Option<DateTime<Utc>>,
+    pub name: String,
+}
+
+#[derive(Insertable, Queryable, Debug, Serialize, Deserialize, Default, Selectable)]
+#[diesel(primary_key(id))]
+#[diesel(table_name = tag_follows)]
+pub struct TagFollow {
+    #[diesel(deserialize_as = i64)]
+    pub id: Option<i64>,
+    #[diesel(deserialize_as = DateTime<Utc>)]
+    pub created_at: Option<DateTime<Utc>>,
+    pub user_id: i64,
+    pub tag_id: i64,
 }
 
 #[derive(Insertable, Queryable, Debug, Serialize, Deserialize, Default, Selectable)]
@@ -86,4 +699,13 @@ pub struct User {
     pub company_position_id: Option<i64>,
     #[diesel(deserialize_as = i64)]
     pub role: i64,
+    /// Set while the account is suspended by a moderator.
+    pub suspended_at: Option<DateTime<Utc>>,
+    /// Followers need approval, see `follow_requests`.
+    pub is_private: bool,
+    /// Kept up to date by database triggers; `reconcile-counters` fixes drift.
+    pub follower_count: i64,
+    pub following_count: i64,
+    /// Published posts only.
+    pub post_count: i64,
 }
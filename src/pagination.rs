@@ -0,0 +1,170 @@
+use crate::response::ErrorResponse;
+use actix_web::http::StatusCode;
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::{DateTime, Utc};
+use serde_json::json;
+
+pub const DEFAULT_PAGE_SIZE: i64 = 20;
+pub const MAX_PAGE_SIZE: i64 = 100;
+
+/// Position of the last row of a page, ordered by `(created_at DESC, id DESC)`.
+#[derive(Debug, Clone, Copy)]
+pub struct Cursor {
+    pub created_at: DateTime<Utc>,
+    pub id: i64,
+}
+
+/// A position in a keyset-paginated list, handed out as an opaque `next_cursor`.
+pub trait PageCursor: Sized {
+    /// The sort key, without the row id.
+    fn key(&self) -> String;
+    fn id(&self) -> i64;
+    fn from_parts(key: &str, id: i64) -> Option<Self>;
+
+    fn encode(&self) -> String {
+        URL_SAFE_NO_PAD.encode(format!("{}:{}", self.key(), self.id()))
+    }
+
+    fn decode(cursor: &str) -> Option<Self> {
+        let raw = String::from_utf8(URL_SAFE_NO_PAD.decode(cursor).ok()?).ok()?;
+        let (key, id) = raw.split_once(':')?;
+        Self::from_parts(key, id.parse().ok()?)
+    }
+}
+
+impl Cursor {
+    pub fn new(created_at: DateTime<Utc>, id: i64) -> Self {
+        Cursor { created_at, id }
+    }
+}
+
+impl PageCursor for Cursor {
+    fn key(&self) -> String {
+        self.created_at.timestamp_micros().to_string()
+    }
+
+    fn id(&self) -> i64 {
+        self.id
+    }
+
+    fn from_parts(key: &str, id: i64) -> Option<Self> {
+        let created_at = DateTime::from_timestamp_micros(key.parse().ok()?)?;
+        Some(Cursor::new(created_at, id))
+    }
+}
+
+/// Position of the last row of a page ordered by `(rank DESC, id DESC)`, for
+/// lists sorted by relevance. The rank is kept bit for bit so the next page
+/// compares against exactly the value the database returned.
+#[derive(Debug, Clone, Copy)]
+pub struct RankCursor {
+    pub rank: f32,
+    pub id: i64,
+}
+
+impl PageCursor for RankCursor {
+    fn key(&self) -> String {
+        self.rank.to_bits().to_string()
+    }
+
+    fn id(&self) -> i64 {
+        self.id
+    }
+
+    fn from_parts(key: &str, id: i64) -> Option<Self> {
+        Some(RankCursor {
+            rank: f32::from_bits(key.parse().ok()?),
+            id,
+        })
+    }
+}
+
+/// Decodes the `cursor` query parameter, rejecting anything we did not hand out.
+pub fn parse_cursor(cursor: Option<&String>) -> Result<Option<Cursor>, ErrorResponse> {
+    parse_page_cursor(cursor)
+}
+
+/// Like `parse_cursor`, for lists keyed by something other than `created_at`.
+pub fn parse_page_cursor<C: PageCursor>(
+    cursor: Option<&String>,
+) -> Result<Option<C>, ErrorResponse> {
+    match cursor {
+        Some(c) => match C::decode(c) {
+            Some(c) => Ok(Some(c)),
+            None => Err(ErrorResponse::new(
+                StatusCode::BAD_REQUEST,
+                "Invalid cursor".to_string(),
+                Some("invalid_cursor".to_string()),
+            )),
+        },
+        None => Ok(None),
+    }
+}
+
+/// Clamps the requested page size to `1..=MAX_PAGE_SIZE`.
+pub fn page_size(limit: Option<i64>) -> i64 {
+    limit.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE)
+}
+
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub next_cursor: Option<String>,
+}
+
+impl<T> Page<T> {
+    /// Builds a page from rows loaded with `LIMIT limit + 1`; the extra row only
+    /// tells us whether there is a next page.
+    pub fn new<C: PageCursor>(mut rows: Vec<T>, limit: i64, cursor_of: impl Fn(&T) -> C) -> Self {
+        let next_cursor = if rows.len() as i64 > limit {
+            rows.truncate(limit as usize);
+            rows.last().map(|r| cursor_of(r).encode())
+        } else {
+            None
+        };
+        Page {
+            items: rows,
+            next_cursor,
+        }
+    }
+
+    pub fn map<U>(self, f: impl FnMut(T) -> U) -> Page<U> {
+        Page {
+            items: self.items.into_iter().map(f).collect(),
+            next_cursor: self.next_cursor,
+        }
+    }
+
+    pub fn meta(&self) -> serde_json::Value {
+        json!({ "next_cursor": self.next_cursor })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cursors_round_trip() {
+        let c = Cursor::new(
+            DateTime::from_timestamp_micros(1_792_384_434_567_607).unwrap(),
+            10,
+        );
+        let d = Cursor::decode(&c.encode()).unwrap();
+        assert_eq!((d.created_at, d.id), (c.created_at, c.id));
+
+        for rank in [0.0, 0.1, 0.09090909, f32::MIN_POSITIVE, 1e-30] {
+            let c = RankCursor { rank, id: 7 };
+            let d = RankCursor::decode(&c.encode()).unwrap();
+            assert_eq!((d.rank.to_bits(), d.id), (rank.to_bits(), 7));
+        }
+    }
+
+    #[test]
+    fn rejects_foreign_cursors() {
+        for c in ["", "zzz", "MTIz", "bm90OmFuaWQ"] {
+            assert!(parse_cursor(Some(&c.to_string())).is_err(), "{:?}", c);
+            assert!(parse_page_cursor::<RankCursor>(Some(&c.to_string())).is_err());
+        }
+        assert!(parse_cursor(None).unwrap().is_none());
+    }
+}
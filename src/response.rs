@@ -35,6 +35,18 @@ impl OkResponse {
             }))
             .into()
     }
+
+    pub fn with_meta(
+        message: String,
+        data: Option<serde_json::Value>,
+        meta: serde_json::Value,
+    ) -> HttpResponse<BoxBody> {
+        HttpResponse::build(StatusCode::OK).json(json!({
+            "message": message,
+            "data": data,
+            "meta": meta,
+        }))
+    }
 }
 
 impl ResponseError for ErrorResponse {
@@ -3,4 +3,6 @@ use actix_web::web::ServiceConfig;
 
 pub fn init(config: &mut ServiceConfig) {
     v1::init(config);
+    // Attachments keep the unversioned URLs they were stored under.
+    config.service(v1::get_attachment);
 }
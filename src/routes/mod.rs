@@ -2,3 +2,4 @@ mod init;
 mod v1;
 
 pub use init::init;
+pub use v1::{claim_link_previews, publish_due_posts, rollup_post_stats, store_link_previews};
@@ -0,0 +1,547 @@
+use crate::{
+    db::{DbPool, DbPooled},
+    models::{FollowEventKind, ImpressionKind, Post, PostStatsDaily, User},
+    pagination::{self, Cursor, Page},
+    response::{ErrorResponse, OkResponse},
+    schema::{follow_events, follows, post_impressions, post_stats_daily, posts, users},
+};
+use actix_web::{
+    get,
+    http::StatusCode,
+    web::{self, Data, ServiceConfig},
+    HttpRequest, HttpResponse,
+};
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use diesel::{
+    dsl::count_star,
+    sql_types::{Date, Timestamptz},
+    BoolExpressionMethods, Connection, ExpressionMethods, QueryDsl, QueryResult, RunQueryDsl,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+/// A viewer is counted at most once per post and kind within this window.
+const IMPRESSION_WINDOW_SECS: i64 = 60 * 60;
+/// Raw impressions are kept this long after being rolled up.
+const IMPRESSION_RETENTION_DAYS: i64 = 7;
+const DEFAULT_ANALYTICS_DAYS: i64 = 30;
+const MAX_ANALYTICS_DAYS: i64 = 90;
+const TOP_POSTS_LIMIT: usize = 5;
+
+#[derive(Deserialize)]
+struct AnalyticsQuery {
+    user_id: Option<i64>,
+    days: Option<i64>,
+}
+
+/// `unique_viewers` adds up the daily unique viewers of each post, so someone who
+/// reads two posts, or one post on two days, is counted twice.
+#[derive(Serialize, Default, Clone, Copy)]
+struct Totals {
+    views: i64,
+    unique_viewers: i64,
+    impressions: i64,
+    new_followers: i64,
+}
+
+#[derive(Serialize)]
+struct DailyStats {
+    day: NaiveDate,
+    #[serde(flatten)]
+    totals: Totals,
+}
+
+#[derive(Serialize)]
+struct PostStats {
+    post_id: i64,
+    views: i64,
+    unique_viewers: i64,
+    impressions: i64,
+}
+
+#[derive(Serialize)]
+struct AnalyticsResult {
+    followers: i64,
+    totals: Totals,
+    daily: Vec<DailyStats>,
+    top_posts: Vec<PostStats>,
+}
+
+#[derive(Deserialize)]
+struct FollowEventQuery {
+    user_id: i64,
+    cursor: Option<String>,
+    limit: Option<i64>,
+}
+
+#[derive(Serialize, Default, Clone, Copy)]
+struct FollowerChange {
+    follows: i64,
+    unfollows: i64,
+    net: i64,
+}
+
+#[derive(Serialize)]
+struct DailyFollowers {
+    day: NaiveDate,
+    #[serde(flatten)]
+    change: FollowerChange,
+    /// Follower count at the end of the day.
+    followers: i64,
+}
+
+#[derive(Serialize)]
+struct FollowerGrowth {
+    followers: i64,
+    totals: FollowerChange,
+    daily: Vec<DailyFollowers>,
+}
+
+/// A follow or unfollow of the user, with the follower it was made by.
+#[derive(Serialize)]
+struct FollowEventEntry {
+    id: i64,
+    kind: FollowEventKind,
+    occurred_at: DateTime<Utc>,
+    follower_id: i64,
+    username: String,
+    name: String,
+}
+
+/// Identifies who is looking for deduplication: the user when known, otherwise
+/// the client address.
+pub fn viewer_key(req: &HttpRequest, viewer_id: Option<i64>) -> Option<String> {
+    match viewer_id {
+        Some(v) => Some(format!("user:{}", v)),
+        None => req.peer_addr().map(|a| format!("ip:{}", a.ip())),
+    }
+}
+
+/// Counts `posts` as seen by `viewer_key`. Authors looking at their own posts
+/// are not counted, and repeats within the same window are ignored.
+pub fn record_impressions(
+    conn: &mut DbPooled,
+    posts: &[&Post],
+    viewer_id: Option<i64>,
+    viewer_key: &str,
+    kind: ImpressionKind,
+) -> QueryResult<()> {
+    let now = Utc::now().timestamp();
+    let window_start =
+        DateTime::from_timestamp(now - now % IMPRESSION_WINDOW_SECS, 0).unwrap_or_default();
+    let rows: Vec<_> = posts
+        .iter()
+        .filter(|p| Some(p.user_id) != viewer_id)
+        .map(|p| {
+            (
+                post_impressions::post_id.eq(p.id.unwrap()),
+                post_impressions::kind.eq(kind),
+                post_impressions::viewer_key.eq(viewer_key),
+                post_impressions::window_start.eq(window_start),
+            )
+        })
+        .collect();
+    if rows.is_empty() {
+        return Ok(());
+    }
+    diesel::insert_into(post_impressions::table)
+        .values(&rows)
+        .on_conflict_do_nothing()
+        .execute(conn)?;
+    Ok(())
+}
+
+/// Like `record_impressions`, but only logs failures: analytics must not break reads.
+pub fn track(
+    conn: &mut DbPooled,
+    req: &HttpRequest,
+    posts: &[&Post],
+    viewer_id: Option<i64>,
+    kind: ImpressionKind,
+) {
+    if let Some(key) = viewer_key(req, viewer_id) {
+        if let Err(e) = record_impressions(conn, posts, viewer_id, &key, kind) {
+            error!("Failed to record post impressions: {}", e);
+        }
+    }
+}
+
+/// Rebuilds `post_stats_daily` for every day still covered by raw impressions,
+/// then drops raw impressions past retention. Days are in UTC.
+pub fn rollup_post_stats(conn: &mut DbPooled) -> QueryResult<usize> {
+    let keep_from = (Utc::now() - Duration::days(IMPRESSION_RETENTION_DAYS))
+        .date_naive()
+        .and_hms_opt(0, 0, 0)
+        .unwrap()
+        .and_utc();
+    conn.transaction(|conn| {
+        let updated = diesel::sql_query(
+            "INSERT INTO post_stats_daily (post_id, day, views, unique_viewers, impressions) \
+             SELECT post_id, (window_start AT TIME ZONE 'UTC')::date, \
+                    count(*) FILTER (WHERE kind = 'view'), \
+                    count(DISTINCT viewer_key) FILTER (WHERE kind = 'view'), \
+                    count(*) FILTER (WHERE kind = 'impression') \
+             FROM post_impressions WHERE window_start >= $1 \
+             GROUP BY 1, 2 \
+             ON CONFLICT (post_id, day) DO UPDATE SET \
+                 views = EXCLUDED.views, \
+                 unique_viewers = EXCLUDED.unique_viewers, \
+                 impressions = EXCLUDED.impressions",
+        )
+        .bind::<Timestamptz, _>(keep_from)
+        .execute(conn)?;
+        diesel::delete(
+            post_impressions::table.filter(post_impressions::window_start.lt(keep_from)),
+        )
+        .execute(conn)?;
+        Ok(updated)
+    })
+}
+
+#[get("")]
+async fn get_analytics(
+    req: HttpRequest,
+    data: Data<DbPool>,
+) -> Result<HttpResponse, ErrorResponse> {
+    let mut connection = match data.get() {
+        Ok(conn) => conn,
+        Err(e) => {
+            return Err(ErrorResponse::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to get db connection from pool: {}", e),
+                Some("db_connection_failed".to_string()),
+            ));
+        }
+    };
+    let analytics_query =
+        web::Query::<AnalyticsQuery>::from_query(req.query_string()).map_err(|_| {
+            ErrorResponse::new(
+                StatusCode::BAD_REQUEST,
+                "Invalid query".to_string(),
+                Some("invalid_query".to_string()),
+            )
+        })?;
+    let author_id = match analytics_query.user_id {
+        Some(u) => u,
+        None => {
+            return Err(ErrorResponse::new(
+                StatusCode::BAD_REQUEST,
+                "User id is required".to_string(),
+                Some("user_id_required".to_string()),
+            ));
+        }
+    };
+    let days = analytics_query
+        .days
+        .unwrap_or(DEFAULT_ANALYTICS_DAYS)
+        .clamp(1, MAX_ANALYTICS_DAYS);
+    let today = Utc::now().date_naive();
+    let since = today - Duration::days(days - 1);
+    let load_failed = |e: diesel::result::Error| {
+        ErrorResponse::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to load analytics: {}", e),
+            Some("load_analytics_failed".to_string()),
+        )
+    };
+
+    let stats = post_stats_daily::table
+        .inner_join(posts::table)
+        .filter(posts::user_id.eq(author_id))
+        .filter(post_stats_daily::day.ge(since))
+        .select((
+            post_stats_daily::post_id,
+            post_stats_daily::day,
+            post_stats_daily::views,
+            post_stats_daily::unique_viewers,
+            post_stats_daily::impressions,
+        ))
+        .load::<PostStatsDaily>(&mut connection)
+        .map_err(load_failed)?;
+    let new_followers = follows::table
+        .filter(follows::followed_user_id.eq(author_id))
+        .filter(follows::created_at.ge(since.and_hms_opt(0, 0, 0).unwrap().and_utc()))
+        .select(diesel::dsl::sql::<Date>(
+            "(follows.created_at AT TIME ZONE 'UTC')::date",
+        ))
+        .load::<NaiveDate>(&mut connection)
+        .map_err(load_failed)?;
+    let followers = follows::table
+        .filter(follows::followed_user_id.eq(author_id))
+        .select(count_star())
+        .first::<i64>(&mut connection)
+        .map_err(load_failed)?;
+
+    let mut daily: BTreeMap<NaiveDate, Totals> = since
+        .iter_days()
+        .take(days as usize)
+        .map(|d| (d, Totals::default()))
+        .collect();
+    let mut per_post: HashMap<i64, PostStats> = HashMap::new();
+    for s in stats {
+        let d = daily.entry(s.day).or_default();
+        d.views += s.views;
+        d.unique_viewers += s.unique_viewers;
+        d.impressions += s.impressions;
+        let p = per_post.entry(s.post_id).or_insert(PostStats {
+            post_id: s.post_id,
+            views: 0,
+            unique_viewers: 0,
+            impressions: 0,
+        });
+        p.views += s.views;
+        p.unique_viewers += s.unique_viewers;
+        p.impressions += s.impressions;
+    }
+    for day in new_followers {
+        daily.entry(day).or_default().new_followers += 1;
+    }
+
+    let mut totals = Totals::default();
+    for d in daily.values() {
+        totals.views += d.views;
+        totals.unique_viewers += d.unique_viewers;
+        totals.impressions += d.impressions;
+        totals.new_followers += d.new_followers;
+    }
+    let mut top_posts: Vec<PostStats> = per_post.into_values().collect();
+    top_posts.sort_by_key(|p| std::cmp::Reverse((p.views, p.impressions)));
+    top_posts.truncate(TOP_POSTS_LIMIT);
+
+    let result = AnalyticsResult {
+        followers,
+        totals,
+        daily: daily
+            .into_iter()
+            .map(|(day, totals)| DailyStats { day, totals })
+            .collect(),
+        top_posts,
+    };
+    Ok(OkResponse::new(
+        "Analytics found".to_string(),
+        Some(serde_json::to_value(result).unwrap()),
+    ))
+}
+
+/// Follows, unfollows and net change of a user's followers per day (UTC), with
+/// the follower count at the end of each day. Counts are only complete from the
+/// day the follow event log was introduced: earlier unfollows were not kept.
+#[get("/followers")]
+async fn get_follower_growth(
+    req: HttpRequest,
+    data: Data<DbPool>,
+) -> Result<HttpResponse, ErrorResponse> {
+    let mut connection = match data.get() {
+        Ok(conn) => conn,
+        Err(e) => {
+            return Err(ErrorResponse::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to get db connection from pool: {}", e),
+                Some("db_connection_failed".to_string()),
+            ));
+        }
+    };
+    let analytics_query =
+        web::Query::<AnalyticsQuery>::from_query(req.query_string()).map_err(|_| {
+            ErrorResponse::new(
+                StatusCode::BAD_REQUEST,
+                "Invalid query".to_string(),
+                Some("invalid_query".to_string()),
+            )
+        })?;
+    let user_id = match analytics_query.user_id {
+        Some(u) => u,
+        None => {
+            return Err(ErrorResponse::new(
+                StatusCode::BAD_REQUEST,
+                "User id is required".to_string(),
+                Some("user_id_required".to_string()),
+            ));
+        }
+    };
+    let days = analytics_query
+        .days
+        .unwrap_or(DEFAULT_ANALYTICS_DAYS)
+        .clamp(1, MAX_ANALYTICS_DAYS);
+    let today = Utc::now().date_naive();
+    let since = today - Duration::days(days - 1);
+    let followers = match users::table
+        .find(user_id)
+        .select(users::follower_count)
+        .first::<i64>(&mut connection)
+    {
+        Ok(f) => f,
+        Err(diesel::result::Error::NotFound) => {
+            return Err(ErrorResponse::new(
+                StatusCode::NOT_FOUND,
+                "User not found".to_string(),
+                Some("user_not_found".to_string()),
+            ));
+        }
+        Err(e) => {
+            return Err(ErrorResponse::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to load follower growth: {}", e),
+                Some("load_follower_growth_failed".to_string()),
+            ));
+        }
+    };
+    let events = follow_events::table
+        .filter(follow_events::followed_user_id.eq(user_id))
+        .filter(follow_events::created_at.ge(since.and_hms_opt(0, 0, 0).unwrap().and_utc()))
+        .select((
+            diesel::dsl::sql::<Date>("(follow_events.created_at AT TIME ZONE 'UTC')::date"),
+            follow_events::kind,
+        ))
+        .load::<(NaiveDate, FollowEventKind)>(&mut connection)
+        .map_err(|e| {
+            ErrorResponse::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to load follower growth: {}", e),
+                Some("load_follower_growth_failed".to_string()),
+            )
+        })?;
+
+    let mut daily: BTreeMap<NaiveDate, FollowerChange> = since
+        .iter_days()
+        .take(days as usize)
+        .map(|d| (d, FollowerChange::default()))
+        .collect();
+    for (day, kind) in events {
+        let d = daily.entry(day).or_default();
+        match kind {
+            FollowEventKind::Follow => d.follows += 1,
+            FollowEventKind::Unfollow => d.unfollows += 1,
+        }
+        d.net = d.follows - d.unfollows;
+    }
+
+    let mut totals = FollowerChange::default();
+    for d in daily.values() {
+        totals.follows += d.follows;
+        totals.unfollows += d.unfollows;
+        totals.net += d.net;
+    }
+    // Walks forward from the count before the window so the last day ends at
+    // the current follower count.
+    let mut running = followers - totals.net;
+    let result = FollowerGrowth {
+        followers,
+        totals,
+        daily: daily
+            .into_iter()
+            .map(|(day, change)| {
+                running += change.net;
+                DailyFollowers {
+                    day,
+                    change,
+                    followers: running,
+                }
+            })
+            .collect(),
+    };
+    Ok(OkResponse::new(
+        "Follower growth found".to_string(),
+        Some(serde_json::to_value(result).unwrap()),
+    ))
+}
+
+/// Exports the follow and unfollow events of a user's followers, most recent first.
+#[get("/followers/events")]
+async fn get_follow_events(
+    req: HttpRequest,
+    data: Data<DbPool>,
+) -> Result<HttpResponse, ErrorResponse> {
+    let mut connection = match data.get() {
+        Ok(conn) => conn,
+        Err(e) => {
+            return Err(ErrorResponse::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to get db connection from pool: {}", e),
+                Some("db_connection_failed".to_string()),
+            ));
+        }
+    };
+    let event_query =
+        web::Query::<FollowEventQuery>::from_query(req.query_string()).map_err(|_| {
+            ErrorResponse::new(
+                StatusCode::BAD_REQUEST,
+                "Invalid query".to_string(),
+                Some("invalid_query".to_string()),
+            )
+        })?;
+    let limit = pagination::page_size(event_query.limit);
+    let cursor = pagination::parse_cursor(event_query.cursor.as_ref())?;
+    let load_failed = |e: diesel::result::Error| {
+        ErrorResponse::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to load follow events: {}", e),
+            Some("load_follow_events_failed".to_string()),
+        )
+    };
+
+    let mut query = follow_events::table
+        .filter(follow_events::followed_user_id.eq(event_query.user_id))
+        .select((
+            follow_events::created_at,
+            follow_events::id,
+            follow_events::kind,
+            follow_events::follower_id,
+        ))
+        .order((follow_events::created_at.desc(), follow_events::id.desc()))
+        .limit(limit + 1)
+        .into_boxed();
+    if let Some(c) = cursor {
+        query = query.filter(
+            follow_events::created_at
+                .lt(c.created_at)
+                .or(follow_events::created_at
+                    .eq(c.created_at)
+                    .and(follow_events::id.lt(c.id))),
+        );
+    }
+    let rows = query
+        .load::<(DateTime<Utc>, i64, FollowEventKind, i64)>(&mut connection)
+        .map_err(load_failed)?;
+    let page = Page::new(rows, limit, |(created_at, id, _, _)| {
+        Cursor::new(*created_at, *id)
+    });
+    let meta = page.meta();
+
+    let ids: Vec<i64> = page.items.iter().map(|(_, _, _, u)| *u).collect();
+    let followers: HashMap<i64, User> = users::table
+        .filter(users::id.eq_any(&ids))
+        .load::<User>(&mut connection)
+        .map_err(load_failed)?
+        .into_iter()
+        .map(|u| (u.id.unwrap(), u))
+        .collect();
+    let results: Vec<FollowEventEntry> = page
+        .items
+        .into_iter()
+        .filter_map(|(occurred_at, id, kind, u)| {
+            followers.get(&u).map(|user| FollowEventEntry {
+                id,
+                kind,
+                occurred_at,
+                follower_id: u,
+                username: user.username.clone(),
+                name: user.name.clone(),
+            })
+        })
+        .collect();
+    Ok(OkResponse::with_meta(
+        "Follow events found".to_string(),
+        Some(serde_json::to_value(results).unwrap()),
+        meta,
+    ))
+}
+
+pub fn init(config: &mut ServiceConfig) {
+    config.service(
+        web::scope("/analytics")
+            .service(get_analytics)
+            .service(get_follower_growth)
+            .service(get_follow_events),
+    );
+}
@@ -0,0 +1,96 @@
+use crate::{
+    db::DbPool,
+    response::ErrorResponse,
+    routes::v1::post::{read_attachment_failed, visible_to},
+    schema::{post_attachments, posts},
+    storage::Storage,
+};
+use actix_web::{
+    get,
+    http::StatusCode,
+    web::{self, Data},
+    HttpRequest, HttpResponse,
+};
+use diesel::{BoolExpressionMethods, ExpressionMethods, OptionalExtension, QueryDsl, RunQueryDsl};
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+struct AttachmentQuery {
+    viewer_id: Option<i64>,
+}
+
+fn attachment_not_found() -> ErrorResponse {
+    ErrorResponse::new(
+        StatusCode::NOT_FOUND,
+        "Attachment not found".to_string(),
+        Some("attachment_not_found".to_string()),
+    )
+}
+
+/// Serves an attachment if the viewer may see its post, or wrote it. Files of
+/// posts the viewer cannot see are reported as missing rather than forbidden.
+#[get("/uploads/{key:.*}")]
+pub async fn get_attachment(
+    req: HttpRequest,
+    path: web::Path<String>,
+    data: Data<DbPool>,
+    storage: Data<dyn Storage>,
+) -> Result<HttpResponse, ErrorResponse> {
+    let mut connection = match data.get() {
+        Ok(conn) => conn,
+        Err(e) => {
+            return Err(ErrorResponse::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to get db connection from pool: {}", e),
+                Some("db_connection_failed".to_string()),
+            ));
+        }
+    };
+    let attachment_query =
+        web::Query::<AttachmentQuery>::from_query(req.query_string()).map_err(|_| {
+            ErrorResponse::new(
+                StatusCode::BAD_REQUEST,
+                "Invalid query".to_string(),
+                Some("invalid_query".to_string()),
+            )
+        })?;
+    let key = path.into_inner();
+    let viewer_id = attachment_query.viewer_id;
+    let mut query = post_attachments::table
+        .inner_join(posts::table)
+        .filter(post_attachments::storage_key.eq(&key))
+        .select(post_attachments::mime_type)
+        .into_boxed();
+    query = match viewer_id {
+        Some(v) => query.filter(visible_to(viewer_id).or(posts::user_id.eq(v))),
+        None => query.filter(visible_to(None)),
+    };
+    let mime_type = match query.first::<String>(&mut connection).optional() {
+        Ok(Some(m)) => m,
+        Ok(None) => return Err(attachment_not_found()),
+        Err(e) => {
+            return Err(ErrorResponse::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to load attachment: {}", e),
+                Some("load_attachment_failed".to_string()),
+            ));
+        }
+    };
+    drop(connection);
+
+    let storage = storage.into_inner();
+    let bytes = match web::block(move || storage.get(&key)).await {
+        Ok(Ok(b)) => b,
+        Ok(Err(e)) if e.kind() == std::io::ErrorKind::NotFound => {
+            return Err(attachment_not_found())
+        }
+        Ok(Err(e)) => return Err(read_attachment_failed(e)),
+        Err(e) => return Err(read_attachment_failed(e)),
+    };
+    // Visibility can change, so shared caches must not keep the file.
+    Ok(HttpResponse::Ok()
+        .content_type(mime_type)
+        .insert_header(("Cache-Control", "private, no-cache"))
+        .insert_header(("X-Content-Type-Options", "nosniff"))
+        .body(bytes))
+}
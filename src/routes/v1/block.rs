@@ -0,0 +1,492 @@
+use crate::{
+    db::{DbPool, DbPooled},
+    models::{User, UserBlock, UserMute},
+    pagination::{self, Cursor, Page},
+    response::{ErrorResponse, OkResponse},
+    schema::{
+        connection_invitations, connections, follow_requests, follows, user_blocks, user_mutes,
+        users,
+    },
+};
+use actix_web::{
+    get,
+    http::StatusCode,
+    post,
+    web::{self, Data, ServiceConfig},
+    HttpRequest, HttpResponse, Result,
+};
+use chrono::{DateTime, Utc};
+use diesel::{
+    dsl::{exists, select},
+    BoolExpressionMethods, Connection, ExpressionMethods, JoinOnDsl, QueryDsl, QueryResult,
+    RunQueryDsl,
+};
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize, Debug)]
+struct BlockForm {
+    user_id: i64,
+    blocked_user_id: i64,
+}
+
+#[derive(Deserialize, Debug)]
+struct MuteForm {
+    user_id: i64,
+    muted_user_id: i64,
+}
+
+#[derive(Deserialize)]
+struct BlockListQuery {
+    user_id: i64,
+    cursor: Option<String>,
+    limit: Option<i64>,
+}
+
+#[derive(Serialize)]
+struct BlockListEntry {
+    id: i64,
+    username: String,
+    name: String,
+    profile_picture: Option<String>,
+    /// When the user was blocked or muted.
+    since: DateTime<Utc>,
+}
+
+/// Whether either user has blocked the other.
+pub fn blocked_between(conn: &mut DbPooled, user_id: i64, other_id: i64) -> QueryResult<bool> {
+    select(exists(
+        user_blocks::table.filter(
+            user_blocks::blocker_id
+                .eq(user_id)
+                .and(user_blocks::blocked_id.eq(other_id))
+                .or(user_blocks::blocker_id
+                    .eq(other_id)
+                    .and(user_blocks::blocked_id.eq(user_id))),
+        ),
+    ))
+    .get_result(conn)
+}
+
+/// Users `user_id` has blocked or was blocked by.
+pub fn blocked_user_ids(conn: &mut DbPooled, user_id: i64) -> QueryResult<Vec<i64>> {
+    let mut ids = user_blocks::table
+        .filter(user_blocks::blocker_id.eq(user_id))
+        .select(user_blocks::blocked_id)
+        .load::<i64>(conn)?;
+    ids.extend(
+        user_blocks::table
+            .filter(user_blocks::blocked_id.eq(user_id))
+            .select(user_blocks::blocker_id)
+            .load::<i64>(conn)?,
+    );
+    Ok(ids)
+}
+
+/// Refuses interactions between users when either has blocked the other.
+pub fn check_not_blocked(
+    conn: &mut DbPooled,
+    user_id: i64,
+    other_id: i64,
+) -> Result<(), ErrorResponse> {
+    match blocked_between(conn, user_id, other_id) {
+        Ok(false) => Ok(()),
+        Ok(true) => Err(ErrorResponse::new(
+            StatusCode::FORBIDDEN,
+            "You cannot interact with this user".to_string(),
+            Some("user_blocked".to_string()),
+        )),
+        Err(e) => Err(ErrorResponse::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to check blocks: {}", e),
+            Some("check_blocks_failed".to_string()),
+        )),
+    }
+}
+
+/// Ends every relationship between two users, in both directions: follows,
+/// follow requests, connections and connection invitations.
+fn sever(conn: &mut DbPooled, user_id: i64, other_id: i64) -> QueryResult<()> {
+    diesel::delete(
+        follows::table.filter(
+            follows::following_user_id
+                .eq(user_id)
+                .and(follows::followed_user_id.eq(other_id))
+                .or(follows::following_user_id
+                    .eq(other_id)
+                    .and(follows::followed_user_id.eq(user_id))),
+        ),
+    )
+    .execute(conn)?;
+    diesel::delete(
+        follow_requests::table.filter(
+            follow_requests::requester_id
+                .eq(user_id)
+                .and(follow_requests::target_user_id.eq(other_id))
+                .or(follow_requests::requester_id
+                    .eq(other_id)
+                    .and(follow_requests::target_user_id.eq(user_id))),
+        ),
+    )
+    .execute(conn)?;
+    diesel::delete(
+        connections::table.filter(
+            connections::user_id
+                .eq(user_id)
+                .and(connections::connected_user_id.eq(other_id))
+                .or(connections::user_id
+                    .eq(other_id)
+                    .and(connections::connected_user_id.eq(user_id))),
+        ),
+    )
+    .execute(conn)?;
+    diesel::delete(
+        connection_invitations::table.filter(
+            connection_invitations::sender_id
+                .eq(user_id)
+                .and(connection_invitations::recipient_id.eq(other_id))
+                .or(connection_invitations::sender_id
+                    .eq(other_id)
+                    .and(connection_invitations::recipient_id.eq(user_id))),
+        ),
+    )
+    .execute(conn)?;
+    Ok(())
+}
+
+fn check_users(conn: &mut DbPooled, ids: [i64; 2]) -> Result<(), ErrorResponse> {
+    for u in ids {
+        if users::table.find(u).first::<User>(conn).is_err() {
+            return Err(ErrorResponse::new(
+                StatusCode::NOT_FOUND,
+                "User not found".to_string(),
+                Some("user_not_found".to_string()),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Blocks a user. Follows, connections and pending requests between the two are
+/// removed and not restored by unblocking.
+#[post("/block")]
+async fn block(
+    form: web::Json<BlockForm>,
+    data: Data<DbPool>,
+) -> Result<HttpResponse, ErrorResponse> {
+    if form.user_id == form.blocked_user_id {
+        return Err(ErrorResponse::new(
+            StatusCode::BAD_REQUEST,
+            "User cannot block themselves".to_string(),
+            Some("user_blocking_self".to_string()),
+        ));
+    }
+    let mut connection = match data.get() {
+        Ok(conn) => conn,
+        Err(e) => {
+            return Err(ErrorResponse::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to get db connection from pool: {}", e),
+                Some("db_connection_failed".to_string()),
+            ));
+        }
+    };
+    check_users(&mut connection, [form.user_id, form.blocked_user_id])?;
+    let inserted = connection
+        .transaction::<_, diesel::result::Error, _>(|conn| {
+            let inserted = diesel::insert_into(user_blocks::table)
+                .values(UserBlock {
+                    blocker_id: form.user_id,
+                    blocked_id: form.blocked_user_id,
+                    ..Default::default()
+                })
+                .on_conflict((user_blocks::blocker_id, user_blocks::blocked_id))
+                .do_nothing()
+                .execute(conn)?;
+            sever(conn, form.user_id, form.blocked_user_id)?;
+            Ok(inserted)
+        })
+        .map_err(|e| {
+            ErrorResponse::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to block user: {}", e),
+                Some("block_user_failed".to_string()),
+            )
+        })?;
+    let message = if inserted == 0 {
+        "User already blocked"
+    } else {
+        "User blocked"
+    };
+    Ok(OkResponse::new(message.to_string(), None))
+}
+
+#[post("/unblock")]
+async fn unblock(
+    form: web::Json<BlockForm>,
+    data: Data<DbPool>,
+) -> Result<HttpResponse, ErrorResponse> {
+    let mut connection = match data.get() {
+        Ok(conn) => conn,
+        Err(e) => {
+            return Err(ErrorResponse::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to get db connection from pool: {}", e),
+                Some("db_connection_failed".to_string()),
+            ));
+        }
+    };
+    let deleted = diesel::delete(
+        user_blocks::table
+            .filter(user_blocks::blocker_id.eq(form.user_id))
+            .filter(user_blocks::blocked_id.eq(form.blocked_user_id)),
+    )
+    .execute(&mut connection)
+    .map_err(|e| {
+        ErrorResponse::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to unblock user: {}", e),
+            Some("unblock_user_failed".to_string()),
+        )
+    })?;
+    let message = if deleted == 0 {
+        "User was not blocked"
+    } else {
+        "User unblocked"
+    };
+    Ok(OkResponse::new(message.to_string(), None))
+}
+
+/// Mutes a user: their posts no longer appear in the caller's feed, but stay
+/// visible everywhere else and nothing changes for the muted user.
+#[post("/mute")]
+async fn mute(
+    form: web::Json<MuteForm>,
+    data: Data<DbPool>,
+) -> Result<HttpResponse, ErrorResponse> {
+    if form.user_id == form.muted_user_id {
+        return Err(ErrorResponse::new(
+            StatusCode::BAD_REQUEST,
+            "User cannot mute themselves".to_string(),
+            Some("user_muting_self".to_string()),
+        ));
+    }
+    let mut connection = match data.get() {
+        Ok(conn) => conn,
+        Err(e) => {
+            return Err(ErrorResponse::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to get db connection from pool: {}", e),
+                Some("db_connection_failed".to_string()),
+            ));
+        }
+    };
+    check_users(&mut connection, [form.user_id, form.muted_user_id])?;
+    let inserted = diesel::insert_into(user_mutes::table)
+        .values(UserMute {
+            muter_id: form.user_id,
+            muted_id: form.muted_user_id,
+            ..Default::default()
+        })
+        .on_conflict((user_mutes::muter_id, user_mutes::muted_id))
+        .do_nothing()
+        .execute(&mut connection)
+        .map_err(|e| {
+            ErrorResponse::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to mute user: {}", e),
+                Some("mute_user_failed".to_string()),
+            )
+        })?;
+    let message = if inserted == 0 {
+        "User already muted"
+    } else {
+        "User muted"
+    };
+    Ok(OkResponse::new(message.to_string(), None))
+}
+
+#[post("/unmute")]
+async fn unmute(
+    form: web::Json<MuteForm>,
+    data: Data<DbPool>,
+) -> Result<HttpResponse, ErrorResponse> {
+    let mut connection = match data.get() {
+        Ok(conn) => conn,
+        Err(e) => {
+            return Err(ErrorResponse::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to get db connection from pool: {}", e),
+                Some("db_connection_failed".to_string()),
+            ));
+        }
+    };
+    let deleted = diesel::delete(
+        user_mutes::table
+            .filter(user_mutes::muter_id.eq(form.user_id))
+            .filter(user_mutes::muted_id.eq(form.muted_user_id)),
+    )
+    .execute(&mut connection)
+    .map_err(|e| {
+        ErrorResponse::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to unmute user: {}", e),
+            Some("unmute_user_failed".to_string()),
+        )
+    })?;
+    let message = if deleted == 0 {
+        "User was not muted"
+    } else {
+        "User unmuted"
+    };
+    Ok(OkResponse::new(message.to_string(), None))
+}
+
+/// Rows of a block or mute list: (since, relation id, user id, username, name, picture).
+type ListRow = (DateTime<Utc>, i64, i64, String, String, Option<String>);
+
+fn list_page(rows: Vec<ListRow>, limit: i64) -> HttpResponse {
+    let page = Page::new(rows, limit, |(since, id, _, _, _, _)| {
+        Cursor::new(*since, *id)
+    });
+    let meta = page.meta();
+    let results: Vec<BlockListEntry> = page
+        .items
+        .into_iter()
+        .map(
+            |(since, _, id, username, name, profile_picture)| BlockListEntry {
+                id,
+                username,
+                name,
+                profile_picture,
+                since,
+            },
+        )
+        .collect();
+    OkResponse::with_meta(
+        "Users found".to_string(),
+        Some(serde_json::to_value(results).unwrap()),
+        meta,
+    )
+}
+
+/// Lists the users `user_id` has blocked, most recent first.
+#[get("/blocks")]
+async fn get_blocks(req: HttpRequest, data: Data<DbPool>) -> Result<HttpResponse, ErrorResponse> {
+    let mut connection = match data.get() {
+        Ok(conn) => conn,
+        Err(e) => {
+            return Err(ErrorResponse::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to get db connection from pool: {}", e),
+                Some("db_connection_failed".to_string()),
+            ));
+        }
+    };
+    let list_query =
+        web::Query::<BlockListQuery>::from_query(req.query_string()).map_err(|_| {
+            ErrorResponse::new(
+                StatusCode::BAD_REQUEST,
+                "Invalid query".to_string(),
+                Some("invalid_query".to_string()),
+            )
+        })?;
+    let limit = pagination::page_size(list_query.limit);
+    let cursor = pagination::parse_cursor(list_query.cursor.as_ref())?;
+    let mut query = user_blocks::table
+        .inner_join(users::table.on(users::id.eq(user_blocks::blocked_id)))
+        .filter(user_blocks::blocker_id.eq(list_query.user_id))
+        .select((
+            user_blocks::created_at,
+            user_blocks::id,
+            users::id,
+            users::username,
+            users::name,
+            users::profile_picture,
+        ))
+        .order((user_blocks::created_at.desc(), user_blocks::id.desc()))
+        .limit(limit + 1)
+        .into_boxed();
+    if let Some(c) = cursor {
+        query = query.filter(
+            user_blocks::created_at
+                .lt(c.created_at)
+                .or(user_blocks::created_at
+                    .eq(c.created_at)
+                    .and(user_blocks::id.lt(c.id))),
+        );
+    }
+    let rows = query.load::<ListRow>(&mut connection).map_err(|e| {
+        ErrorResponse::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to load blocks: {}", e),
+            Some("load_blocks_failed".to_string()),
+        )
+    })?;
+    Ok(list_page(rows, limit))
+}
+
+/// Lists the users `user_id` has muted, most recent first.
+#[get("/mutes")]
+async fn get_mutes(req: HttpRequest, data: Data<DbPool>) -> Result<HttpResponse, ErrorResponse> {
+    let mut connection = match data.get() {
+        Ok(conn) => conn,
+        Err(e) => {
+            return Err(ErrorResponse::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to get db connection from pool: {}", e),
+                Some("db_connection_failed".to_string()),
+            ));
+        }
+    };
+    let list_query =
+        web::Query::<BlockListQuery>::from_query(req.query_string()).map_err(|_| {
+            ErrorResponse::new(
+                StatusCode::BAD_REQUEST,
+                "Invalid query".to_string(),
+                Some("invalid_query".to_string()),
+            )
+        })?;
+    let limit = pagination::page_size(list_query.limit);
+    let cursor = pagination::parse_cursor(list_query.cursor.as_ref())?;
+    let mut query = user_mutes::table
+        .inner_join(users::table.on(users::id.eq(user_mutes::muted_id)))
+        .filter(user_mutes::muter_id.eq(list_query.user_id))
+        .select((
+            user_mutes::created_at,
+            user_mutes::id,
+            users::id,
+            users::username,
+            users::name,
+            users::profile_picture,
+        ))
+        .order((user_mutes::created_at.desc(), user_mutes::id.desc()))
+        .limit(limit + 1)
+        .into_boxed();
+    if let Some(c) = cursor {
+        query = query.filter(
+            user_mutes::created_at
+                .lt(c.created_at)
+                .or(user_mutes::created_at
+                    .eq(c.created_at)
+                    .and(user_mutes::id.lt(c.id))),
+        );
+    }
+    let rows = query.load::<ListRow>(&mut connection).map_err(|e| {
+        ErrorResponse::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to load mutes: {}", e),
+            Some("load_mutes_failed".to_string()),
+        )
+    })?;
+    Ok(list_page(rows, limit))
+}
+
+pub fn init(config: &mut ServiceConfig) {
+    config
+        .service(block)
+        .service(unblock)
+        .service(mute)
+        .service(unmute)
+        .service(get_blocks)
+        .service(get_mutes);
+}
@@ -0,0 +1,239 @@
+use crate::{
+    db::DbPool,
+    models::{Post, User},
+    pagination::{self, Cursor, Page},
+    response::{ErrorResponse, OkResponse},
+    routes::v1::post::{to_post_results, visible_to},
+    schema::{bookmarks, posts, users},
+};
+use actix_web::{
+    get,
+    http::StatusCode,
+    post,
+    web::{self, Data, ServiceConfig},
+    HttpRequest, HttpResponse, Result,
+};
+use chrono::{DateTime, Utc};
+use diesel::{BoolExpressionMethods, ExpressionMethods, QueryDsl, RunQueryDsl};
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize)]
+struct BookmarkQuery {
+    user_id: Option<i64>,
+    cursor: Option<String>,
+    limit: Option<i64>,
+}
+
+#[derive(Deserialize, Debug)]
+struct BookmarkForm {
+    user_id: i64,
+    post_id: i64,
+}
+
+#[derive(Serialize)]
+struct BookmarkState {
+    post_id: i64,
+    saved: bool,
+}
+
+fn bookmark_state(message: &str, post_id: i64, saved: bool) -> HttpResponse {
+    OkResponse::new(
+        message.to_string(),
+        Some(serde_json::to_value(BookmarkState { post_id, saved }).unwrap()),
+    )
+}
+
+/// Saves a post for later. Only posts the user can currently read can be saved;
+/// saving twice is a no-op.
+#[post("/save")]
+async fn save_post(
+    form: web::Json<BookmarkForm>,
+    data: Data<DbPool>,
+) -> Result<HttpResponse, ErrorResponse> {
+    let mut connection = match data.get() {
+        Ok(conn) => conn,
+        Err(e) => {
+            return Err(ErrorResponse::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to get db connection from pool: {}", e),
+                Some("db_connection_failed".to_string()),
+            ));
+        }
+    };
+    if users::table
+        .find(form.user_id)
+        .first::<User>(&mut connection)
+        .is_err()
+    {
+        return Err(ErrorResponse::new(
+            StatusCode::NOT_FOUND,
+            "User not found".to_string(),
+            Some("user_not_found".to_string()),
+        ));
+    }
+    if posts::table
+        .filter(posts::id.eq(form.post_id))
+        .filter(visible_to(Some(form.user_id)))
+        .first::<Post>(&mut connection)
+        .is_err()
+    {
+        return Err(ErrorResponse::new(
+            StatusCode::NOT_FOUND,
+            "Post not found".to_string(),
+            Some("post_not_found".to_string()),
+        ));
+    }
+    let inserted = diesel::insert_into(bookmarks::table)
+        .values((
+            bookmarks::user_id.eq(form.user_id),
+            bookmarks::post_id.eq(form.post_id),
+        ))
+        .on_conflict((bookmarks::user_id, bookmarks::post_id))
+        .do_nothing()
+        .execute(&mut connection)
+        .map_err(|e| {
+            ErrorResponse::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to save post: {}", e),
+                Some("save_post_failed".to_string()),
+            )
+        })?;
+    Ok(if inserted == 0 {
+        bookmark_state("Post already saved", form.post_id, true)
+    } else {
+        bookmark_state("Post saved", form.post_id, true)
+    })
+}
+
+/// Removes a saved post. Works on posts that are no longer visible, so users can
+/// clean those up even though they are left out of the listing.
+#[post("/unsave")]
+async fn unsave_post(
+    form: web::Json<BookmarkForm>,
+    data: Data<DbPool>,
+) -> Result<HttpResponse, ErrorResponse> {
+    let mut connection = match data.get() {
+        Ok(conn) => conn,
+        Err(e) => {
+            return Err(ErrorResponse::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to get db connection from pool: {}", e),
+                Some("db_connection_failed".to_string()),
+            ));
+        }
+    };
+    let deleted = diesel::delete(
+        bookmarks::table
+            .filter(bookmarks::user_id.eq(form.user_id))
+            .filter(bookmarks::post_id.eq(form.post_id)),
+    )
+    .execute(&mut connection)
+    .map_err(|e| {
+        ErrorResponse::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to unsave post: {}", e),
+            Some("unsave_post_failed".to_string()),
+        )
+    })?;
+    Ok(if deleted == 0 {
+        bookmark_state("Post not saved", form.post_id, false)
+    } else {
+        bookmark_state("Post unsaved", form.post_id, false)
+    })
+}
+
+/// Saved posts, most recently saved first. Deleted posts disappear with their
+/// bookmark; posts the user can no longer read are skipped but stay saved, and
+/// show up again if they become visible.
+#[get("")]
+async fn get_bookmarks(
+    req: HttpRequest,
+    data: Data<DbPool>,
+) -> Result<HttpResponse, ErrorResponse> {
+    let mut connection = match data.get() {
+        Ok(conn) => conn,
+        Err(e) => {
+            return Err(ErrorResponse::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to get db connection from pool: {}", e),
+                Some("db_connection_failed".to_string()),
+            ));
+        }
+    };
+    let bookmark_query =
+        web::Query::<BookmarkQuery>::from_query(req.query_string()).map_err(|_| {
+            ErrorResponse::new(
+                StatusCode::BAD_REQUEST,
+                "Invalid query".to_string(),
+                Some("invalid_query".to_string()),
+            )
+        })?;
+    let user_id = match bookmark_query.user_id {
+        Some(u) => u,
+        None => {
+            return Err(ErrorResponse::new(
+                StatusCode::BAD_REQUEST,
+                "User id is required".to_string(),
+                Some("user_id_required".to_string()),
+            ));
+        }
+    };
+    let limit = pagination::page_size(bookmark_query.limit);
+    let cursor = pagination::parse_cursor(bookmark_query.cursor.as_ref())?;
+    let load_failed = |e: diesel::result::Error| {
+        ErrorResponse::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to load saved posts: {}", e),
+            Some("load_bookmarks_failed".to_string()),
+        )
+    };
+
+    let mut query = bookmarks::table
+        .inner_join(posts::table.inner_join(users::table))
+        .filter(bookmarks::user_id.eq(user_id))
+        .filter(visible_to(Some(user_id)))
+        .select((
+            bookmarks::created_at,
+            bookmarks::id,
+            (posts::all_columns, users::username, users::name),
+        ))
+        .order((bookmarks::created_at.desc(), bookmarks::id.desc()))
+        .limit(limit + 1)
+        .into_boxed();
+    if let Some(c) = cursor {
+        query = query.filter(
+            bookmarks::created_at
+                .lt(c.created_at)
+                .or(bookmarks::created_at
+                    .eq(c.created_at)
+                    .and(bookmarks::id.lt(c.id))),
+        );
+    }
+    let rows = query
+        .load::<(DateTime<Utc>, i64, (Post, String, String))>(&mut connection)
+        .map_err(load_failed)?;
+    let page = Page::new(rows, limit, |(created_at, id, _)| {
+        Cursor::new(*created_at, *id)
+    });
+    let meta = page.meta();
+    let results = to_post_results(
+        &mut connection,
+        page.items.into_iter().map(|(_, _, row)| row).collect(),
+        Some(user_id),
+    )
+    .map_err(load_failed)?;
+    Ok(OkResponse::with_meta(
+        "Saved posts found".to_string(),
+        Some(serde_json::to_value(results).unwrap()),
+        meta,
+    ))
+}
+
+pub fn init(config: &mut ServiceConfig) {
+    config.service(
+        web::scope("/bookmark")
+            .service(save_post)
+            .service(unsave_post)
+            .service(get_bookmarks),
+    );
+}
@@ -1,7 +1,10 @@
 use crate::{
     db::{DbPool, DbPooled},
-    models::Company,
+    models::{Company, CompanyFollow, User},
+    pagination::{self, Cursor, Page},
     response::{ErrorResponse, OkResponse},
+    routes::v1::moderation::is_moderator,
+    schema::{company, company_admins, company_follows, company_position, users},
 };
 use actix_multipart::form::{text::Text, MultipartForm};
 use actix_web::{
@@ -11,12 +14,20 @@ use actix_web::{
     web::{self, Data, ServiceConfig},
     HttpRequest, HttpResponse, Result,
 };
-use diesel::{ExpressionMethods, QueryDsl, RunQueryDsl};
+use chrono::{DateTime, Utc};
+use diesel::{
+    dsl::{exists, select},
+    BoolExpressionMethods, Connection, ExpressionMethods, QueryDsl, QueryResult, RunQueryDsl,
+};
 use serde::{Deserialize, Serialize};
 
 #[derive(Deserialize)]
 struct Query {
     id: Option<i64>,
+    /// Sets `is_following` on a single company.
+    viewer_id: Option<i64>,
+    cursor: Option<String>,
+    limit: Option<i64>,
 }
 
 #[derive(Serialize, Clone)]
@@ -45,9 +56,19 @@ async fn req_company(req: HttpRequest, data: Data<DbPool>) -> Result<HttpRespons
         let result = company.find(i).first::<Company>(&mut connection);
         if let Ok(comp) = result {
             let comp: Company = comp;
+            let state = company_follow_state(&mut connection, i, query.viewer_id).map_err(|e| {
+                ErrorResponse::new(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    format!("Failed to load company followers: {}", e),
+                    Some("load_company_followers_failed".to_string()),
+                )
+            })?;
+            let mut result = serde_json::to_value(comp).unwrap();
+            let result = result.as_object_mut().unwrap();
+            result.insert("is_following".to_string(), state.following.into());
             return Ok(OkResponse::new(
                 "Company found".to_string(),
-                Some(serde_json::to_value(comp).unwrap()),
+                Some(serde_json::to_value(result).unwrap()),
             ));
         } else {
             return Err(ErrorResponse::new(
@@ -57,11 +78,14 @@ async fn req_company(req: HttpRequest, data: Data<DbPool>) -> Result<HttpRespons
             ));
         }
     } else {
-        let companies = get_company(&mut connection, None);
-        if let Ok(companies) = companies {
-            return Ok(OkResponse::new(
+        let limit = pagination::page_size(query.limit);
+        let cursor = pagination::parse_cursor(query.cursor.as_ref())?;
+        let companies = list_company(&mut connection, cursor, limit);
+        if let Ok(page) = companies {
+            return Ok(OkResponse::with_meta(
                 "Companies found".to_string(),
-                Some(serde_json::to_value(companies).unwrap()),
+                Some(serde_json::to_value(&page.items).unwrap()),
+                page.meta(),
             ));
         }
         return Err(ErrorResponse::new(
@@ -75,6 +99,54 @@ async fn req_company(req: HttpRequest, data: Data<DbPool>) -> Result<HttpRespons
 #[derive(Debug, MultipartForm)]
 struct CompanyForm {
     name: Option<Text<String>>,
+    /// Becomes the first admin of the company.
+    user_id: Option<Text<i64>>,
+}
+
+#[derive(MultipartForm)]
+struct CompanyAdminForm {
+    company_id: Option<Text<i64>>,
+    user_id: Option<Text<i64>>,
+    /// The admin making the change.
+    actor_user_id: Option<Text<i64>>,
+}
+
+#[derive(Deserialize)]
+struct CompanyAdminQuery {
+    company_id: Option<i64>,
+}
+
+#[derive(Deserialize, Debug)]
+struct CompanyFollowForm {
+    user_id: i64,
+    company_id: i64,
+}
+
+/// Whether a user follows a company, after a follow or unfollow.
+#[derive(Serialize)]
+struct CompanyFollowState {
+    company_id: i64,
+    /// False for anonymous callers.
+    following: bool,
+    follower_count: i64,
+}
+
+#[derive(Serialize)]
+struct CompanyAdminResult {
+    user_id: i64,
+    username: String,
+    name: String,
+}
+
+/// Whether `user_id` may act on behalf of the company: post announcements,
+/// pin them and manage other admins.
+pub fn is_company_admin(conn: &mut DbPooled, company_id: i64, user_id: i64) -> QueryResult<bool> {
+    select(exists(
+        company_admins::table
+            .filter(company_admins::company_id.eq(company_id))
+            .filter(company_admins::user_id.eq(user_id)),
+    ))
+    .get_result(conn)
 }
 
 #[post("")]
@@ -104,13 +176,34 @@ async fn add_company(
             ));
         }
     };
-    match diesel::insert_into(company)
-        .values(Company {
-            name: company_name,
-            ..Default::default()
-        })
-        .execute(&mut connection)
-    {
+    let admin_id = form.user_id.map(|u| u.into_inner());
+    if let Some(u) = admin_id {
+        if users::table.find(u).first::<User>(&mut connection).is_err() {
+            return Err(ErrorResponse::new(
+                StatusCode::NOT_FOUND,
+                "User not found".to_string(),
+                Some("user_not_found".to_string()),
+            ));
+        }
+    }
+    match connection.transaction::<_, diesel::result::Error, _>(|conn| {
+        let company_id = diesel::insert_into(company)
+            .values(Company {
+                name: company_name,
+                ..Default::default()
+            })
+            .returning(id)
+            .get_result::<i64>(conn)?;
+        if let Some(u) = admin_id {
+            diesel::insert_into(company_admins::table)
+                .values((
+                    company_admins::company_id.eq(company_id),
+                    company_admins::user_id.eq(u),
+                ))
+                .execute(conn)?;
+        }
+        Ok(())
+    }) {
         Ok(_) => Ok(OkResponse::new("Company added".to_string(), None)),
         Err(e) => Err(ErrorResponse::new(
             StatusCode::INTERNAL_SERVER_ERROR,
@@ -176,6 +269,385 @@ async fn update(
     }
 }
 
+fn read_admin_form(form: CompanyAdminForm) -> Result<(i64, i64, i64), ErrorResponse> {
+    match (form.company_id, form.user_id, form.actor_user_id) {
+        (Some(c), Some(u), Some(a)) => Ok((c.into_inner(), u.into_inner(), a.into_inner())),
+        _ => Err(ErrorResponse::new(
+            StatusCode::BAD_REQUEST,
+            "Company id, user id and actor user id are required".to_string(),
+            Some("company_admin_fields_required".to_string()),
+        )),
+    }
+}
+
+/// `is_company_admin` as a request guard.
+pub fn check_company_admin(
+    conn: &mut DbPooled,
+    company_id: i64,
+    actor_user_id: i64,
+) -> Result<(), ErrorResponse> {
+    match is_company_admin(conn, company_id, actor_user_id) {
+        Ok(true) => Ok(()),
+        Ok(false) => Err(ErrorResponse::new(
+            StatusCode::FORBIDDEN,
+            "Only company admins can do this".to_string(),
+            Some("not_company_admin".to_string()),
+        )),
+        Err(e) => Err(ErrorResponse::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to check company admin: {}", e),
+            Some("company_admin_check_failed".to_string()),
+        )),
+    }
+}
+
+#[get("/admin")]
+async fn get_admins(req: HttpRequest, data: Data<DbPool>) -> Result<HttpResponse, ErrorResponse> {
+    let mut connection = match data.get() {
+        Ok(conn) => conn,
+        Err(e) => {
+            return Err(ErrorResponse::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to get db connection from pool: {}", e),
+                Some("db_connection_failed".to_string()),
+            ));
+        }
+    };
+    let company_id = match web::Query::<CompanyAdminQuery>::from_query(req.query_string()) {
+        Ok(q) if q.company_id.is_some() => q.company_id.unwrap(),
+        _ => {
+            return Err(ErrorResponse::new(
+                StatusCode::BAD_REQUEST,
+                "Company id is required".to_string(),
+                Some("company_id_required".to_string()),
+            ));
+        }
+    };
+    let admins = company_admins::table
+        .inner_join(users::table)
+        .filter(company_admins::company_id.eq(company_id))
+        .select((users::id, users::username, users::name))
+        .order(company_admins::created_at)
+        .load::<(i64, String, String)>(&mut connection)
+        .map_err(|e| {
+            ErrorResponse::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to load company admins: {}", e),
+                Some("load_company_admins_failed".to_string()),
+            )
+        })?;
+    let admins: Vec<CompanyAdminResult> = admins
+        .into_iter()
+        .map(|(i, u, n)| CompanyAdminResult {
+            user_id: i,
+            username: u,
+            name: n,
+        })
+        .collect();
+    Ok(OkResponse::new(
+        "Company admins found".to_string(),
+        Some(serde_json::to_value(admins).unwrap()),
+    ))
+}
+
+/// Whether `actor_user_id` may make `user_id` an admin. Admins can add anyone. While
+/// a company has no admin, e.g. when it was created without `user_id`, a moderator
+/// can appoint the first one, or an employee can claim the role for themselves.
+fn may_add_admin(
+    conn: &mut DbPooled,
+    company_id: i64,
+    user_id: i64,
+    actor_user_id: i64,
+) -> QueryResult<bool> {
+    if is_company_admin(conn, company_id, actor_user_id)? {
+        return Ok(true);
+    }
+    let has_admins: bool = select(exists(
+        company_admins::table.filter(company_admins::company_id.eq(company_id)),
+    ))
+    .get_result(conn)?;
+    if has_admins {
+        return Ok(false);
+    }
+    if is_moderator(conn, actor_user_id)? {
+        return Ok(true);
+    }
+    if actor_user_id != user_id {
+        return Ok(false);
+    }
+    select(exists(
+        users::table
+            .inner_join(company_position::table)
+            .filter(users::id.eq(user_id))
+            .filter(company_position::company_id.eq(company_id)),
+    ))
+    .get_result(conn)
+}
+
+#[post("/admin/add")]
+async fn add_admin(
+    MultipartForm(form): MultipartForm<CompanyAdminForm>,
+    data: Data<DbPool>,
+) -> Result<HttpResponse, ErrorResponse> {
+    let mut connection = match data.get() {
+        Ok(conn) => conn,
+        Err(e) => {
+            return Err(ErrorResponse::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to get db connection from pool: {}", e),
+                Some("db_connection_failed".to_string()),
+            ));
+        }
+    };
+    let (company_id, user_id, actor_user_id) = read_admin_form(form)?;
+    if company::table
+        .find(company_id)
+        .first::<Company>(&mut connection)
+        .is_err()
+    {
+        return Err(ErrorResponse::new(
+            StatusCode::NOT_FOUND,
+            "Company not found".to_string(),
+            Some("company_not_found".to_string()),
+        ));
+    }
+    if users::table
+        .find(user_id)
+        .first::<User>(&mut connection)
+        .is_err()
+    {
+        return Err(ErrorResponse::new(
+            StatusCode::NOT_FOUND,
+            "User not found".to_string(),
+            Some("user_not_found".to_string()),
+        ));
+    }
+    let added = connection.transaction::<_, diesel::result::Error, _>(|conn| {
+        // Serializes first-admin claims on the same company.
+        company::table
+            .find(company_id)
+            .select(company::id)
+            .for_update()
+            .execute(conn)?;
+        if !may_add_admin(conn, company_id, user_id, actor_user_id)? {
+            return Ok(false);
+        }
+        diesel::insert_into(company_admins::table)
+            .values((
+                company_admins::company_id.eq(company_id),
+                company_admins::user_id.eq(user_id),
+            ))
+            .on_conflict((company_admins::company_id, company_admins::user_id))
+            .do_nothing()
+            .execute(conn)?;
+        Ok(true)
+    });
+    match added {
+        Ok(true) => Ok(OkResponse::new("Company admin added".to_string(), None)),
+        Ok(false) => Err(ErrorResponse::new(
+            StatusCode::FORBIDDEN,
+            "Only company admins can do this".to_string(),
+            Some("not_company_admin".to_string()),
+        )),
+        Err(e) => Err(ErrorResponse::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to add company admin: {}", e),
+            Some("company_admin_add_failed".to_string()),
+        )),
+    }
+}
+
+/// Removes an admin. A company always keeps at least one admin.
+#[post("/admin/remove")]
+async fn remove_admin(
+    MultipartForm(form): MultipartForm<CompanyAdminForm>,
+    data: Data<DbPool>,
+) -> Result<HttpResponse, ErrorResponse> {
+    let mut connection = match data.get() {
+        Ok(conn) => conn,
+        Err(e) => {
+            return Err(ErrorResponse::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to get db connection from pool: {}", e),
+                Some("db_connection_failed".to_string()),
+            ));
+        }
+    };
+    let (company_id, user_id, actor_user_id) = read_admin_form(form)?;
+    check_company_admin(&mut connection, company_id, actor_user_id)?;
+    let removed = connection.transaction::<_, diesel::result::Error, _>(|conn| {
+        let admins = company_admins::table
+            .filter(company_admins::company_id.eq(company_id))
+            .select(company_admins::user_id)
+            .for_update()
+            .load::<i64>(conn)?;
+        if !admins.contains(&user_id) {
+            return Ok(true);
+        }
+        if admins.len() == 1 {
+            return Ok(false);
+        }
+        diesel::delete(
+            company_admins::table
+                .filter(company_admins::company_id.eq(company_id))
+                .filter(company_admins::user_id.eq(user_id)),
+        )
+        .execute(conn)?;
+        Ok(true)
+    });
+    match removed {
+        Ok(true) => Ok(OkResponse::new("Company admin removed".to_string(), None)),
+        Ok(false) => Err(ErrorResponse::new(
+            StatusCode::CONFLICT,
+            "A company needs at least one admin".to_string(),
+            Some("last_company_admin".to_string()),
+        )),
+        Err(e) => Err(ErrorResponse::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to remove company admin: {}", e),
+            Some("company_admin_remove_failed".to_string()),
+        )),
+    }
+}
+
+fn company_follow_state(
+    conn: &mut DbPooled,
+    company_id: i64,
+    user_id: Option<i64>,
+) -> QueryResult<CompanyFollowState> {
+    let following = match user_id {
+        Some(u) => select(exists(
+            company_follows::table
+                .filter(company_follows::company_id.eq(company_id))
+                .filter(company_follows::user_id.eq(u)),
+        ))
+        .get_result(conn)?,
+        None => false,
+    };
+    let follower_count = company::table
+        .find(company_id)
+        .select(company::follower_count)
+        .first(conn)?;
+    Ok(CompanyFollowState {
+        company_id,
+        following,
+        follower_count,
+    })
+}
+
+/// Follows a company: posts made on its behalf show up in the user's feed.
+/// Following a company already followed changes nothing.
+#[post("/follow")]
+async fn follow_company(
+    form: web::Json<CompanyFollowForm>,
+    data: Data<DbPool>,
+) -> Result<HttpResponse, ErrorResponse> {
+    let mut connection = match data.get() {
+        Ok(conn) => conn,
+        Err(e) => {
+            return Err(ErrorResponse::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to get db connection from pool: {}", e),
+                Some("db_connection_failed".to_string()),
+            ));
+        }
+    };
+    if users::table
+        .find(form.user_id)
+        .first::<User>(&mut connection)
+        .is_err()
+    {
+        return Err(ErrorResponse::new(
+            StatusCode::NOT_FOUND,
+            "User not found".to_string(),
+            Some("user_not_found".to_string()),
+        ));
+    }
+    if company::table
+        .find(form.company_id)
+        .first::<Company>(&mut connection)
+        .is_err()
+    {
+        return Err(ErrorResponse::new(
+            StatusCode::NOT_FOUND,
+            "Company not found".to_string(),
+            Some("company_not_found".to_string()),
+        ));
+    }
+    let (inserted, state) = diesel::insert_into(company_follows::table)
+        .values(CompanyFollow {
+            user_id: form.user_id,
+            company_id: form.company_id,
+            ..Default::default()
+        })
+        .on_conflict((company_follows::user_id, company_follows::company_id))
+        .do_nothing()
+        .execute(&mut connection)
+        .and_then(|inserted| {
+            company_follow_state(&mut connection, form.company_id, Some(form.user_id))
+                .map(|s| (inserted, s))
+        })
+        .map_err(|e| {
+            ErrorResponse::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to follow company: {}", e),
+                Some("follow_company_failed".to_string()),
+            )
+        })?;
+    let message = if inserted == 0 {
+        "Already following company"
+    } else {
+        "Company followed"
+    };
+    Ok(OkResponse::new(
+        message.to_string(),
+        Some(serde_json::to_value(state).unwrap()),
+    ))
+}
+
+#[post("/unfollow")]
+async fn unfollow_company(
+    form: web::Json<CompanyFollowForm>,
+    data: Data<DbPool>,
+) -> Result<HttpResponse, ErrorResponse> {
+    let mut connection = match data.get() {
+        Ok(conn) => conn,
+        Err(e) => {
+            return Err(ErrorResponse::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to get db connection from pool: {}", e),
+                Some("db_connection_failed".to_string()),
+            ));
+        }
+    };
+    let (deleted, state) = diesel::delete(
+        company_follows::table
+            .filter(company_follows::user_id.eq(form.user_id))
+            .filter(company_follows::company_id.eq(form.company_id)),
+    )
+    .execute(&mut connection)
+    .and_then(|deleted| {
+        company_follow_state(&mut connection, form.company_id, Some(form.user_id))
+            .map(|s| (deleted, s))
+    })
+    .map_err(|e| {
+        ErrorResponse::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to unfollow company: {}", e),
+            Some("unfollow_company_failed".to_string()),
+        )
+    })?;
+    let message = if deleted == 0 {
+        "Not following company"
+    } else {
+        "Company unfollowed"
+    };
+    Ok(OkResponse::new(
+        message.to_string(),
+        Some(serde_json::to_value(state).unwrap()),
+    ))
+}
+
 pub fn get_company(
     conn: &mut DbPooled,
     company_position_id: Option<i64>,
@@ -213,11 +685,62 @@ pub fn get_company(
     Ok(vec![])
 }
 
+pub fn list_company(
+    conn: &mut DbPooled,
+    cursor: Option<Cursor>,
+    limit: i64,
+) -> QueryResult<Page<CompanyResult>> {
+    use crate::schema::company::dsl::*;
+    use crate::schema::company_position::dsl::*;
+    use crate::schema::position::dsl::*;
+
+    use crate::schema::company::dsl::name as company_name;
+    use crate::schema::company_position::dsl::created_at as q_created_at;
+    use crate::schema::company_position::dsl::id as q_company_position_id;
+    use crate::schema::position::dsl::name as position_name;
+
+    let mut query = company_position
+        .inner_join(company)
+        .inner_join(position)
+        .select((
+            company_name,
+            position_name,
+            q_company_position_id,
+            q_created_at,
+        ))
+        .order((q_created_at.desc(), q_company_position_id.desc()))
+        .limit(limit + 1)
+        .into_boxed();
+    if let Some(c) = cursor {
+        query = query.filter(
+            q_created_at.lt(c.created_at).or(q_created_at
+                .eq(c.created_at)
+                .and(q_company_position_id.lt(c.id))),
+        );
+    }
+
+    let results = query.load::<(String, String, i64, DateTime<Utc>)>(conn)?;
+    Ok(
+        Page::new(results, limit, |(_, _, i, t)| Cursor::new(*t, *i)).map(|(a, b, c, _)| {
+            CompanyResult {
+                company_name: a,
+                position_name: b,
+                id: c,
+            }
+        }),
+    )
+}
+
 pub fn init(config: &mut ServiceConfig) {
     config.service(
         web::scope("/company")
             .service(req_company)
             .service(add_company)
-            .service(update),
+            .service(update)
+            .service(get_admins)
+            .service(add_admin)
+            .service(remove_admin)
+            .service(follow_company)
+            .service(unfollow_company),
     );
 }
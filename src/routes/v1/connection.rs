@@ -0,0 +1,785 @@
+use crate::{
+    db::{DbPool, DbPooled},
+    models::{ConnectionInvitation, User, UserConnection},
+    pagination::{self, Cursor, Page},
+    response::{ErrorResponse, OkResponse},
+    routes::v1::{
+        block::check_not_blocked,
+        moderation::check_not_suspended,
+        notification::{notify, KIND_CONNECTION_ACCEPTED, KIND_CONNECTION_INVITE},
+    },
+    schema::{connection_invitations, connections, follow_requests, follows, users},
+};
+use actix_web::{
+    get,
+    http::StatusCode,
+    post,
+    web::{self, Data, ServiceConfig},
+    HttpRequest, HttpResponse, Result,
+};
+use chrono::{DateTime, Utc};
+use diesel::{
+    dsl::{exists, select},
+    pg::Pg,
+    BoolExpressionMethods, Connection, ExpressionMethods, JoinOnDsl, OptionalExtension, QueryDsl,
+    QueryResult, RunQueryDsl,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+pub const MAX_INVITATION_NOTE_LENGTH: usize = 300;
+
+#[derive(Deserialize, Debug)]
+struct InviteForm {
+    user_id: i64,
+    recipient_id: i64,
+    note: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+struct InvitationForm {
+    user_id: i64,
+    invitation_id: i64,
+}
+
+#[derive(Deserialize, Debug)]
+struct RemoveForm {
+    user_id: i64,
+    connected_user_id: i64,
+}
+
+#[derive(Deserialize)]
+struct InvitationQuery {
+    user_id: i64,
+    /// Lists the invitations the user has sent instead of the ones waiting for them.
+    sent: Option<bool>,
+    cursor: Option<String>,
+    limit: Option<i64>,
+}
+
+#[derive(Deserialize)]
+struct ConnectionListQuery {
+    cursor: Option<String>,
+    limit: Option<i64>,
+}
+
+#[derive(Deserialize)]
+struct DegreeQuery {
+    user_id: i64,
+    other_user_id: i64,
+}
+
+/// A pending invitation together with the other user: the sender for received
+/// invitations, the recipient for sent ones.
+#[derive(Serialize)]
+struct InvitationEntry {
+    id: i64,
+    invited_at: DateTime<Utc>,
+    note: Option<String>,
+    user_id: i64,
+    username: String,
+    name: String,
+    profile_picture: Option<String>,
+}
+
+#[derive(Serialize)]
+struct ConnectionEntry {
+    id: i64,
+    username: String,
+    name: String,
+    profile_picture: Option<String>,
+    connected_at: DateTime<Utc>,
+}
+
+#[derive(Serialize)]
+struct Degree {
+    user_id: i64,
+    other_user_id: i64,
+    /// 1 for connections, 2 for connections of connections, 3 one step further;
+    /// null when the users are further apart.
+    degree: Option<u8>,
+    mutual_connections: i64,
+}
+
+enum Invited {
+    Sent(ConnectionInvitation),
+    /// The recipient had already invited the sender, so they are now connected.
+    Accepted(UserConnection),
+    AlreadySent,
+    AlreadyConnected,
+}
+
+/// Connects two users in both directions and makes them follow each other. Any
+/// invitations or follow requests between them are settled by this.
+fn connect(conn: &mut DbPooled, user_id: i64, other_id: i64) -> QueryResult<UserConnection> {
+    diesel::insert_into(connections::table)
+        .values(&vec![
+            UserConnection {
+                user_id,
+                connected_user_id: other_id,
+                ..Default::default()
+            },
+            UserConnection {
+                user_id: other_id,
+                connected_user_id: user_id,
+                ..Default::default()
+            },
+        ])
+        .on_conflict((connections::user_id, connections::connected_user_id))
+        .do_nothing()
+        .execute(conn)?;
+    diesel::insert_into(follows::table)
+        .values(&vec![
+            (
+                follows::following_user_id.eq(user_id),
+                follows::followed_user_id.eq(other_id),
+            ),
+            (
+                follows::following_user_id.eq(other_id),
+                follows::followed_user_id.eq(user_id),
+            ),
+        ])
+        .on_conflict((follows::following_user_id, follows::followed_user_id))
+        .do_nothing()
+        .execute(conn)?;
+    diesel::delete(
+        follow_requests::table.filter(
+            follow_requests::requester_id
+                .eq(user_id)
+                .and(follow_requests::target_user_id.eq(other_id))
+                .or(follow_requests::requester_id
+                    .eq(other_id)
+                    .and(follow_requests::target_user_id.eq(user_id))),
+        ),
+    )
+    .execute(conn)?;
+    diesel::delete(
+        connection_invitations::table.filter(
+            connection_invitations::sender_id
+                .eq(user_id)
+                .and(connection_invitations::recipient_id.eq(other_id))
+                .or(connection_invitations::sender_id
+                    .eq(other_id)
+                    .and(connection_invitations::recipient_id.eq(user_id))),
+        ),
+    )
+    .execute(conn)?;
+    connections::table
+        .filter(connections::user_id.eq(user_id))
+        .filter(connections::connected_user_id.eq(other_id))
+        .first::<UserConnection>(conn)
+}
+
+/// Trims the note of an invitation; blank notes are dropped.
+fn check_invitation_note(note: Option<&String>) -> Result<Option<String>, ErrorResponse> {
+    let note = match note.map(|n| n.trim()) {
+        Some(n) if !n.is_empty() => n,
+        _ => return Ok(None),
+    };
+    if note.chars().count() > MAX_INVITATION_NOTE_LENGTH {
+        return Err(ErrorResponse::new(
+            StatusCode::BAD_REQUEST,
+            format!(
+                "Note must be at most {} characters",
+                MAX_INVITATION_NOTE_LENGTH
+            ),
+            Some("note_too_long".to_string()),
+        ));
+    }
+    Ok(Some(note.to_string()))
+}
+
+/// Invites a user to connect. If they have already invited the caller, their
+/// invitation is accepted instead.
+#[post("/invite")]
+async fn invite(
+    form: web::Json<InviteForm>,
+    data: Data<DbPool>,
+) -> Result<HttpResponse, ErrorResponse> {
+    if form.user_id == form.recipient_id {
+        return Err(ErrorResponse::new(
+            StatusCode::BAD_REQUEST,
+            "User cannot connect with themselves".to_string(),
+            Some("cannot_connect_self".to_string()),
+        ));
+    }
+    let mut connection = match data.get() {
+        Ok(conn) => conn,
+        Err(e) => {
+            return Err(ErrorResponse::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to get db connection from pool: {}", e),
+                Some("db_connection_failed".to_string()),
+            ));
+        }
+    };
+    let note = check_invitation_note(form.note.as_ref())?;
+    for u in [form.user_id, form.recipient_id] {
+        if users::table.find(u).first::<User>(&mut connection).is_err() {
+            return Err(ErrorResponse::new(
+                StatusCode::NOT_FOUND,
+                "User not found".to_string(),
+                Some("user_not_found".to_string()),
+            ));
+        }
+    }
+    check_not_suspended(&mut connection, form.user_id)?;
+    check_not_blocked(&mut connection, form.user_id, form.recipient_id)?;
+
+    let invited = connection
+        .transaction::<_, diesel::result::Error, _>(|conn| {
+            if select(exists(
+                connections::table
+                    .filter(connections::user_id.eq(form.user_id))
+                    .filter(connections::connected_user_id.eq(form.recipient_id)),
+            ))
+            .get_result::<bool>(conn)?
+            {
+                return Ok(Invited::AlreadyConnected);
+            }
+            if select(exists(
+                connection_invitations::table
+                    .filter(connection_invitations::sender_id.eq(form.recipient_id))
+                    .filter(connection_invitations::recipient_id.eq(form.user_id)),
+            ))
+            .get_result::<bool>(conn)?
+            {
+                let connected = connect(conn, form.user_id, form.recipient_id)?;
+                notify(
+                    conn,
+                    form.recipient_id,
+                    form.user_id,
+                    KIND_CONNECTION_ACCEPTED,
+                    None,
+                )?;
+                return Ok(Invited::Accepted(connected));
+            }
+            let invitation = diesel::insert_into(connection_invitations::table)
+                .values(ConnectionInvitation {
+                    sender_id: form.user_id,
+                    recipient_id: form.recipient_id,
+                    note: note.clone(),
+                    ..Default::default()
+                })
+                .on_conflict((
+                    connection_invitations::sender_id,
+                    connection_invitations::recipient_id,
+                ))
+                .do_nothing()
+                .get_result::<ConnectionInvitation>(conn)
+                .optional()?;
+            match invitation {
+                Some(i) => {
+                    notify(
+                        conn,
+                        form.recipient_id,
+                        form.user_id,
+                        KIND_CONNECTION_INVITE,
+                        None,
+                    )?;
+                    Ok(Invited::Sent(i))
+                }
+                None => Ok(Invited::AlreadySent),
+            }
+        })
+        .map_err(|e| {
+            ErrorResponse::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to send invitation: {}", e),
+                Some("invite_failed".to_string()),
+            )
+        })?;
+    match invited {
+        Invited::Sent(i) => Ok(OkResponse::new(
+            "Invitation sent".to_string(),
+            Some(serde_json::to_value(i).unwrap()),
+        )),
+        Invited::Accepted(c) => Ok(OkResponse::new(
+            "Invitation accepted".to_string(),
+            Some(serde_json::to_value(c).unwrap()),
+        )),
+        Invited::AlreadySent => Err(ErrorResponse::new(
+            StatusCode::CONFLICT,
+            "Invitation already sent".to_string(),
+            Some("invitation_already_sent".to_string()),
+        )),
+        Invited::AlreadyConnected => Err(ErrorResponse::new(
+            StatusCode::CONFLICT,
+            "Users are already connected".to_string(),
+            Some("already_connected".to_string()),
+        )),
+    }
+}
+
+/// Lists pending invitations of a user, most recent first.
+#[get("/invitations")]
+async fn get_invitations(
+    req: HttpRequest,
+    data: Data<DbPool>,
+) -> Result<HttpResponse, ErrorResponse> {
+    let mut connection = match data.get() {
+        Ok(conn) => conn,
+        Err(e) => {
+            return Err(ErrorResponse::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to get db connection from pool: {}", e),
+                Some("db_connection_failed".to_string()),
+            ));
+        }
+    };
+    let invitation_query =
+        web::Query::<InvitationQuery>::from_query(req.query_string()).map_err(|_| {
+            ErrorResponse::new(
+                StatusCode::BAD_REQUEST,
+                "Invalid query".to_string(),
+                Some("invalid_query".to_string()),
+            )
+        })?;
+    let limit = pagination::page_size(invitation_query.limit);
+    let cursor = pagination::parse_cursor(invitation_query.cursor.as_ref())?;
+    let load_failed = |e: diesel::result::Error| {
+        ErrorResponse::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to load invitations: {}", e),
+            Some("load_invitations_failed".to_string()),
+        )
+    };
+
+    // Selects (invited at, invitation id, note, other user) for either direction.
+    let mut query: connection_invitations::BoxedQuery<'_, Pg, _> =
+        if invitation_query.sent == Some(true) {
+            connection_invitations::table
+                .filter(connection_invitations::sender_id.eq(invitation_query.user_id))
+                .select((
+                    connection_invitations::created_at,
+                    connection_invitations::id,
+                    connection_invitations::note,
+                    connection_invitations::recipient_id,
+                ))
+                .into_boxed()
+        } else {
+            connection_invitations::table
+                .filter(connection_invitations::recipient_id.eq(invitation_query.user_id))
+                .select((
+                    connection_invitations::created_at,
+                    connection_invitations::id,
+                    connection_invitations::note,
+                    connection_invitations::sender_id,
+                ))
+                .into_boxed()
+        };
+    query = query
+        .order((
+            connection_invitations::created_at.desc(),
+            connection_invitations::id.desc(),
+        ))
+        .limit(limit + 1);
+    if let Some(c) = cursor {
+        query = query.filter(
+            connection_invitations::created_at.lt(c.created_at).or(
+                connection_invitations::created_at
+                    .eq(c.created_at)
+                    .and(connection_invitations::id.lt(c.id)),
+            ),
+        );
+    }
+    let rows = query
+        .load::<(DateTime<Utc>, i64, Option<String>, i64)>(&mut connection)
+        .map_err(load_failed)?;
+    let page = Page::new(rows, limit, |(created_at, id, _, _)| {
+        Cursor::new(*created_at, *id)
+    });
+    let meta = page.meta();
+
+    let ids: Vec<i64> = page.items.iter().map(|(_, _, _, u)| *u).collect();
+    let listed: HashMap<i64, User> = users::table
+        .filter(users::id.eq_any(&ids))
+        .load::<User>(&mut connection)
+        .map_err(load_failed)?
+        .into_iter()
+        .map(|u| (u.id.unwrap(), u))
+        .collect();
+    let results: Vec<InvitationEntry> = page
+        .items
+        .into_iter()
+        .filter_map(|(invited_at, id, note, u)| {
+            listed.get(&u).map(|user| InvitationEntry {
+                id,
+                invited_at,
+                note,
+                user_id: u,
+                username: user.username.clone(),
+                name: user.name.clone(),
+                profile_picture: user.profile_picture.clone(),
+            })
+        })
+        .collect();
+    Ok(OkResponse::with_meta(
+        "Invitations found".to_string(),
+        Some(serde_json::to_value(results).unwrap()),
+        meta,
+    ))
+}
+
+fn invitation_not_found() -> ErrorResponse {
+    ErrorResponse::new(
+        StatusCode::NOT_FOUND,
+        "Invitation not found".to_string(),
+        Some("invitation_not_found".to_string()),
+    )
+}
+
+/// Accepts an invitation sent to `user_id`. Both users then follow each other,
+/// even if one of the accounts is private.
+#[post("/invitations/accept")]
+async fn accept_invitation(
+    form: web::Json<InvitationForm>,
+    data: Data<DbPool>,
+) -> Result<HttpResponse, ErrorResponse> {
+    let mut connection = match data.get() {
+        Ok(conn) => conn,
+        Err(e) => {
+            return Err(ErrorResponse::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to get db connection from pool: {}", e),
+                Some("db_connection_failed".to_string()),
+            ));
+        }
+    };
+    let connected = connection
+        .transaction::<_, diesel::result::Error, _>(|conn| {
+            let invitation = connection_invitations::table
+                .filter(connection_invitations::id.eq(form.invitation_id))
+                .filter(connection_invitations::recipient_id.eq(form.user_id))
+                .first::<ConnectionInvitation>(conn)
+                .optional()?;
+            let invitation = match invitation {
+                Some(i) => i,
+                None => return Ok(None),
+            };
+            let connected = connect(conn, form.user_id, invitation.sender_id)?;
+            notify(
+                conn,
+                invitation.sender_id,
+                form.user_id,
+                KIND_CONNECTION_ACCEPTED,
+                None,
+            )?;
+            Ok(Some(connected))
+        })
+        .map_err(|e| {
+            ErrorResponse::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to accept invitation: {}", e),
+                Some("accept_invitation_failed".to_string()),
+            )
+        })?
+        .ok_or_else(invitation_not_found)?;
+    Ok(OkResponse::new(
+        "Invitation accepted".to_string(),
+        Some(serde_json::to_value(connected).unwrap()),
+    ))
+}
+
+/// Deletes a pending invitation: declining one sent to `user_id`, or withdrawing
+/// one they sent.
+async fn delete_invitation(
+    form: web::Json<InvitationForm>,
+    data: Data<DbPool>,
+    declining: bool,
+) -> Result<HttpResponse, ErrorResponse> {
+    let mut connection = match data.get() {
+        Ok(conn) => conn,
+        Err(e) => {
+            return Err(ErrorResponse::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to get db connection from pool: {}", e),
+                Some("db_connection_failed".to_string()),
+            ));
+        }
+    };
+    let invitation =
+        connection_invitations::table.filter(connection_invitations::id.eq(form.invitation_id));
+    let deleted = if declining {
+        diesel::delete(invitation.filter(connection_invitations::recipient_id.eq(form.user_id)))
+            .execute(&mut connection)
+    } else {
+        diesel::delete(invitation.filter(connection_invitations::sender_id.eq(form.user_id)))
+            .execute(&mut connection)
+    }
+    .map_err(|e| {
+        ErrorResponse::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to delete invitation: {}", e),
+            Some("delete_invitation_failed".to_string()),
+        )
+    })?;
+    if deleted == 0 {
+        return Err(invitation_not_found());
+    }
+    let message = if declining {
+        "Invitation declined"
+    } else {
+        "Invitation withdrawn"
+    };
+    Ok(OkResponse::new(message.to_string(), None))
+}
+
+/// Declines an invitation sent to `user_id`. The sender is not told.
+#[post("/invitations/decline")]
+async fn decline_invitation(
+    form: web::Json<InvitationForm>,
+    data: Data<DbPool>,
+) -> Result<HttpResponse, ErrorResponse> {
+    delete_invitation(form, data, true).await
+}
+
+/// Withdraws an invitation `user_id` sent.
+#[post("/invitations/withdraw")]
+async fn withdraw_invitation(
+    form: web::Json<InvitationForm>,
+    data: Data<DbPool>,
+) -> Result<HttpResponse, ErrorResponse> {
+    delete_invitation(form, data, false).await
+}
+
+/// Removes a connection on both sides. The users keep following each other.
+#[post("/remove")]
+async fn remove_connection(
+    form: web::Json<RemoveForm>,
+    data: Data<DbPool>,
+) -> Result<HttpResponse, ErrorResponse> {
+    let mut connection = match data.get() {
+        Ok(conn) => conn,
+        Err(e) => {
+            return Err(ErrorResponse::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to get db connection from pool: {}", e),
+                Some("db_connection_failed".to_string()),
+            ));
+        }
+    };
+    let deleted = diesel::delete(
+        connections::table.filter(
+            connections::user_id
+                .eq(form.user_id)
+                .and(connections::connected_user_id.eq(form.connected_user_id))
+                .or(connections::user_id
+                    .eq(form.connected_user_id)
+                    .and(connections::connected_user_id.eq(form.user_id))),
+        ),
+    )
+    .execute(&mut connection)
+    .map_err(|e| {
+        ErrorResponse::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to remove connection: {}", e),
+            Some("remove_connection_failed".to_string()),
+        )
+    })?;
+    if deleted == 0 {
+        return Err(ErrorResponse::new(
+            StatusCode::NOT_FOUND,
+            "Users are not connected".to_string(),
+            Some("connection_not_found".to_string()),
+        ));
+    }
+    Ok(OkResponse::new("Connection removed".to_string(), None))
+}
+
+fn connection_ids(conn: &mut DbPooled, user_id: i64) -> QueryResult<HashSet<i64>> {
+    Ok(connections::table
+        .filter(connections::user_id.eq(user_id))
+        .select(connections::connected_user_id)
+        .load::<i64>(conn)?
+        .into_iter()
+        .collect())
+}
+
+/// How far apart two users are in the connection graph, looking at most three
+/// steps out: the connections of both are loaded and compared, and only the
+/// third degree needs another query.
+fn connection_degree(conn: &mut DbPooled, user_id: i64, other_id: i64) -> QueryResult<Degree> {
+    let near = connection_ids(conn, user_id)?;
+    let far = connection_ids(conn, other_id)?;
+    let mutual_connections = near.intersection(&far).count() as i64;
+    let degree = if near.contains(&other_id) {
+        Some(1)
+    } else if mutual_connections > 0 {
+        Some(2)
+    } else if !near.is_empty()
+        && !far.is_empty()
+        && select(exists(
+            connections::table
+                .filter(connections::user_id.eq_any(&near))
+                .filter(connections::connected_user_id.eq_any(&far)),
+        ))
+        .get_result::<bool>(conn)?
+    {
+        Some(3)
+    } else {
+        None
+    };
+    Ok(Degree {
+        user_id,
+        other_user_id: other_id,
+        degree,
+        mutual_connections,
+    })
+}
+
+#[get("/degree")]
+async fn get_degree(req: HttpRequest, data: Data<DbPool>) -> Result<HttpResponse, ErrorResponse> {
+    let mut connection = match data.get() {
+        Ok(conn) => conn,
+        Err(e) => {
+            return Err(ErrorResponse::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to get db connection from pool: {}", e),
+                Some("db_connection_failed".to_string()),
+            ));
+        }
+    };
+    let degree_query = web::Query::<DegreeQuery>::from_query(req.query_string()).map_err(|_| {
+        ErrorResponse::new(
+            StatusCode::BAD_REQUEST,
+            "Invalid query".to_string(),
+            Some("invalid_query".to_string()),
+        )
+    })?;
+    if degree_query.user_id == degree_query.other_user_id {
+        return Err(ErrorResponse::new(
+            StatusCode::BAD_REQUEST,
+            "Users must be different".to_string(),
+            Some("same_user".to_string()),
+        ));
+    }
+    let degree = connection_degree(
+        &mut connection,
+        degree_query.user_id,
+        degree_query.other_user_id,
+    )
+    .map_err(|e| {
+        ErrorResponse::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to load connections: {}", e),
+            Some("load_connections_failed".to_string()),
+        )
+    })?;
+    Ok(OkResponse::new(
+        "Degree found".to_string(),
+        Some(serde_json::to_value(degree).unwrap()),
+    ))
+}
+
+/// Lists the connections of `username`, most recent first.
+#[get("/{username}/connections")]
+pub async fn get_connections(
+    req: HttpRequest,
+    path: web::Path<String>,
+    data: Data<DbPool>,
+) -> Result<HttpResponse, ErrorResponse> {
+    let mut connection = match data.get() {
+        Ok(conn) => conn,
+        Err(e) => {
+            return Err(ErrorResponse::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to get db connection from pool: {}", e),
+                Some("db_connection_failed".to_string()),
+            ));
+        }
+    };
+    let list_query =
+        web::Query::<ConnectionListQuery>::from_query(req.query_string()).map_err(|_| {
+            ErrorResponse::new(
+                StatusCode::BAD_REQUEST,
+                "Invalid query".to_string(),
+                Some("invalid_query".to_string()),
+            )
+        })?;
+    let limit = pagination::page_size(list_query.limit);
+    let cursor = pagination::parse_cursor(list_query.cursor.as_ref())?;
+    let user_id = match users::table
+        .filter(users::username.eq(path.into_inner()))
+        .select(users::id)
+        .first::<i64>(&mut connection)
+    {
+        Ok(u) => u,
+        Err(_) => {
+            return Err(ErrorResponse::new(
+                StatusCode::NOT_FOUND,
+                "User not found".to_string(),
+                Some("user_not_found".to_string()),
+            ));
+        }
+    };
+    let load_failed = |e: diesel::result::Error| {
+        ErrorResponse::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to load connections: {}", e),
+            Some("load_connections_failed".to_string()),
+        )
+    };
+
+    let mut query = connections::table
+        .inner_join(users::table.on(users::id.eq(connections::connected_user_id)))
+        .filter(connections::user_id.eq(user_id))
+        .select((
+            connections::created_at,
+            connections::id,
+            users::id,
+            users::username,
+            users::name,
+            users::profile_picture,
+        ))
+        .order((connections::created_at.desc(), connections::id.desc()))
+        .limit(limit + 1)
+        .into_boxed();
+    if let Some(c) = cursor {
+        query = query.filter(
+            connections::created_at
+                .lt(c.created_at)
+                .or(connections::created_at
+                    .eq(c.created_at)
+                    .and(connections::id.lt(c.id))),
+        );
+    }
+    let rows = query
+        .load::<(DateTime<Utc>, i64, i64, String, String, Option<String>)>(&mut connection)
+        .map_err(load_failed)?;
+    let page = Page::new(rows, limit, |(created_at, id, _, _, _, _)| {
+        Cursor::new(*created_at, *id)
+    });
+    let meta = page.meta();
+    let results: Vec<ConnectionEntry> = page
+        .items
+        .into_iter()
+        .map(
+            |(connected_at, _, id, username, name, profile_picture)| ConnectionEntry {
+                id,
+                username,
+                name,
+                profile_picture,
+                connected_at,
+            },
+        )
+        .collect();
+    Ok(OkResponse::with_meta(
+        "Connections found".to_string(),
+        Some(serde_json::to_value(results).unwrap()),
+        meta,
+    ))
+}
+
+pub fn init(config: &mut ServiceConfig) {
+    config.service(
+        web::scope("/connection")
+            .service(invite)
+            .service(get_invitations)
+            .service(accept_invitation)
+            .service(decline_invitation)
+            .service(withdraw_invitation)
+            .service(remove_connection)
+            .service(get_degree),
+    );
+}
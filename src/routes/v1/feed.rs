@@ -0,0 +1,207 @@
+use crate::{
+    db::{DbPool, DbPooled},
+    models::{ImpressionKind, Post},
+    pagination::{self, Cursor, Page},
+    response::{ErrorResponse, OkResponse},
+    routes::v1::{
+        analytics,
+        post::{to_post_results, visible_to},
+    },
+    schema::{company_follows, post_tags, tag_follows, user_mutes},
+};
+use actix_web::{
+    get,
+    http::StatusCode,
+    web::{self, Data, ServiceConfig},
+    HttpRequest, HttpResponse, Result,
+};
+use diesel::{
+    BoolExpressionMethods, ExpressionMethods, NullableExpressionMethods, QueryDsl, QueryResult,
+    RunQueryDsl, Table,
+};
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+struct FeedQuery {
+    user_id: Option<i64>,
+    cursor: Option<String>,
+    limit: Option<i64>,
+}
+
+/// Up to `limit + 1` posts for `viewer_id`'s home feed after `cursor`, newest
+/// first: posts of users and companies they follow, posts with tags they follow
+/// and their own.
+fn load_feed(
+    conn: &mut DbPooled,
+    viewer_id: i64,
+    cursor: Option<Cursor>,
+    limit: i64,
+) -> QueryResult<Vec<(Post, String, String)>> {
+    use crate::schema::follows::dsl::{followed_user_id, following_user_id, follows};
+    use crate::schema::posts::dsl::*;
+    use crate::schema::users::dsl::{name, username, users};
+
+    let followed = follows
+        .filter(following_user_id.eq(viewer_id))
+        .select(followed_user_id);
+    let followed_tags = tag_follows::table
+        .filter(tag_follows::user_id.eq(viewer_id))
+        .select(tag_follows::tag_id);
+    let tagged = post_tags::table
+        .filter(post_tags::tag_id.eq_any(followed_tags))
+        .select(post_tags::post_id);
+    let followed_companies = company_follows::table
+        .filter(company_follows::user_id.eq(viewer_id))
+        .select(company_follows::company_id.nullable());
+    let muted = user_mutes::table
+        .filter(user_mutes::muter_id.eq(viewer_id))
+        .select(user_mutes::muted_id);
+    let mut query = posts
+        .inner_join(users)
+        .filter(
+            user_id
+                .eq_any(followed)
+                .or(user_id.eq(viewer_id))
+                .or(id.eq_any(tagged))
+                .or(company_id.eq_any(followed_companies)),
+        )
+        .filter(user_id.ne_all(muted))
+        .filter(visible_to(Some(viewer_id)))
+        .select((posts::all_columns(), username, name))
+        .order((created_at.desc(), id.desc()))
+        .limit(limit + 1)
+        .into_boxed();
+    if let Some(c) = cursor {
+        query = query.filter(
+            created_at
+                .lt(c.created_at)
+                .or(created_at.eq(c.created_at).and(id.lt(c.id))),
+        );
+    }
+    query.load(conn)
+}
+
+#[get("/feed")]
+async fn get_feed(req: HttpRequest, data: Data<DbPool>) -> Result<HttpResponse, ErrorResponse> {
+    let mut connection = match data.get() {
+        Ok(conn) => conn,
+        Err(e) => {
+            return Err(ErrorResponse::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to get db connection from pool: {}", e),
+                Some("db_connection_failed".to_string()),
+            ));
+        }
+    };
+    let feed_query = web::Query::<FeedQuery>::from_query(req.query_string()).map_err(|_| {
+        ErrorResponse::new(
+            StatusCode::BAD_REQUEST,
+            "Invalid query".to_string(),
+            Some("invalid_query".to_string()),
+        )
+    })?;
+    let viewer_id = match feed_query.user_id {
+        Some(u) => u,
+        None => {
+            return Err(ErrorResponse::new(
+                StatusCode::BAD_REQUEST,
+                "User id is required".to_string(),
+                Some("user_id_required".to_string()),
+            ));
+        }
+    };
+    let limit = pagination::page_size(feed_query.limit);
+    let cursor = pagination::parse_cursor(feed_query.cursor.as_ref())?;
+
+    let results = load_feed(&mut connection, viewer_id, cursor, limit).map_err(|e| {
+        ErrorResponse::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to load feed: {}", e),
+            Some("load_feed_failed".to_string()),
+        )
+    })?;
+    let page = Page::new(results, limit, |(p, _, _)| {
+        Cursor::new(p.created_at.unwrap(), p.id.unwrap())
+    });
+    let meta = page.meta();
+    analytics::track(
+        &mut connection,
+        &req,
+        &page.items.iter().map(|(p, _, _)| p).collect::<Vec<_>>(),
+        Some(viewer_id),
+        ImpressionKind::Impression,
+    );
+    let results = to_post_results(&mut connection, page.items, Some(viewer_id)).map_err(|e| {
+        ErrorResponse::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to load posts: {}", e),
+            Some("load_posts_failed".to_string()),
+        )
+    })?;
+    Ok(OkResponse::with_meta(
+        "Feed found".to_string(),
+        Some(serde_json::to_value(results).unwrap()),
+        meta,
+    ))
+}
+
+pub fn init(config: &mut ServiceConfig) {
+    config.service(get_feed);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        models::Visibility,
+        schema::{company, follows, posts},
+        testing::{insert_post, insert_user, test_connection},
+    };
+
+    fn feed_ids(conn: &mut DbPooled, viewer_id: i64) -> Vec<i64> {
+        load_feed(conn, viewer_id, None, 50)
+            .unwrap()
+            .into_iter()
+            .map(|(p, _, _)| p.id.unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn followers_only_company_posts_go_to_company_followers() {
+        let Some(mut conn) = test_connection() else {
+            return;
+        };
+        let admin = insert_user(&mut conn, "feed_admin");
+        let admin_follower = insert_user(&mut conn, "feed_admin_follower");
+        let company_follower = insert_user(&mut conn, "feed_company_follower");
+        let company_id: i64 = diesel::insert_into(company::table)
+            .values(company::name.eq("Feed Co"))
+            .returning(company::id)
+            .get_result(&mut conn)
+            .unwrap();
+        diesel::insert_into(follows::table)
+            .values((
+                follows::following_user_id.eq(admin_follower),
+                follows::followed_user_id.eq(admin),
+            ))
+            .execute(&mut conn)
+            .unwrap();
+        diesel::insert_into(company_follows::table)
+            .values((
+                company_follows::user_id.eq(company_follower),
+                company_follows::company_id.eq(company_id),
+            ))
+            .execute(&mut conn)
+            .unwrap();
+        let own = insert_post(&mut conn, admin, "admin's own", Visibility::Followers);
+        let announcement = insert_post(&mut conn, admin, "announcement", Visibility::Followers);
+        diesel::update(posts::table.find(announcement))
+            .set(posts::company_id.eq(company_id))
+            .execute(&mut conn)
+            .unwrap();
+
+        assert_eq!(feed_ids(&mut conn, admin_follower), [own]);
+        assert_eq!(feed_ids(&mut conn, company_follower), [announcement]);
+        assert_eq!(feed_ids(&mut conn, admin), [announcement, own]);
+    }
+}
@@ -1,13 +1,59 @@
 use actix_web::{
+    get,
     http::StatusCode,
     post,
     web::{self, Data, ServiceConfig},
-    HttpResponse, Result,
+    HttpRequest, HttpResponse, Result,
 };
-use diesel::{ExpressionMethods, QueryDsl, RunQueryDsl};
-use serde::Deserialize;
+use chrono::{DateTime, Utc};
+use diesel::{
+    dsl::{exists, select},
+    pg::Pg,
+    BoolExpressionMethods, Connection, ExpressionMethods, OptionalExtension, QueryDsl, QueryResult,
+    RunQueryDsl,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    db::{DbPool, DbPooled},
+    models::{FollowRequest, User},
+    pagination::{self, Cursor, Page},
+    response::{ErrorResponse, OkResponse},
+    routes::v1::{
+        block::check_not_blocked,
+        notification::{notify, KIND_FOLLOW_ACCEPTED, KIND_FOLLOW_REQUEST},
+    },
+    schema::{follow_requests, follows, users},
+};
+
+#[derive(Deserialize)]
+struct FollowListQuery {
+    viewer_id: Option<i64>,
+    cursor: Option<String>,
+    limit: Option<i64>,
+}
 
-use crate::{db::DbPool, models::User, response::ErrorResponse};
+/// A user in a followers or following list. The flags describe the
+/// relationship with the caller and are false for anonymous callers.
+#[derive(Serialize)]
+struct FollowListEntry {
+    id: i64,
+    username: String,
+    name: String,
+    profile_picture: Option<String>,
+    followed_at: DateTime<Utc>,
+    /// The caller follows this user.
+    is_following: bool,
+    /// This user follows the caller.
+    follows_you: bool,
+}
+
+#[derive(Clone, Copy)]
+enum FollowList {
+    Followers,
+    Following,
+}
 
 #[derive(Deserialize, Debug)]
 struct FollowForm {
@@ -15,6 +61,55 @@ struct FollowForm {
     followed_user_id: i64,
 }
 
+/// Where one user stands with another after a follow or unfollow.
+#[derive(Serialize)]
+pub struct Relationship {
+    user_id: i64,
+    followed_user_id: i64,
+    /// `user_id` follows `followed_user_id`.
+    following: bool,
+    /// `followed_user_id` follows `user_id` back.
+    followed_by: bool,
+    /// `user_id` asked to follow the private account and awaits approval.
+    requested: bool,
+    /// Followers of `followed_user_id`.
+    follower_count: i64,
+}
+
+pub fn relationship(
+    conn: &mut DbPooled,
+    user_id: i64,
+    followed_user_id: i64,
+) -> QueryResult<Relationship> {
+    let follows_of = |from: i64, to: i64| {
+        follows::table
+            .filter(follows::following_user_id.eq(from))
+            .filter(follows::followed_user_id.eq(to))
+    };
+    let following = select(exists(follows_of(user_id, followed_user_id))).get_result(conn)?;
+    let followed_by = select(exists(follows_of(followed_user_id, user_id))).get_result(conn)?;
+    let requested = select(exists(
+        follow_requests::table
+            .filter(follow_requests::requester_id.eq(user_id))
+            .filter(follow_requests::target_user_id.eq(followed_user_id)),
+    ))
+    .get_result(conn)?;
+    let follower_count = users::table
+        .find(followed_user_id)
+        .select(users::follower_count)
+        .first(conn)?;
+    Ok(Relationship {
+        user_id,
+        followed_user_id,
+        following,
+        followed_by,
+        requested,
+        follower_count,
+    })
+}
+
+/// Follows a user, or asks to if their account is private. Following someone
+/// already followed or asked changes nothing.
 #[post("/follow")]
 async fn follow(
     form: web::Json<FollowForm>,
@@ -70,12 +165,55 @@ async fn follow(
             ));
         }
     };
-    diesel::insert_into(follows)
-        .values((
-            following_user_id.eq(following_user.id.unwrap()),
-            followed_user_id.eq(followed_user.id.unwrap()),
-        ))
-        .execute(&mut connection)
+    let follower = following_user.id.unwrap();
+    let followed = followed_user.id.unwrap();
+    check_not_blocked(&mut connection, follower, followed)?;
+    let (message, state) = connection
+        .transaction::<_, diesel::result::Error, _>(|conn| {
+            let message = if !followed_user.is_private {
+                let inserted = diesel::insert_into(follows)
+                    .values((
+                        following_user_id.eq(follower),
+                        followed_user_id.eq(followed),
+                    ))
+                    .on_conflict((following_user_id, followed_user_id))
+                    .do_nothing()
+                    .execute(conn)?;
+                if inserted == 0 {
+                    "Already following user"
+                } else {
+                    "User followed"
+                }
+            } else if select(exists(
+                follows
+                    .filter(following_user_id.eq(follower))
+                    .filter(followed_user_id.eq(followed)),
+            ))
+            .get_result::<bool>(conn)?
+            {
+                "Already following user"
+            } else {
+                let inserted = diesel::insert_into(follow_requests::table)
+                    .values(FollowRequest {
+                        requester_id: follower,
+                        target_user_id: followed,
+                        ..Default::default()
+                    })
+                    .on_conflict((
+                        follow_requests::requester_id,
+                        follow_requests::target_user_id,
+                    ))
+                    .do_nothing()
+                    .execute(conn)?;
+                if inserted == 0 {
+                    "Follow already requested"
+                } else {
+                    notify(conn, followed, follower, KIND_FOLLOW_REQUEST, None)?;
+                    "Follow requested"
+                }
+            };
+            Ok((message, relationship(conn, follower, followed)?))
+        })
         .map_err(|e| {
             ErrorResponse::new(
                 StatusCode::INTERNAL_SERVER_ERROR,
@@ -83,9 +221,13 @@ async fn follow(
                 Some("follow_user_failed".to_string()),
             )
         })?;
-    Ok(HttpResponse::Ok().finish())
+    Ok(OkResponse::new(
+        message.to_string(),
+        Some(serde_json::to_value(state).unwrap()),
+    ))
 }
 
+/// Unfollows a user. Unfollowing someone not followed changes nothing.
 #[post("/unfollow")]
 async fn unfollow(
     form: web::Json<FollowForm>,
@@ -141,12 +283,15 @@ async fn unfollow(
             ));
         }
     };
-    diesel::delete(
+    let (deleted, state) = diesel::delete(
         follows
             .filter(following_user_id.eq(following_user.id.unwrap()))
             .filter(followed_user_id.eq(followed_user.id.unwrap())),
     )
     .execute(&mut connection)
+    .and_then(|deleted| {
+        relationship(&mut connection, form.user_id, form.followed_user_id).map(|r| (deleted, r))
+    })
     .map_err(|e| {
         ErrorResponse::new(
             StatusCode::INTERNAL_SERVER_ERROR,
@@ -154,9 +299,452 @@ async fn unfollow(
             Some("unfollow_user_failed".to_string()),
         )
     })?;
-    Ok(HttpResponse::Ok().finish())
+    let message = if deleted == 0 {
+        "Not following user"
+    } else {
+        "User unfollowed"
+    };
+    Ok(OkResponse::new(
+        message.to_string(),
+        Some(serde_json::to_value(state).unwrap()),
+    ))
+}
+
+/// Lists who follows `username`, or whom they follow, most recent first.
+async fn follow_list(
+    req: HttpRequest,
+    username: String,
+    data: Data<DbPool>,
+    list: FollowList,
+) -> Result<HttpResponse, ErrorResponse> {
+    let mut connection = match data.get() {
+        Ok(conn) => conn,
+        Err(e) => {
+            return Err(ErrorResponse::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to get db connection from pool: {}", e),
+                Some("db_connection_failed".to_string()),
+            ));
+        }
+    };
+    let list_query =
+        web::Query::<FollowListQuery>::from_query(req.query_string()).map_err(|_| {
+            ErrorResponse::new(
+                StatusCode::BAD_REQUEST,
+                "Invalid query".to_string(),
+                Some("invalid_query".to_string()),
+            )
+        })?;
+    let limit = pagination::page_size(list_query.limit);
+    let cursor = pagination::parse_cursor(list_query.cursor.as_ref())?;
+    let user_id = match users::table
+        .filter(users::username.eq(&username))
+        .select(users::id)
+        .first::<i64>(&mut connection)
+    {
+        Ok(u) => u,
+        Err(_) => {
+            return Err(ErrorResponse::new(
+                StatusCode::NOT_FOUND,
+                "User not found".to_string(),
+                Some("user_not_found".to_string()),
+            ));
+        }
+    };
+    let load_failed = |e: diesel::result::Error| {
+        ErrorResponse::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to load follows: {}", e),
+            Some("load_follows_failed".to_string()),
+        )
+    };
+
+    // Selects (followed at, follow id, listed user) for either direction.
+    let mut query: follows::BoxedQuery<'_, Pg, _> = match list {
+        FollowList::Followers => follows::table
+            .filter(follows::followed_user_id.eq(user_id))
+            .select((follows::created_at, follows::id, follows::following_user_id))
+            .into_boxed(),
+        FollowList::Following => follows::table
+            .filter(follows::following_user_id.eq(user_id))
+            .select((follows::created_at, follows::id, follows::followed_user_id))
+            .into_boxed(),
+    };
+    query = query
+        .order((follows::created_at.desc(), follows::id.desc()))
+        .limit(limit + 1);
+    if let Some(c) = cursor {
+        query = query.filter(
+            follows::created_at.lt(c.created_at).or(follows::created_at
+                .eq(c.created_at)
+                .and(follows::id.lt(c.id))),
+        );
+    }
+    let rows = query
+        .load::<(DateTime<Utc>, i64, i64)>(&mut connection)
+        .map_err(load_failed)?;
+    let page = Page::new(rows, limit, |(created_at, id, _)| {
+        Cursor::new(*created_at, *id)
+    });
+    let meta = page.meta();
+
+    let ids: Vec<i64> = page.items.iter().map(|(_, _, u)| *u).collect();
+    let mut listed: HashMap<i64, User> = users::table
+        .filter(users::id.eq_any(&ids))
+        .load::<User>(&mut connection)
+        .map_err(load_failed)?
+        .into_iter()
+        .map(|u| (u.id.unwrap(), u))
+        .collect();
+    let (following, followers): (HashSet<i64>, HashSet<i64>) = match list_query.viewer_id {
+        Some(v) => (
+            follows::table
+                .filter(follows::following_user_id.eq(v))
+                .filter(follows::followed_user_id.eq_any(&ids))
+                .select(follows::followed_user_id)
+                .load::<i64>(&mut connection)
+                .map_err(load_failed)?
+                .into_iter()
+                .collect(),
+            follows::table
+                .filter(follows::followed_user_id.eq(v))
+                .filter(follows::following_user_id.eq_any(&ids))
+                .select(follows::following_user_id)
+                .load::<i64>(&mut connection)
+                .map_err(load_failed)?
+                .into_iter()
+                .collect(),
+        ),
+        None => (HashSet::new(), HashSet::new()),
+    };
+    let results: Vec<FollowListEntry> = page
+        .items
+        .into_iter()
+        .filter_map(|(followed_at, _, u)| {
+            listed.remove(&u).map(|user| FollowListEntry {
+                id: u,
+                username: user.username,
+                name: user.name,
+                profile_picture: user.profile_picture,
+                followed_at,
+                is_following: following.contains(&u),
+                follows_you: followers.contains(&u),
+            })
+        })
+        .collect();
+    Ok(OkResponse::with_meta(
+        "Users found".to_string(),
+        Some(serde_json::to_value(results).unwrap()),
+        meta,
+    ))
+}
+
+#[get("/{username}/followers")]
+pub async fn get_followers(
+    req: HttpRequest,
+    path: web::Path<String>,
+    data: Data<DbPool>,
+) -> Result<HttpResponse, ErrorResponse> {
+    follow_list(req, path.into_inner(), data, FollowList::Followers).await
+}
+
+#[get("/{username}/following")]
+pub async fn get_following(
+    req: HttpRequest,
+    path: web::Path<String>,
+    data: Data<DbPool>,
+) -> Result<HttpResponse, ErrorResponse> {
+    follow_list(req, path.into_inner(), data, FollowList::Following).await
+}
+
+#[derive(Deserialize)]
+struct FollowRequestQuery {
+    user_id: i64,
+    /// Lists the requests the user has sent instead of the ones waiting for them.
+    sent: Option<bool>,
+    cursor: Option<String>,
+    limit: Option<i64>,
+}
+
+/// A pending request together with the other user: the requester for received
+/// requests, the private account for sent ones.
+#[derive(Serialize)]
+struct FollowRequestEntry {
+    id: i64,
+    requested_at: DateTime<Utc>,
+    user_id: i64,
+    username: String,
+    name: String,
+    profile_picture: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+struct FollowRequestForm {
+    user_id: i64,
+    request_id: i64,
+}
+
+/// Lists pending follow requests of a user, most recent first.
+#[get("/follow/requests")]
+async fn get_follow_requests(
+    req: HttpRequest,
+    data: Data<DbPool>,
+) -> Result<HttpResponse, ErrorResponse> {
+    let mut connection = match data.get() {
+        Ok(conn) => conn,
+        Err(e) => {
+            return Err(ErrorResponse::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to get db connection from pool: {}", e),
+                Some("db_connection_failed".to_string()),
+            ));
+        }
+    };
+    let request_query =
+        web::Query::<FollowRequestQuery>::from_query(req.query_string()).map_err(|_| {
+            ErrorResponse::new(
+                StatusCode::BAD_REQUEST,
+                "Invalid query".to_string(),
+                Some("invalid_query".to_string()),
+            )
+        })?;
+    let limit = pagination::page_size(request_query.limit);
+    let cursor = pagination::parse_cursor(request_query.cursor.as_ref())?;
+    let load_failed = |e: diesel::result::Error| {
+        ErrorResponse::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to load follow requests: {}", e),
+            Some("load_follow_requests_failed".to_string()),
+        )
+    };
+
+    // Selects (requested at, request id, other user) for either direction.
+    let mut query: follow_requests::BoxedQuery<'_, Pg, _> = if request_query.sent == Some(true) {
+        follow_requests::table
+            .filter(follow_requests::requester_id.eq(request_query.user_id))
+            .select((
+                follow_requests::created_at,
+                follow_requests::id,
+                follow_requests::target_user_id,
+            ))
+            .into_boxed()
+    } else {
+        follow_requests::table
+            .filter(follow_requests::target_user_id.eq(request_query.user_id))
+            .select((
+                follow_requests::created_at,
+                follow_requests::id,
+                follow_requests::requester_id,
+            ))
+            .into_boxed()
+    };
+    query = query
+        .order((
+            follow_requests::created_at.desc(),
+            follow_requests::id.desc(),
+        ))
+        .limit(limit + 1);
+    if let Some(c) = cursor {
+        query = query.filter(
+            follow_requests::created_at
+                .lt(c.created_at)
+                .or(follow_requests::created_at
+                    .eq(c.created_at)
+                    .and(follow_requests::id.lt(c.id))),
+        );
+    }
+    let rows = query
+        .load::<(DateTime<Utc>, i64, i64)>(&mut connection)
+        .map_err(load_failed)?;
+    let page = Page::new(rows, limit, |(created_at, id, _)| {
+        Cursor::new(*created_at, *id)
+    });
+    let meta = page.meta();
+
+    let ids: Vec<i64> = page.items.iter().map(|(_, _, u)| *u).collect();
+    let listed: HashMap<i64, User> = users::table
+        .filter(users::id.eq_any(&ids))
+        .load::<User>(&mut connection)
+        .map_err(load_failed)?
+        .into_iter()
+        .map(|u| (u.id.unwrap(), u))
+        .collect();
+    let results: Vec<FollowRequestEntry> = page
+        .items
+        .into_iter()
+        .filter_map(|(requested_at, id, u)| {
+            listed.get(&u).map(|user| FollowRequestEntry {
+                id,
+                requested_at,
+                user_id: u,
+                username: user.username.clone(),
+                name: user.name.clone(),
+                profile_picture: user.profile_picture.clone(),
+            })
+        })
+        .collect();
+    Ok(OkResponse::with_meta(
+        "Follow requests found".to_string(),
+        Some(serde_json::to_value(results).unwrap()),
+        meta,
+    ))
+}
+
+fn follow_request_not_found() -> ErrorResponse {
+    ErrorResponse::new(
+        StatusCode::NOT_FOUND,
+        "Follow request not found".to_string(),
+        Some("follow_request_not_found".to_string()),
+    )
+}
+
+/// Approves a request sent to `user_id`: the requester becomes a follower and
+/// is notified. The response describes the requester's side.
+#[post("/follow/requests/accept")]
+async fn accept_follow_request(
+    form: web::Json<FollowRequestForm>,
+    data: Data<DbPool>,
+) -> Result<HttpResponse, ErrorResponse> {
+    let mut connection = match data.get() {
+        Ok(conn) => conn,
+        Err(e) => {
+            return Err(ErrorResponse::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to get db connection from pool: {}", e),
+                Some("db_connection_failed".to_string()),
+            ));
+        }
+    };
+    let state = connection
+        .transaction::<_, diesel::result::Error, _>(|conn| {
+            let request = diesel::delete(
+                follow_requests::table
+                    .filter(follow_requests::id.eq(form.request_id))
+                    .filter(follow_requests::target_user_id.eq(form.user_id)),
+            )
+            .get_result::<FollowRequest>(conn)
+            .optional()?;
+            let request = match request {
+                Some(r) => r,
+                None => return Ok(None),
+            };
+            diesel::insert_into(follows::table)
+                .values((
+                    follows::following_user_id.eq(request.requester_id),
+                    follows::followed_user_id.eq(form.user_id),
+                ))
+                .on_conflict((follows::following_user_id, follows::followed_user_id))
+                .do_nothing()
+                .execute(conn)?;
+            notify(
+                conn,
+                request.requester_id,
+                form.user_id,
+                KIND_FOLLOW_ACCEPTED,
+                None,
+            )?;
+            relationship(conn, request.requester_id, form.user_id).map(Some)
+        })
+        .map_err(|e| {
+            ErrorResponse::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to accept follow request: {}", e),
+                Some("accept_follow_request_failed".to_string()),
+            )
+        })?
+        .ok_or_else(follow_request_not_found)?;
+    Ok(OkResponse::new(
+        "Follow request accepted".to_string(),
+        Some(serde_json::to_value(state).unwrap()),
+    ))
+}
+
+/// Deletes a pending request: rejecting one sent to `user_id`, or cancelling
+/// one they sent.
+async fn delete_follow_request(
+    form: web::Json<FollowRequestForm>,
+    data: Data<DbPool>,
+    rejecting: bool,
+) -> Result<HttpResponse, ErrorResponse> {
+    let mut connection = match data.get() {
+        Ok(conn) => conn,
+        Err(e) => {
+            return Err(ErrorResponse::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to get db connection from pool: {}", e),
+                Some("db_connection_failed".to_string()),
+            ));
+        }
+    };
+    let request = follow_requests::table.filter(follow_requests::id.eq(form.request_id));
+    let deleted = if rejecting {
+        diesel::delete(request.filter(follow_requests::target_user_id.eq(form.user_id)))
+            .execute(&mut connection)
+    } else {
+        diesel::delete(request.filter(follow_requests::requester_id.eq(form.user_id)))
+            .execute(&mut connection)
+    }
+    .map_err(|e| {
+        ErrorResponse::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to delete follow request: {}", e),
+            Some("delete_follow_request_failed".to_string()),
+        )
+    })?;
+    if deleted == 0 {
+        return Err(follow_request_not_found());
+    }
+    let message = if rejecting {
+        "Follow request rejected"
+    } else {
+        "Follow request cancelled"
+    };
+    Ok(OkResponse::new(message.to_string(), None))
+}
+
+/// Rejects a request sent to `user_id`. The requester is not told.
+#[post("/follow/requests/reject")]
+async fn reject_follow_request(
+    form: web::Json<FollowRequestForm>,
+    data: Data<DbPool>,
+) -> Result<HttpResponse, ErrorResponse> {
+    delete_follow_request(form, data, true).await
+}
+
+/// Withdraws a request `user_id` sent.
+#[post("/follow/requests/cancel")]
+async fn cancel_follow_request(
+    form: web::Json<FollowRequestForm>,
+    data: Data<DbPool>,
+) -> Result<HttpResponse, ErrorResponse> {
+    delete_follow_request(form, data, false).await
+}
+
+/// Turns every pending request to `user_id` into a follow, for when they make
+/// their account public.
+pub fn accept_all_follow_requests(conn: &mut DbPooled, user_id: i64) -> QueryResult<usize> {
+    diesel::insert_into(follows::table)
+        .values(
+            follow_requests::table
+                .filter(follow_requests::target_user_id.eq(user_id))
+                .select((
+                    follow_requests::requester_id,
+                    follow_requests::target_user_id,
+                )),
+        )
+        .into_columns((follows::following_user_id, follows::followed_user_id))
+        .on_conflict_do_nothing()
+        .execute(conn)?;
+    diesel::delete(follow_requests::table.filter(follow_requests::target_user_id.eq(user_id)))
+        .execute(conn)
 }
 
 pub fn init(config: &mut ServiceConfig) {
-    config.service(follow).service(unfollow);
+    config
+        .service(follow)
+        .service(unfollow)
+        .service(get_follow_requests)
+        .service(accept_follow_request)
+        .service(reject_follow_request)
+        .service(cancel_follow_request);
 }
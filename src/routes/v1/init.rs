@@ -1,4 +1,7 @@
-use super::{company, follow, position, post, user};
+use super::{
+    analytics, block, bookmark, company, connection, feed, follow, moderation, notification,
+    position, post, report, tag, user,
+};
 use actix_web::web::{self, ServiceConfig};
 
 pub fn init(cfg: &mut ServiceConfig) {
@@ -8,6 +11,15 @@ pub fn init(cfg: &mut ServiceConfig) {
             .configure(company::init)
             .configure(position::init)
             .configure(post::init)
-            .configure(follow::init),
+            .configure(follow::init)
+            .configure(block::init)
+            .configure(connection::init)
+            .configure(feed::init)
+            .configure(tag::init)
+            .configure(notification::init)
+            .configure(analytics::init)
+            .configure(bookmark::init)
+            .configure(report::init)
+            .configure(moderation::init),
     );
 }
@@ -0,0 +1,201 @@
+use crate::{
+    db::DbPooled,
+    models::PreviewStatus,
+    preview::{self, Preview, MAX_LINKS_PER_POST},
+    schema::{link_previews, post_links},
+    text,
+};
+use chrono::{Duration, Utc};
+use diesel::{
+    BoolExpressionMethods, Connection, ExpressionMethods, QueryDsl, QueryResult, RunQueryDsl,
+};
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// Previews claimed by one worker tick.
+const FETCH_BATCH_SIZE: i64 = 5;
+/// A preview is given up on after this many failed fetches.
+const MAX_FETCH_ATTEMPTS: i32 = 3;
+const RETRY_AFTER_MINUTES: i64 = 5;
+
+#[derive(Serialize, Clone)]
+pub struct LinkPreviewEntity {
+    pub url: String,
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub image_url: Option<String>,
+    pub site_name: Option<String>,
+}
+
+/// Replaces the links of a post with the first `MAX_LINKS_PER_POST` distinct links
+/// in `body`. Previews are cached per normalized URL; links seen for the first time
+/// are queued for the background worker.
+pub fn sync_post_links(conn: &mut DbPooled, post_id: i64, body: &str) -> QueryResult<()> {
+    diesel::delete(post_links::table.filter(post_links::post_id.eq(post_id))).execute(conn)?;
+    let mut urls: Vec<String> = vec![];
+    for link in text::extract_urls(body) {
+        if let Some(url) = preview::normalize_url(&link) {
+            if !urls.contains(&url) {
+                urls.push(url);
+            }
+        }
+        if urls.len() == MAX_LINKS_PER_POST {
+            break;
+        }
+    }
+    if urls.is_empty() {
+        return Ok(());
+    }
+
+    let new_previews: Vec<_> = urls.iter().map(|u| link_previews::url.eq(u)).collect();
+    diesel::insert_into(link_previews::table)
+        .values(&new_previews)
+        .on_conflict(link_previews::url)
+        .do_nothing()
+        .execute(conn)?;
+    let ids: HashMap<String, i64> = link_previews::table
+        .filter(link_previews::url.eq_any(&urls))
+        .select((link_previews::url, link_previews::id))
+        .load::<(String, i64)>(conn)?
+        .into_iter()
+        .collect();
+    let new_links: Vec<_> = urls
+        .iter()
+        .enumerate()
+        .filter_map(|(i, u)| {
+            ids.get(u).map(|p| {
+                (
+                    post_links::post_id.eq(post_id),
+                    post_links::link_preview_id.eq(*p),
+                    post_links::position.eq(i as i32),
+                )
+            })
+        })
+        .collect();
+    diesel::insert_into(post_links::table)
+        .values(&new_links)
+        .execute(conn)?;
+    Ok(())
+}
+
+/// Fetched previews of the given posts keyed by post id, in order of appearance.
+/// Links that are still pending or could not be fetched are left out.
+pub fn load_previews(
+    conn: &mut DbPooled,
+    post_ids: &[i64],
+) -> QueryResult<HashMap<i64, Vec<LinkPreviewEntity>>> {
+    let rows = post_links::table
+        .inner_join(link_previews::table)
+        .filter(post_links::post_id.eq_any(post_ids))
+        .filter(link_previews::status.eq(PreviewStatus::Ready))
+        .select((
+            post_links::post_id,
+            link_previews::url,
+            link_previews::title,
+            link_previews::description,
+            link_previews::image_url,
+            link_previews::site_name,
+        ))
+        .order((post_links::post_id, post_links::position))
+        .load::<(
+            i64,
+            String,
+            Option<String>,
+            Option<String>,
+            Option<String>,
+            Option<String>,
+        )>(conn)?;
+    let mut result: HashMap<i64, Vec<LinkPreviewEntity>> = HashMap::new();
+    for (p, url, title, description, image_url, site_name) in rows {
+        result.entry(p).or_default().push(LinkPreviewEntity {
+            url,
+            title,
+            description,
+            image_url,
+            site_name,
+        });
+    }
+    Ok(result)
+}
+
+/// A pending preview claimed by the worker.
+pub struct ClaimedPreview {
+    pub id: i64,
+    pub url: String,
+    attempts: i32,
+}
+
+/// Claims a batch of pending previews for fetching. Rows are claimed in a short
+/// transaction (`SKIP LOCKED`, so several workers can run) and fetched outside of
+/// it, without holding a connection; a failed fetch is retried after
+/// `RETRY_AFTER_MINUTES` until `MAX_FETCH_ATTEMPTS`.
+pub fn claim_link_previews(conn: &mut DbPooled) -> QueryResult<Vec<ClaimedPreview>> {
+    let now = Utc::now();
+    let retry_before = now - Duration::minutes(RETRY_AFTER_MINUTES);
+    conn.transaction::<_, diesel::result::Error, _>(|conn| {
+        let rows = link_previews::table
+            .filter(link_previews::status.eq(PreviewStatus::Pending))
+            .filter(
+                link_previews::fetched_at
+                    .is_null()
+                    .or(link_previews::fetched_at.lt(retry_before)),
+            )
+            .order(link_previews::created_at)
+            .limit(FETCH_BATCH_SIZE)
+            .select((
+                link_previews::id,
+                link_previews::url,
+                link_previews::attempts,
+            ))
+            .for_update()
+            .skip_locked()
+            .load::<(i64, String, i32)>(conn)?;
+        let ids: Vec<i64> = rows.iter().map(|(id, _, _)| *id).collect();
+        diesel::update(link_previews::table.filter(link_previews::id.eq_any(&ids)))
+            .set((
+                link_previews::attempts.eq(link_previews::attempts + 1),
+                link_previews::fetched_at.eq(now),
+            ))
+            .execute(conn)?;
+        Ok(rows
+            .into_iter()
+            .map(|(id, url, attempts)| ClaimedPreview { id, url, attempts })
+            .collect())
+    })
+}
+
+/// Saves the outcome of fetching claimed previews. Returns the number of previews
+/// that became ready.
+pub fn store_link_previews(
+    conn: &mut DbPooled,
+    fetched: Vec<(ClaimedPreview, Result<Preview, String>)>,
+) -> QueryResult<usize> {
+    let mut ready = 0;
+    for (claimed, result) in fetched {
+        let target = link_previews::table.filter(link_previews::id.eq(claimed.id));
+        match result {
+            Ok(p) => {
+                diesel::update(target)
+                    .set((
+                        link_previews::status.eq(PreviewStatus::Ready),
+                        link_previews::title.eq(p.title),
+                        link_previews::description.eq(p.description),
+                        link_previews::image_url.eq(p.image_url),
+                        link_previews::site_name.eq(p.site_name),
+                        link_previews::fetched_at.eq(Utc::now()),
+                    ))
+                    .execute(conn)?;
+                ready += 1;
+            }
+            Err(e) => {
+                warn!("Failed to fetch link preview for {}: {}", claimed.url, e);
+                if claimed.attempts + 1 >= MAX_FETCH_ATTEMPTS {
+                    diesel::update(target)
+                        .set(link_previews::status.eq(PreviewStatus::Failed))
+                        .execute(conn)?;
+                }
+            }
+        }
+    }
+    Ok(ready)
+}
@@ -0,0 +1,193 @@
+use crate::{
+    db::DbPooled,
+    routes::v1::{
+        block::blocked_user_ids,
+        notification::{self, KIND_MENTION},
+        post::visible_to,
+    },
+    schema::{mentions, posts, users},
+    text,
+};
+use diesel::{dsl::exists, select, ExpressionMethods, QueryDsl, QueryResult, RunQueryDsl};
+use serde::Serialize;
+use std::collections::HashMap;
+
+#[derive(Serialize, Clone)]
+pub struct MentionEntity {
+    pub user_id: i64,
+    pub username: String,
+    pub start: i32,
+    pub end: i32,
+}
+
+/// Replaces the mentions of a post with the `@username`s in `body` that resolve to
+/// users, except users blocked either way by the author. With `notify`, users that
+/// were not mentioned in the previous version are notified; unpublished posts pass
+/// `false` and use `notify_mentions` once published. Either way only users who can
+/// read the post are notified, so mentions in private or followers-only posts reach
+/// the author's followers alone.
+pub fn sync_post_mentions(
+    conn: &mut DbPooled,
+    post_id: i64,
+    author_id: i64,
+    body: &str,
+    notify: bool,
+) -> QueryResult<()> {
+    let found = text::extract_mentions(body);
+    let previous = diesel::delete(mentions::table.filter(mentions::post_id.eq(post_id)))
+        .returning(mentions::user_id)
+        .get_results::<i64>(conn)?;
+    if found.is_empty() {
+        return Ok(());
+    }
+    let names: Vec<&String> = found.iter().map(|m| &m.username).collect();
+    let blocked = blocked_user_ids(conn, author_id)?;
+    let resolved: HashMap<String, i64> = users::table
+        .filter(users::username.eq_any(names))
+        .filter(users::id.ne_all(blocked))
+        .select((users::username, users::id))
+        .load::<(String, i64)>(conn)?
+        .into_iter()
+        .collect();
+    let new_mentions: Vec<_> = found
+        .iter()
+        .filter_map(|m| {
+            resolved.get(&m.username).map(|u| {
+                (
+                    mentions::post_id.eq(post_id),
+                    mentions::user_id.eq(*u),
+                    mentions::start_offset.eq(m.start as i32),
+                    mentions::end_offset.eq(m.end as i32),
+                )
+            })
+        })
+        .collect();
+    if new_mentions.is_empty() {
+        return Ok(());
+    }
+    diesel::insert_into(mentions::table)
+        .values(&new_mentions)
+        .execute(conn)?;
+    if !notify {
+        return Ok(());
+    }
+
+    let mut notified = previous;
+    for m in &found {
+        if let Some(u) = resolved.get(&m.username) {
+            if !notified.contains(u) {
+                if can_read(conn, post_id, *u)? {
+                    notification::notify(conn, *u, author_id, KIND_MENTION, Some(post_id))?;
+                }
+                notified.push(*u);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Notifies everyone mentioned in a post who can read it, used when a draft or
+/// scheduled post goes live.
+pub fn notify_mentions(conn: &mut DbPooled, post_id: i64, author_id: i64) -> QueryResult<()> {
+    let mentioned = mentions::table
+        .filter(mentions::post_id.eq(post_id))
+        .select(mentions::user_id)
+        .distinct()
+        .load::<i64>(conn)?;
+    for u in mentioned {
+        if can_read(conn, post_id, u)? {
+            notification::notify(conn, u, author_id, KIND_MENTION, Some(post_id))?;
+        }
+    }
+    Ok(())
+}
+
+/// Whether `user_id` is allowed to see the post, checked once it is published.
+fn can_read(conn: &mut DbPooled, post_id: i64, user_id: i64) -> QueryResult<bool> {
+    select(exists(
+        posts::table
+            .filter(posts::id.eq(post_id))
+            .filter(visible_to(Some(user_id))),
+    ))
+    .get_result(conn)
+}
+
+/// Mentions of the given posts keyed by post id, ordered by position in the body.
+pub fn load_mentions(
+    conn: &mut DbPooled,
+    post_ids: &[i64],
+) -> QueryResult<HashMap<i64, Vec<MentionEntity>>> {
+    let rows = mentions::table
+        .inner_join(users::table)
+        .filter(mentions::post_id.eq_any(post_ids))
+        .select((
+            mentions::post_id,
+            mentions::user_id,
+            users::username,
+            mentions::start_offset,
+            mentions::end_offset,
+        ))
+        .order((mentions::post_id, mentions::start_offset))
+        .load::<(i64, i64, String, i32, i32)>(conn)?;
+    let mut result: HashMap<i64, Vec<MentionEntity>> = HashMap::new();
+    for (p, u, n, s, e) in rows {
+        result.entry(p).or_default().push(MentionEntity {
+            user_id: u,
+            username: n,
+            start: s,
+            end: e,
+        });
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        models::Visibility,
+        schema::{follows, notifications},
+        testing::{insert_post, insert_user, test_connection},
+    };
+
+    fn notified(conn: &mut DbPooled, post_id: i64) -> Vec<i64> {
+        notifications::table
+            .filter(notifications::post_id.eq(post_id))
+            .filter(notifications::kind.eq(KIND_MENTION))
+            .select(notifications::user_id)
+            .order(notifications::user_id)
+            .load(conn)
+            .unwrap()
+    }
+
+    #[test]
+    fn mentions_notify_only_users_who_can_read_the_post() {
+        let Some(mut conn) = test_connection() else {
+            return;
+        };
+        let author = insert_user(&mut conn, "mention_author");
+        let follower = insert_user(&mut conn, "mention_follower");
+        let stranger = insert_user(&mut conn, "mention_stranger");
+        diesel::insert_into(follows::table)
+            .values((
+                follows::following_user_id.eq(follower),
+                follows::followed_user_id.eq(author),
+            ))
+            .execute(&mut conn)
+            .unwrap();
+        let body = "hi @mention_follower and @mention_stranger";
+
+        let public = insert_post(&mut conn, author, body, Visibility::Public);
+        sync_post_mentions(&mut conn, public, author, body, true).unwrap();
+        assert_eq!(notified(&mut conn, public), vec![follower, stranger]);
+
+        let followers_only = insert_post(&mut conn, author, body, Visibility::Followers);
+        sync_post_mentions(&mut conn, followers_only, author, body, true).unwrap();
+        assert_eq!(notified(&mut conn, followers_only), vec![follower]);
+
+        let private = insert_post(&mut conn, author, body, Visibility::Private);
+        sync_post_mentions(&mut conn, private, author, body, false).unwrap();
+        notify_mentions(&mut conn, private, author).unwrap();
+        assert_eq!(notified(&mut conn, private), Vec::<i64>::new());
+    }
+}
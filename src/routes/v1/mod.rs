@@ -1,8 +1,28 @@
+mod analytics;
+mod attachment;
+mod block;
+mod bookmark;
 mod company;
+mod connection;
+mod feed;
 mod follow;
 mod init;
+mod link_preview;
+mod mention;
+mod moderation;
+mod notification;
+mod pin;
+mod poll;
 mod position;
 mod post;
+mod report;
+mod search;
+mod suggestion;
+mod tag;
 mod user;
 
+pub use analytics::rollup_post_stats;
+pub use attachment::get_attachment;
 pub use init::*;
+pub use link_preview::{claim_link_previews, store_link_previews};
+pub use post::publish_due_posts;
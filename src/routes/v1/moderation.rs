@@ -0,0 +1,825 @@
+use crate::{
+    db::{DbPool, DbPooled},
+    models::{
+        ModerationAction, ModerationActionKind, Post, PostStatus, Report, ReportReason,
+        ReportStatus, ReportTargetType, User,
+    },
+    pagination::{self, Cursor, Page},
+    response::{ErrorResponse, OkResponse},
+    routes::v1::{mention::notify_mentions, post::to_post_results},
+    schema::{moderation_actions, moderators, posts, reports, users},
+};
+use actix_web::{
+    get,
+    http::StatusCode,
+    post,
+    web::{self, Data, ServiceConfig},
+    HttpRequest, HttpResponse, Result,
+};
+use chrono::Utc;
+use diesel::{
+    dsl::{exists, select},
+    BoolExpressionMethods, Connection, ExpressionMethods, QueryDsl, QueryResult, RunQueryDsl,
+};
+use serde::Deserialize;
+
+pub const MAX_NOTE_LENGTH: usize = 1000;
+
+#[derive(Deserialize)]
+struct ReportQueueQuery {
+    moderator_id: Option<i64>,
+    /// Defaults to `open`.
+    status: Option<ReportStatus>,
+    target_type: Option<ReportTargetType>,
+    reason: Option<ReportReason>,
+    cursor: Option<String>,
+    limit: Option<i64>,
+}
+
+#[derive(Deserialize)]
+struct ActionQuery {
+    moderator_id: Option<i64>,
+    target_post_id: Option<i64>,
+    target_user_id: Option<i64>,
+    cursor: Option<String>,
+    limit: Option<i64>,
+}
+
+#[derive(Deserialize)]
+struct HeldQuery {
+    moderator_id: Option<i64>,
+    cursor: Option<String>,
+    limit: Option<i64>,
+}
+
+#[derive(Deserialize, Debug)]
+struct ResolveForm {
+    moderator_id: i64,
+    report_id: i64,
+    /// `actioned` or `dismissed`.
+    status: ReportStatus,
+    note: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+struct PostActionForm {
+    moderator_id: i64,
+    post_id: i64,
+    /// Closes this report as actioned along with the action.
+    report_id: Option<i64>,
+    note: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+struct UserActionForm {
+    moderator_id: i64,
+    user_id: i64,
+    report_id: Option<i64>,
+    note: Option<String>,
+}
+
+pub fn is_moderator(conn: &mut DbPooled, user_id: i64) -> QueryResult<bool> {
+    select(exists(
+        moderators::table.filter(moderators::user_id.eq(user_id)),
+    ))
+    .get_result(conn)
+}
+
+/// `is_moderator` as a request guard.
+pub fn check_moderator(conn: &mut DbPooled, user_id: i64) -> Result<(), ErrorResponse> {
+    match is_moderator(conn, user_id) {
+        Ok(true) => Ok(()),
+        Ok(false) => Err(ErrorResponse::new(
+            StatusCode::FORBIDDEN,
+            "Only moderators can do this".to_string(),
+            Some("not_moderator".to_string()),
+        )),
+        Err(e) => Err(ErrorResponse::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to check moderator: {}", e),
+            Some("moderator_check_failed".to_string()),
+        )),
+    }
+}
+
+/// Rejects users whose account is suspended.
+pub fn check_not_suspended(conn: &mut DbPooled, user_id: i64) -> Result<(), ErrorResponse> {
+    let suspended = select(exists(
+        users::table
+            .filter(users::id.eq(user_id))
+            .filter(users::suspended_at.is_not_null()),
+    ))
+    .get_result::<bool>(conn);
+    match suspended {
+        Ok(false) => Ok(()),
+        Ok(true) => Err(ErrorResponse::new(
+            StatusCode::FORBIDDEN,
+            "This account is suspended".to_string(),
+            Some("user_suspended".to_string()),
+        )),
+        Err(e) => Err(ErrorResponse::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to check account status: {}", e),
+            Some("suspension_check_failed".to_string()),
+        )),
+    }
+}
+
+pub fn check_note(note: Option<&String>) -> Result<(), ErrorResponse> {
+    if note.is_some_and(|n| n.chars().count() > MAX_NOTE_LENGTH) {
+        return Err(ErrorResponse::new(
+            StatusCode::BAD_REQUEST,
+            format!("Note must be at most {} characters", MAX_NOTE_LENGTH),
+            Some("note_too_long".to_string()),
+        ));
+    }
+    Ok(())
+}
+
+/// Loads the report an action resolves and checks it is about the same post or
+/// user. Whether it is still open is checked when it is closed.
+fn report_for_action(
+    conn: &mut DbPooled,
+    report_id: Option<i64>,
+    post_id: Option<i64>,
+    user_id: Option<i64>,
+) -> Result<Option<Report>, ErrorResponse> {
+    let report_id = match report_id {
+        Some(r) => r,
+        None => return Ok(None),
+    };
+    let report = match reports::table.find(report_id).first::<Report>(conn) {
+        Ok(r) => r,
+        Err(_) => {
+            return Err(ErrorResponse::new(
+                StatusCode::NOT_FOUND,
+                "Report not found".to_string(),
+                Some("report_not_found".to_string()),
+            ));
+        }
+    };
+    if report.target_post_id != post_id || report.target_user_id != user_id {
+        return Err(ErrorResponse::new(
+            StatusCode::BAD_REQUEST,
+            "Report is about something else".to_string(),
+            Some("report_target_mismatch".to_string()),
+        ));
+    }
+    Ok(Some(report))
+}
+
+/// Closes an open report; returns false if it was already closed.
+fn close_report(
+    conn: &mut DbPooled,
+    report_id: i64,
+    moderator_id: i64,
+    status: ReportStatus,
+) -> QueryResult<bool> {
+    let updated = diesel::update(
+        reports::table
+            .filter(reports::id.eq(report_id))
+            .filter(reports::status.eq(ReportStatus::Open)),
+    )
+    .set((
+        reports::status.eq(status),
+        reports::resolved_by.eq(moderator_id),
+        reports::resolved_at.eq(Utc::now()),
+    ))
+    .execute(conn)?;
+    Ok(updated > 0)
+}
+
+fn report_not_open() -> ErrorResponse {
+    ErrorResponse::new(
+        StatusCode::CONFLICT,
+        "Report has already been resolved".to_string(),
+        Some("report_not_open".to_string()),
+    )
+}
+
+fn post_not_held() -> ErrorResponse {
+    ErrorResponse::new(
+        StatusCode::CONFLICT,
+        "Post is not waiting for review".to_string(),
+        Some("post_not_held".to_string()),
+    )
+}
+
+/// Applies a moderator action and records it in the audit trail, closing
+/// `report` as actioned in the same transaction. `apply` returns false if the
+/// target is already in the requested state; that is only recorded when it
+/// closes a report.
+fn moderate(
+    conn: &mut DbPooled,
+    action: ModerationAction,
+    report: Option<Report>,
+    apply: impl FnOnce(&mut DbPooled) -> QueryResult<bool>,
+) -> Result<bool, ErrorResponse> {
+    let moderator_id = action.moderator_id.unwrap_or_default();
+    let result = conn.transaction::<_, diesel::result::Error, _>(|conn| {
+        if let Some(r) = &report {
+            if !close_report(conn, r.id.unwrap(), moderator_id, ReportStatus::Actioned)? {
+                return Ok(None);
+            }
+        }
+        let applied = apply(conn)?;
+        if applied || report.is_some() {
+            diesel::insert_into(moderation_actions::table)
+                .values(&action)
+                .execute(conn)?;
+        }
+        Ok(Some(applied))
+    });
+    match result {
+        Ok(Some(applied)) => Ok(applied),
+        Ok(None) => Err(report_not_open()),
+        Err(e) => Err(ErrorResponse::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to apply moderation action: {}", e),
+            Some("moderation_action_failed".to_string()),
+        )),
+    }
+}
+
+/// The report queue, oldest first so reports are handled in the order they came in.
+#[get("/reports")]
+async fn get_reports(req: HttpRequest, data: Data<DbPool>) -> Result<HttpResponse, ErrorResponse> {
+    let mut connection = match data.get() {
+        Ok(conn) => conn,
+        Err(e) => {
+            return Err(ErrorResponse::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to get db connection from pool: {}", e),
+                Some("db_connection_failed".to_string()),
+            ));
+        }
+    };
+    let queue_query =
+        web::Query::<ReportQueueQuery>::from_query(req.query_string()).map_err(|_| {
+            ErrorResponse::new(
+                StatusCode::BAD_REQUEST,
+                "Invalid query".to_string(),
+                Some("invalid_query".to_string()),
+            )
+        })?;
+    let moderator_id = match queue_query.moderator_id {
+        Some(m) => m,
+        None => {
+            return Err(ErrorResponse::new(
+                StatusCode::BAD_REQUEST,
+                "Moderator id is required".to_string(),
+                Some("moderator_id_required".to_string()),
+            ));
+        }
+    };
+    check_moderator(&mut connection, moderator_id)?;
+    let limit = pagination::page_size(queue_query.limit);
+    let cursor = pagination::parse_cursor(queue_query.cursor.as_ref())?;
+
+    let mut query = reports::table
+        .filter(reports::status.eq(queue_query.status.unwrap_or_default()))
+        .order((reports::created_at.asc(), reports::id.asc()))
+        .limit(limit + 1)
+        .into_boxed();
+    if let Some(t) = queue_query.target_type {
+        query = query.filter(reports::target_type.eq(t));
+    }
+    if let Some(r) = queue_query.reason {
+        query = query.filter(reports::reason.eq(r));
+    }
+    if let Some(c) = cursor {
+        query = query.filter(
+            reports::created_at.gt(c.created_at).or(reports::created_at
+                .eq(c.created_at)
+                .and(reports::id.gt(c.id))),
+        );
+    }
+    let results = query.load::<Report>(&mut connection).map_err(|e| {
+        ErrorResponse::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to load reports: {}", e),
+            Some("load_reports_failed".to_string()),
+        )
+    })?;
+    let page = Page::new(results, limit, |r| {
+        Cursor::new(r.created_at.unwrap(), r.id.unwrap())
+    });
+    Ok(OkResponse::with_meta(
+        "Reports found".to_string(),
+        Some(serde_json::to_value(&page.items).unwrap()),
+        page.meta(),
+    ))
+}
+
+/// Closes a report without acting on its target, or records that it was dealt
+/// with some other way.
+#[post("/reports/resolve")]
+async fn resolve_report(
+    form: web::Json<ResolveForm>,
+    data: Data<DbPool>,
+) -> Result<HttpResponse, ErrorResponse> {
+    let mut connection = match data.get() {
+        Ok(conn) => conn,
+        Err(e) => {
+            return Err(ErrorResponse::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to get db connection from pool: {}", e),
+                Some("db_connection_failed".to_string()),
+            ));
+        }
+    };
+    check_moderator(&mut connection, form.moderator_id)?;
+    check_note(form.note.as_ref())?;
+    let kind = match form.status {
+        ReportStatus::Actioned => ModerationActionKind::ActionReport,
+        ReportStatus::Dismissed => ModerationActionKind::DismissReport,
+        ReportStatus::Open => {
+            return Err(ErrorResponse::new(
+                StatusCode::BAD_REQUEST,
+                "Status must be actioned or dismissed".to_string(),
+                Some("invalid_report_status".to_string()),
+            ));
+        }
+    };
+    let report = match reports::table
+        .find(form.report_id)
+        .first::<Report>(&mut connection)
+    {
+        Ok(r) => r,
+        Err(_) => {
+            return Err(ErrorResponse::new(
+                StatusCode::NOT_FOUND,
+                "Report not found".to_string(),
+                Some("report_not_found".to_string()),
+            ));
+        }
+    };
+    let resolved = connection.transaction::<_, diesel::result::Error, _>(|conn| {
+        if !close_report(conn, form.report_id, form.moderator_id, form.status)? {
+            return Ok(false);
+        }
+        diesel::insert_into(moderation_actions::table)
+            .values(ModerationAction {
+                moderator_id: Some(form.moderator_id),
+                action: kind,
+                report_id: Some(form.report_id),
+                target_post_id: report.target_post_id,
+                target_user_id: report.target_user_id,
+                note: form.note.clone(),
+                ..Default::default()
+            })
+            .execute(conn)?;
+        Ok(true)
+    });
+    match resolved {
+        Ok(true) => Ok(OkResponse::new("Report resolved".to_string(), None)),
+        Ok(false) => Err(report_not_open()),
+        Err(e) => Err(ErrorResponse::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to resolve report: {}", e),
+            Some("resolve_report_failed".to_string()),
+        )),
+    }
+}
+
+/// Hides or unhides a post depending on `hide`.
+async fn set_post_hidden(
+    form: web::Json<PostActionForm>,
+    data: Data<DbPool>,
+    hide: bool,
+) -> Result<HttpResponse, ErrorResponse> {
+    let mut connection = match data.get() {
+        Ok(conn) => conn,
+        Err(e) => {
+            return Err(ErrorResponse::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to get db connection from pool: {}", e),
+                Some("db_connection_failed".to_string()),
+            ));
+        }
+    };
+    check_moderator(&mut connection, form.moderator_id)?;
+    check_note(form.note.as_ref())?;
+    if posts::table
+        .find(form.post_id)
+        .first::<Post>(&mut connection)
+        .is_err()
+    {
+        return Err(ErrorResponse::new(
+            StatusCode::NOT_FOUND,
+            "Post not found".to_string(),
+            Some("post_not_found".to_string()),
+        ));
+    }
+    let report = report_for_action(&mut connection, form.report_id, Some(form.post_id), None)?;
+    let action = ModerationAction {
+        moderator_id: Some(form.moderator_id),
+        action: if hide {
+            ModerationActionKind::HidePost
+        } else {
+            ModerationActionKind::UnhidePost
+        },
+        report_id: form.report_id,
+        target_post_id: Some(form.post_id),
+        note: form.note.clone(),
+        ..Default::default()
+    };
+    let applied = moderate(&mut connection, action, report, |conn| {
+        let post = posts::table.filter(posts::id.eq(form.post_id));
+        let updated = if hide {
+            diesel::update(post.filter(posts::hidden_at.is_null()))
+                .set(posts::hidden_at.eq(Utc::now()))
+                .execute(conn)?
+        } else {
+            diesel::update(post.filter(posts::hidden_at.is_not_null()))
+                .set(posts::hidden_at.eq(None::<chrono::DateTime<Utc>>))
+                .execute(conn)?
+        };
+        Ok(updated > 0)
+    })?;
+    let message = match (hide, applied) {
+        (true, true) => "Post hidden",
+        (true, false) => "Post was already hidden",
+        (false, true) => "Post unhidden",
+        (false, false) => "Post was not hidden",
+    };
+    Ok(OkResponse::new(message.to_string(), None))
+}
+
+/// Posts the content filter held back, oldest first, with the reason in
+/// `post.held_reason`.
+#[get("/held")]
+async fn get_held_posts(
+    req: HttpRequest,
+    data: Data<DbPool>,
+) -> Result<HttpResponse, ErrorResponse> {
+    let mut connection = match data.get() {
+        Ok(conn) => conn,
+        Err(e) => {
+            return Err(ErrorResponse::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to get db connection from pool: {}", e),
+                Some("db_connection_failed".to_string()),
+            ));
+        }
+    };
+    let held_query = web::Query::<HeldQuery>::from_query(req.query_string()).map_err(|_| {
+        ErrorResponse::new(
+            StatusCode::BAD_REQUEST,
+            "Invalid query".to_string(),
+            Some("invalid_query".to_string()),
+        )
+    })?;
+    let moderator_id = match held_query.moderator_id {
+        Some(m) => m,
+        None => {
+            return Err(ErrorResponse::new(
+                StatusCode::BAD_REQUEST,
+                "Moderator id is required".to_string(),
+                Some("moderator_id_required".to_string()),
+            ));
+        }
+    };
+    check_moderator(&mut connection, moderator_id)?;
+    let limit = pagination::page_size(held_query.limit);
+    let cursor = pagination::parse_cursor(held_query.cursor.as_ref())?;
+    let load_failed = |e: diesel::result::Error| {
+        ErrorResponse::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to load held posts: {}", e),
+            Some("load_held_posts_failed".to_string()),
+        )
+    };
+
+    let mut query = posts::table
+        .inner_join(users::table)
+        .filter(posts::status.eq(PostStatus::Held))
+        .select((posts::all_columns, users::username, users::name))
+        .order((posts::created_at.asc(), posts::id.asc()))
+        .limit(limit + 1)
+        .into_boxed();
+    if let Some(c) = cursor {
+        query = query.filter(
+            posts::created_at
+                .gt(c.created_at)
+                .or(posts::created_at.eq(c.created_at).and(posts::id.gt(c.id))),
+        );
+    }
+    let results = query
+        .load::<(Post, String, String)>(&mut connection)
+        .map_err(load_failed)?;
+    let page = Page::new(results, limit, |(p, _, _)| {
+        Cursor::new(p.created_at.unwrap(), p.id.unwrap())
+    });
+    let meta = page.meta();
+    let results =
+        to_post_results(&mut connection, page.items, Some(moderator_id)).map_err(load_failed)?;
+    Ok(OkResponse::with_meta(
+        "Held posts found".to_string(),
+        Some(serde_json::to_value(results).unwrap()),
+        meta,
+    ))
+}
+
+/// Decides on a held post. Approved posts go live, or back to scheduled if their
+/// publish time is still ahead; rejected ones go back to the author's drafts.
+async fn review_post(
+    form: web::Json<PostActionForm>,
+    data: Data<DbPool>,
+    approve: bool,
+) -> Result<HttpResponse, ErrorResponse> {
+    let mut connection = match data.get() {
+        Ok(conn) => conn,
+        Err(e) => {
+            return Err(ErrorResponse::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to get db connection from pool: {}", e),
+                Some("db_connection_failed".to_string()),
+            ));
+        }
+    };
+    check_moderator(&mut connection, form.moderator_id)?;
+    check_note(form.note.as_ref())?;
+    let post = match posts::table
+        .find(form.post_id)
+        .first::<Post>(&mut connection)
+    {
+        Ok(p) => p,
+        Err(_) => {
+            return Err(ErrorResponse::new(
+                StatusCode::NOT_FOUND,
+                "Post not found".to_string(),
+                Some("post_not_found".to_string()),
+            ));
+        }
+    };
+    // Checked up front so that reviewing a post that is not held leaves its
+    // report open and writes nothing to the audit trail.
+    if post.status != PostStatus::Held {
+        return Err(post_not_held());
+    }
+    let report = report_for_action(&mut connection, form.report_id, Some(form.post_id), None)?;
+    let now = Utc::now();
+    let status = match post.publish_at {
+        _ if !approve => PostStatus::Draft,
+        Some(p) if p > now => PostStatus::Scheduled,
+        _ => PostStatus::Published,
+    };
+    let action = ModerationAction {
+        moderator_id: Some(form.moderator_id),
+        action: if approve {
+            ModerationActionKind::ApprovePost
+        } else {
+            ModerationActionKind::RejectPost
+        },
+        report_id: form.report_id,
+        target_post_id: Some(form.post_id),
+        note: form.note.clone(),
+        ..Default::default()
+    };
+    let applied = moderate(&mut connection, action, report, |conn| {
+        let held = posts::table
+            .filter(posts::id.eq(form.post_id))
+            .filter(posts::status.eq(PostStatus::Held));
+        let updated = if status == PostStatus::Published {
+            diesel::update(held)
+                .set((
+                    posts::status.eq(status),
+                    posts::held_reason.eq(None::<String>),
+                    posts::created_at.eq(now),
+                ))
+                .execute(conn)?
+        } else {
+            diesel::update(held)
+                .set((
+                    posts::status.eq(status),
+                    posts::held_reason.eq(None::<String>),
+                ))
+                .execute(conn)?
+        };
+        if updated > 0 && status == PostStatus::Published {
+            notify_mentions(conn, form.post_id, post.user_id)?;
+        }
+        Ok(updated > 0)
+    })?;
+    if !applied {
+        return Err(post_not_held());
+    }
+    let message = if approve {
+        "Post approved"
+    } else {
+        "Post rejected"
+    };
+    Ok(OkResponse::new(message.to_string(), None))
+}
+
+#[post("/post/approve")]
+async fn approve_post(
+    form: web::Json<PostActionForm>,
+    data: Data<DbPool>,
+) -> Result<HttpResponse, ErrorResponse> {
+    review_post(form, data, true).await
+}
+
+#[post("/post/reject")]
+async fn reject_post(
+    form: web::Json<PostActionForm>,
+    data: Data<DbPool>,
+) -> Result<HttpResponse, ErrorResponse> {
+    review_post(form, data, false).await
+}
+
+#[post("/post/hide")]
+async fn hide_post(
+    form: web::Json<PostActionForm>,
+    data: Data<DbPool>,
+) -> Result<HttpResponse, ErrorResponse> {
+    set_post_hidden(form, data, true).await
+}
+
+#[post("/post/unhide")]
+async fn unhide_post(
+    form: web::Json<PostActionForm>,
+    data: Data<DbPool>,
+) -> Result<HttpResponse, ErrorResponse> {
+    set_post_hidden(form, data, false).await
+}
+
+/// Suspends or reinstates a user depending on `suspend`. Suspended users cannot
+/// post, and their posts are left out of every listing until reinstated.
+async fn set_user_suspended(
+    form: web::Json<UserActionForm>,
+    data: Data<DbPool>,
+    suspend: bool,
+) -> Result<HttpResponse, ErrorResponse> {
+    let mut connection = match data.get() {
+        Ok(conn) => conn,
+        Err(e) => {
+            return Err(ErrorResponse::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to get db connection from pool: {}", e),
+                Some("db_connection_failed".to_string()),
+            ));
+        }
+    };
+    check_moderator(&mut connection, form.moderator_id)?;
+    check_note(form.note.as_ref())?;
+    if users::table
+        .find(form.user_id)
+        .first::<User>(&mut connection)
+        .is_err()
+    {
+        return Err(ErrorResponse::new(
+            StatusCode::NOT_FOUND,
+            "User not found".to_string(),
+            Some("user_not_found".to_string()),
+        ));
+    }
+    if suspend && form.user_id == form.moderator_id {
+        return Err(ErrorResponse::new(
+            StatusCode::BAD_REQUEST,
+            "Moderators cannot suspend themselves".to_string(),
+            Some("cannot_suspend_self".to_string()),
+        ));
+    }
+    let report = report_for_action(&mut connection, form.report_id, None, Some(form.user_id))?;
+    let action = ModerationAction {
+        moderator_id: Some(form.moderator_id),
+        action: if suspend {
+            ModerationActionKind::SuspendUser
+        } else {
+            ModerationActionKind::UnsuspendUser
+        },
+        report_id: form.report_id,
+        target_user_id: Some(form.user_id),
+        note: form.note.clone(),
+        ..Default::default()
+    };
+    let applied = moderate(&mut connection, action, report, |conn| {
+        let user = users::table.filter(users::id.eq(form.user_id));
+        let updated = if suspend {
+            diesel::update(user.filter(users::suspended_at.is_null()))
+                .set(users::suspended_at.eq(Utc::now()))
+                .execute(conn)?
+        } else {
+            diesel::update(user.filter(users::suspended_at.is_not_null()))
+                .set(users::suspended_at.eq(None::<chrono::DateTime<Utc>>))
+                .execute(conn)?
+        };
+        Ok(updated > 0)
+    })?;
+    let message = match (suspend, applied) {
+        (true, true) => "User suspended",
+        (true, false) => "User was already suspended",
+        (false, true) => "User reinstated",
+        (false, false) => "User was not suspended",
+    };
+    Ok(OkResponse::new(message.to_string(), None))
+}
+
+#[post("/user/suspend")]
+async fn suspend_user(
+    form: web::Json<UserActionForm>,
+    data: Data<DbPool>,
+) -> Result<HttpResponse, ErrorResponse> {
+    set_user_suspended(form, data, true).await
+}
+
+#[post("/user/unsuspend")]
+async fn unsuspend_user(
+    form: web::Json<UserActionForm>,
+    data: Data<DbPool>,
+) -> Result<HttpResponse, ErrorResponse> {
+    set_user_suspended(form, data, false).await
+}
+
+/// The audit trail, newest first, optionally for one post or user.
+#[get("/actions")]
+async fn get_actions(req: HttpRequest, data: Data<DbPool>) -> Result<HttpResponse, ErrorResponse> {
+    let mut connection = match data.get() {
+        Ok(conn) => conn,
+        Err(e) => {
+            return Err(ErrorResponse::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to get db connection from pool: {}", e),
+                Some("db_connection_failed".to_string()),
+            ));
+        }
+    };
+    let action_query = web::Query::<ActionQuery>::from_query(req.query_string()).map_err(|_| {
+        ErrorResponse::new(
+            StatusCode::BAD_REQUEST,
+            "Invalid query".to_string(),
+            Some("invalid_query".to_string()),
+        )
+    })?;
+    let moderator_id = match action_query.moderator_id {
+        Some(m) => m,
+        None => {
+            return Err(ErrorResponse::new(
+                StatusCode::BAD_REQUEST,
+                "Moderator id is required".to_string(),
+                Some("moderator_id_required".to_string()),
+            ));
+        }
+    };
+    check_moderator(&mut connection, moderator_id)?;
+    let limit = pagination::page_size(action_query.limit);
+    let cursor = pagination::parse_cursor(action_query.cursor.as_ref())?;
+
+    let mut query = moderation_actions::table
+        .order((
+            moderation_actions::created_at.desc(),
+            moderation_actions::id.desc(),
+        ))
+        .limit(limit + 1)
+        .into_boxed();
+    if let Some(p) = action_query.target_post_id {
+        query = query.filter(moderation_actions::target_post_id.eq(p));
+    }
+    if let Some(u) = action_query.target_user_id {
+        query = query.filter(moderation_actions::target_user_id.eq(u));
+    }
+    if let Some(c) = cursor {
+        query = query.filter(
+            moderation_actions::created_at
+                .lt(c.created_at)
+                .or(moderation_actions::created_at
+                    .eq(c.created_at)
+                    .and(moderation_actions::id.lt(c.id))),
+        );
+    }
+    let results = query
+        .load::<ModerationAction>(&mut connection)
+        .map_err(|e| {
+            ErrorResponse::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to load moderation actions: {}", e),
+                Some("load_moderation_actions_failed".to_string()),
+            )
+        })?;
+    let page = Page::new(results, limit, |a| {
+        Cursor::new(a.created_at.unwrap(), a.id.unwrap())
+    });
+    Ok(OkResponse::with_meta(
+        "Moderation actions found".to_string(),
+        Some(serde_json::to_value(&page.items).unwrap()),
+        page.meta(),
+    ))
+}
+
+pub fn init(config: &mut ServiceConfig) {
+    config.service(
+        web::scope("/moderation")
+            .service(get_reports)
+            .service(resolve_report)
+            .service(get_held_posts)
+            .service(approve_post)
+            .service(reject_post)
+            .service(hide_post)
+            .service(unhide_post)
+            .service(suspend_user)
+            .service(unsuspend_user)
+            .service(get_actions),
+    );
+}
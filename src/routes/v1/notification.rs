@@ -0,0 +1,172 @@
+use crate::{
+    db::{DbPool, DbPooled},
+    models::Notification,
+    pagination::{self, Cursor, Page},
+    response::{ErrorResponse, OkResponse},
+    schema::notifications,
+};
+use actix_web::{
+    get,
+    http::StatusCode,
+    post,
+    web::{self, Data, ServiceConfig},
+    HttpRequest, HttpResponse, Result,
+};
+use diesel::{BoolExpressionMethods, ExpressionMethods, QueryDsl, QueryResult, RunQueryDsl};
+use serde::Deserialize;
+
+pub const KIND_MENTION: &str = "mention";
+pub const KIND_FOLLOW_REQUEST: &str = "follow_request";
+pub const KIND_FOLLOW_ACCEPTED: &str = "follow_accepted";
+pub const KIND_CONNECTION_INVITE: &str = "connection_invite";
+pub const KIND_CONNECTION_ACCEPTED: &str = "connection_accepted";
+
+#[derive(Deserialize)]
+struct NotificationQuery {
+    user_id: Option<i64>,
+    unread: Option<bool>,
+    cursor: Option<String>,
+    limit: Option<i64>,
+}
+
+#[derive(Deserialize, Debug)]
+struct ReadForm {
+    user_id: i64,
+    /// Marks everything as read when omitted.
+    ids: Option<Vec<i64>>,
+}
+
+/// Queues a notification for `user_id`. Users are never notified about their own actions.
+pub fn notify(
+    conn: &mut DbPooled,
+    user_id: i64,
+    actor_user_id: i64,
+    kind: &str,
+    post_id: Option<i64>,
+) -> QueryResult<()> {
+    if user_id == actor_user_id {
+        return Ok(());
+    }
+    diesel::insert_into(notifications::table)
+        .values(Notification {
+            user_id,
+            actor_user_id,
+            kind: kind.to_string(),
+            post_id,
+            ..Default::default()
+        })
+        .execute(conn)?;
+    Ok(())
+}
+
+#[get("")]
+async fn get_notifications(
+    req: HttpRequest,
+    data: Data<DbPool>,
+) -> Result<HttpResponse, ErrorResponse> {
+    let mut connection = match data.get() {
+        Ok(conn) => conn,
+        Err(e) => {
+            return Err(ErrorResponse::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to get db connection from pool: {}", e),
+                Some("db_connection_failed".to_string()),
+            ));
+        }
+    };
+    let notification_query = web::Query::<NotificationQuery>::from_query(req.query_string())
+        .map_err(|_| {
+            ErrorResponse::new(
+                StatusCode::BAD_REQUEST,
+                "Invalid query".to_string(),
+                Some("invalid_query".to_string()),
+            )
+        })?;
+    let user_id = match notification_query.user_id {
+        Some(u) => u,
+        None => {
+            return Err(ErrorResponse::new(
+                StatusCode::BAD_REQUEST,
+                "User id is required".to_string(),
+                Some("user_id_required".to_string()),
+            ));
+        }
+    };
+    let limit = pagination::page_size(notification_query.limit);
+    let cursor = pagination::parse_cursor(notification_query.cursor.as_ref())?;
+
+    let mut query = notifications::table
+        .filter(notifications::user_id.eq(user_id))
+        .order((notifications::created_at.desc(), notifications::id.desc()))
+        .limit(limit + 1)
+        .into_boxed();
+    if notification_query.unread.unwrap_or(false) {
+        query = query.filter(notifications::is_read.eq(false));
+    }
+    if let Some(c) = cursor {
+        query = query.filter(
+            notifications::created_at
+                .lt(c.created_at)
+                .or(notifications::created_at
+                    .eq(c.created_at)
+                    .and(notifications::id.lt(c.id))),
+        );
+    }
+    let results = query.load::<Notification>(&mut connection).map_err(|e| {
+        ErrorResponse::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to load notifications: {}", e),
+            Some("load_notifications_failed".to_string()),
+        )
+    })?;
+    let page = Page::new(results, limit, |n| {
+        Cursor::new(n.created_at.unwrap(), n.id.unwrap())
+    });
+    Ok(OkResponse::with_meta(
+        "Notifications found".to_string(),
+        Some(serde_json::to_value(&page.items).unwrap()),
+        page.meta(),
+    ))
+}
+
+#[post("/read")]
+async fn mark_read(
+    form: web::Json<ReadForm>,
+    data: Data<DbPool>,
+) -> Result<HttpResponse, ErrorResponse> {
+    let mut connection = match data.get() {
+        Ok(conn) => conn,
+        Err(e) => {
+            return Err(ErrorResponse::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to get db connection from pool: {}", e),
+                Some("db_connection_failed".to_string()),
+            ));
+        }
+    };
+    let mut query = diesel::update(notifications::table)
+        .filter(notifications::user_id.eq(form.user_id))
+        .into_boxed();
+    if let Some(ids) = &form.ids {
+        query = query.filter(notifications::id.eq_any(ids));
+    }
+    query
+        .set(notifications::is_read.eq(true))
+        .execute(&mut connection)
+        .map_err(|e| {
+            ErrorResponse::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to mark notifications as read: {}", e),
+                Some("mark_read_failed".to_string()),
+            )
+        })?;
+    Ok(OkResponse::new("Notifications read".to_string(), None))
+}
+
+pub fn init(config: &mut ServiceConfig) {
+    config.service(
+        web::scope("/notification")
+            .service(get_notifications)
+            .service(mark_read),
+    );
+}
@@ -0,0 +1,318 @@
+use crate::{
+    db::{DbPool, DbPooled},
+    models::{Post, PostStatus},
+    response::{ErrorResponse, OkResponse},
+    routes::v1::{
+        company::check_company_admin,
+        post::{to_post_results, visible_to, PostResult},
+    },
+    schema::{company, pinned_posts, posts, users},
+};
+use actix_web::{
+    http::StatusCode,
+    post,
+    web::{self, Data},
+    HttpResponse, Result,
+};
+use diesel::{pg::Pg, Connection, ExpressionMethods, QueryDsl, QueryResult, RunQueryDsl};
+use serde::Deserialize;
+
+/// How many posts a user or company can pin.
+pub const MAX_PINNED_POSTS: usize = 3;
+
+#[derive(Deserialize, Debug)]
+struct PinForm {
+    user_id: i64,
+    post_id: i64,
+    /// Pins to the company profile instead; the user must be one of its admins.
+    company_id: Option<i64>,
+}
+
+#[derive(Deserialize, Debug)]
+struct PinOrderForm {
+    user_id: i64,
+    company_id: Option<i64>,
+    /// Every pinned post, in the new order.
+    post_ids: Vec<i64>,
+}
+
+/// Whose profile a pin is on.
+#[derive(Debug, Clone, Copy)]
+pub enum PinOwner {
+    User(i64),
+    Company(i64),
+}
+
+impl PinOwner {
+    fn pins(self) -> pinned_posts::BoxedQuery<'static, Pg> {
+        match self {
+            PinOwner::User(u) => pinned_posts::table
+                .filter(pinned_posts::user_id.eq(u))
+                .into_boxed(),
+            PinOwner::Company(c) => pinned_posts::table
+                .filter(pinned_posts::company_id.eq(c))
+                .into_boxed(),
+        }
+    }
+
+    /// Locks the user or company row so that concurrent changes to the same
+    /// profile's pins run one after the other.
+    fn lock(self, conn: &mut DbPooled) -> QueryResult<()> {
+        match self {
+            PinOwner::User(u) => users::table
+                .find(u)
+                .select(users::id)
+                .for_update()
+                .execute(conn)?,
+            PinOwner::Company(c) => company::table
+                .find(c)
+                .select(company::id)
+                .for_update()
+                .execute(conn)?,
+        };
+        Ok(())
+    }
+}
+
+/// Pinned posts of a profile that `viewer_id` may read, in pin order.
+pub fn load_pinned(
+    conn: &mut DbPooled,
+    owner: PinOwner,
+    viewer_id: Option<i64>,
+) -> QueryResult<Vec<(Post, String, String)>> {
+    let mut query = pinned_posts::table
+        .inner_join(posts::table.inner_join(users::table))
+        .filter(visible_to(viewer_id))
+        .select((posts::all_columns, users::username, users::name))
+        .order((pinned_posts::position, pinned_posts::id))
+        .into_boxed();
+    query = match owner {
+        PinOwner::User(u) => query.filter(pinned_posts::user_id.eq(u)),
+        PinOwner::Company(c) => query.filter(pinned_posts::company_id.eq(c)),
+    };
+    query.load(conn)
+}
+
+/// Builds a profile page: on the first page the pinned posts come first, marked as
+/// such, followed by the timeline `rows`. Pinned posts are not counted against the
+/// page size and the timeline is expected to leave them out.
+pub fn with_pinned(
+    conn: &mut DbPooled,
+    owner: PinOwner,
+    viewer_id: Option<i64>,
+    first_page: bool,
+    rows: Vec<(Post, String, String)>,
+) -> QueryResult<Vec<PostResult>> {
+    let mut results = if first_page {
+        let pinned = load_pinned(conn, owner, viewer_id)?;
+        to_post_results(conn, pinned, viewer_id)?
+    } else {
+        vec![]
+    };
+    results.iter_mut().for_each(|r| r.pinned = true);
+    results.extend(to_post_results(conn, rows, viewer_id)?);
+    Ok(results)
+}
+
+/// Ids of the posts pinned on a profile, so they can be left out of the timeline.
+pub fn pinned_post_ids(conn: &mut DbPooled, owner: PinOwner) -> QueryResult<Vec<i64>> {
+    owner.pins().select(pinned_posts::post_id).load(conn)
+}
+
+/// Resolves whose profile the form acts on and checks the user may pin there.
+fn pin_owner(
+    conn: &mut DbPooled,
+    user_id: i64,
+    company_id: Option<i64>,
+) -> Result<PinOwner, ErrorResponse> {
+    match company_id {
+        Some(c) => {
+            check_company_admin(conn, c, user_id)?;
+            Ok(PinOwner::Company(c))
+        }
+        None => Ok(PinOwner::User(user_id)),
+    }
+}
+
+#[post("/pin")]
+pub async fn pin_post(
+    form: web::Json<PinForm>,
+    data: Data<DbPool>,
+) -> Result<HttpResponse, ErrorResponse> {
+    let mut connection = match data.get() {
+        Ok(conn) => conn,
+        Err(e) => {
+            return Err(ErrorResponse::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to get db connection from pool: {}", e),
+                Some("db_connection_failed".to_string()),
+            ));
+        }
+    };
+    let owner = pin_owner(&mut connection, form.user_id, form.company_id)?;
+    let post = match posts::table
+        .find(form.post_id)
+        .first::<Post>(&mut connection)
+    {
+        Ok(p) => p,
+        Err(_) => {
+            return Err(ErrorResponse::new(
+                StatusCode::NOT_FOUND,
+                "Post not found".to_string(),
+                Some("post_not_found".to_string()),
+            ));
+        }
+    };
+    let belongs = match owner {
+        PinOwner::User(u) => post.user_id == u,
+        PinOwner::Company(c) => post.company_id == Some(c),
+    };
+    if !belongs {
+        return Err(ErrorResponse::new(
+            StatusCode::FORBIDDEN,
+            "Only posts of this profile can be pinned to it".to_string(),
+            Some("not_post_owner".to_string()),
+        ));
+    }
+    if post.status != PostStatus::Published {
+        return Err(ErrorResponse::new(
+            StatusCode::BAD_REQUEST,
+            "Only published posts can be pinned".to_string(),
+            Some("post_not_published".to_string()),
+        ));
+    }
+
+    let pinned = connection.transaction::<_, diesel::result::Error, _>(|conn| {
+        owner.lock(conn)?;
+        let pinned = owner
+            .pins()
+            .select((pinned_posts::post_id, pinned_posts::position))
+            .load::<(i64, i32)>(conn)?;
+        if pinned.iter().any(|(p, _)| *p == form.post_id) {
+            return Ok(true);
+        }
+        if pinned.len() >= MAX_PINNED_POSTS {
+            return Ok(false);
+        }
+        let position = pinned.iter().map(|(_, p)| p + 1).max().unwrap_or(0);
+        let (user_id, company_id) = match owner {
+            PinOwner::User(u) => (Some(u), None),
+            PinOwner::Company(c) => (None, Some(c)),
+        };
+        diesel::insert_into(pinned_posts::table)
+            .values((
+                pinned_posts::post_id.eq(form.post_id),
+                pinned_posts::user_id.eq(user_id),
+                pinned_posts::company_id.eq(company_id),
+                pinned_posts::position.eq(position),
+            ))
+            .execute(conn)?;
+        Ok(true)
+    });
+    match pinned {
+        Ok(true) => Ok(OkResponse::new("Post pinned".to_string(), None)),
+        Ok(false) => Err(ErrorResponse::new(
+            StatusCode::CONFLICT,
+            format!("At most {} posts can be pinned", MAX_PINNED_POSTS),
+            Some("pin_limit_reached".to_string()),
+        )),
+        Err(e) => Err(ErrorResponse::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to pin post: {}", e),
+            Some("pin_post_failed".to_string()),
+        )),
+    }
+}
+
+#[post("/unpin")]
+pub async fn unpin_post(
+    form: web::Json<PinForm>,
+    data: Data<DbPool>,
+) -> Result<HttpResponse, ErrorResponse> {
+    let mut connection = match data.get() {
+        Ok(conn) => conn,
+        Err(e) => {
+            return Err(ErrorResponse::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to get db connection from pool: {}", e),
+                Some("db_connection_failed".to_string()),
+            ));
+        }
+    };
+    let owner = pin_owner(&mut connection, form.user_id, form.company_id)?;
+    let unpinned = match owner {
+        PinOwner::User(u) => diesel::delete(
+            pinned_posts::table
+                .filter(pinned_posts::user_id.eq(u))
+                .filter(pinned_posts::post_id.eq(form.post_id)),
+        )
+        .execute(&mut connection),
+        PinOwner::Company(c) => diesel::delete(
+            pinned_posts::table
+                .filter(pinned_posts::company_id.eq(c))
+                .filter(pinned_posts::post_id.eq(form.post_id)),
+        )
+        .execute(&mut connection),
+    };
+    match unpinned {
+        Ok(_) => Ok(OkResponse::new("Post unpinned".to_string(), None)),
+        Err(e) => Err(ErrorResponse::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to unpin post: {}", e),
+            Some("unpin_post_failed".to_string()),
+        )),
+    }
+}
+
+/// Sets the order of pinned posts; `post_ids` must list exactly the pinned posts.
+#[post("/pin/order")]
+pub async fn order_pins(
+    form: web::Json<PinOrderForm>,
+    data: Data<DbPool>,
+) -> Result<HttpResponse, ErrorResponse> {
+    let mut connection = match data.get() {
+        Ok(conn) => conn,
+        Err(e) => {
+            return Err(ErrorResponse::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to get db connection from pool: {}", e),
+                Some("db_connection_failed".to_string()),
+            ));
+        }
+    };
+    let owner = pin_owner(&mut connection, form.user_id, form.company_id)?;
+    let ordered = connection.transaction::<_, diesel::result::Error, _>(|conn| {
+        owner.lock(conn)?;
+        let mut pinned = owner
+            .pins()
+            .select(pinned_posts::post_id)
+            .load::<i64>(conn)?;
+        let mut requested = form.post_ids.clone();
+        pinned.sort_unstable();
+        requested.sort_unstable();
+        if pinned != requested {
+            return Ok(false);
+        }
+        for (i, post_id) in form.post_ids.iter().enumerate() {
+            let pin = owner.pins().filter(pinned_posts::post_id.eq(*post_id));
+            let pin_id = pin.select(pinned_posts::id).first::<i64>(conn)?;
+            diesel::update(pinned_posts::table.find(pin_id))
+                .set(pinned_posts::position.eq(i as i32))
+                .execute(conn)?;
+        }
+        Ok(true)
+    });
+    match ordered {
+        Ok(true) => Ok(OkResponse::new("Pinned posts reordered".to_string(), None)),
+        Ok(false) => Err(ErrorResponse::new(
+            StatusCode::BAD_REQUEST,
+            "The new order must list every pinned post exactly once".to_string(),
+            Some("invalid_pin_order".to_string()),
+        )),
+        Err(e) => Err(ErrorResponse::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to reorder pinned posts: {}", e),
+            Some("order_pins_failed".to_string()),
+        )),
+    }
+}
@@ -0,0 +1,311 @@
+use crate::{
+    db::{DbPool, DbPooled},
+    models::{Poll, PollOption, Post},
+    response::{ErrorResponse, OkResponse},
+    routes::v1::{moderation::check_not_suspended, post::visible_to},
+    schema::{poll_options, poll_votes, polls, posts},
+};
+use actix_web::{
+    http::StatusCode,
+    post,
+    web::{self, Data},
+    HttpResponse, Result,
+};
+use chrono::{DateTime, Duration, Utc};
+use diesel::{dsl::count_star, ExpressionMethods, QueryDsl, QueryResult, RunQueryDsl};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+pub const MIN_POLL_OPTIONS: usize = 2;
+pub const MAX_POLL_OPTIONS: usize = 4;
+pub const MAX_POLL_OPTION_LENGTH: usize = 100;
+/// Polls without a closing time run this long after the post is published.
+const DEFAULT_POLL_HOURS: i64 = 24;
+const MAX_POLL_DAYS: i64 = 30;
+
+#[derive(Deserialize, Debug)]
+struct VoteForm {
+    user_id: i64,
+    post_id: i64,
+    option_id: i64,
+}
+
+#[derive(Serialize)]
+pub struct PollOptionEntity {
+    pub id: i64,
+    pub label: String,
+    /// Hidden until the viewer has voted or the poll has closed.
+    pub votes: Option<i64>,
+}
+
+#[derive(Serialize)]
+pub struct PollEntity {
+    pub closes_at: DateTime<Utc>,
+    pub closed: bool,
+    /// Hidden like the per-option counts.
+    pub total_votes: Option<i64>,
+    pub options: Vec<PollOptionEntity>,
+    pub voted_option_id: Option<i64>,
+}
+
+/// Trims the option labels and checks there are enough of them, that none is
+/// empty or too long, and that no two are the same.
+pub fn check_poll_options(options: Vec<String>) -> Result<Vec<String>, ErrorResponse> {
+    let options: Vec<String> = options.into_iter().map(|o| o.trim().to_string()).collect();
+    if options.len() < MIN_POLL_OPTIONS || options.len() > MAX_POLL_OPTIONS {
+        return Err(ErrorResponse::new(
+            StatusCode::BAD_REQUEST,
+            format!(
+                "Polls need between {} and {} options",
+                MIN_POLL_OPTIONS, MAX_POLL_OPTIONS
+            ),
+            Some("invalid_poll_option_count".to_string()),
+        ));
+    }
+    for (i, o) in options.iter().enumerate() {
+        if o.is_empty() || o.chars().count() > MAX_POLL_OPTION_LENGTH {
+            return Err(ErrorResponse::new(
+                StatusCode::BAD_REQUEST,
+                format!(
+                    "Poll options must be 1 to {} characters",
+                    MAX_POLL_OPTION_LENGTH
+                ),
+                Some("invalid_poll_option".to_string()),
+            ));
+        }
+        if options[..i]
+            .iter()
+            .any(|p| p.to_lowercase() == o.to_lowercase())
+        {
+            return Err(ErrorResponse::new(
+                StatusCode::BAD_REQUEST,
+                "Poll options must be different from each other".to_string(),
+                Some("duplicate_poll_option".to_string()),
+            ));
+        }
+    }
+    Ok(options)
+}
+
+/// Picks the closing time of a poll on a post going live at `opens_at`: the
+/// requested one, or `DEFAULT_POLL_HOURS` later. It must fall within
+/// `MAX_POLL_DAYS` of `opens_at`.
+pub fn poll_closes_at(
+    opens_at: DateTime<Utc>,
+    closes_at: Option<DateTime<Utc>>,
+) -> Result<DateTime<Utc>, ErrorResponse> {
+    let closes_at = closes_at.unwrap_or(opens_at + Duration::hours(DEFAULT_POLL_HOURS));
+    if closes_at <= opens_at {
+        return Err(ErrorResponse::new(
+            StatusCode::BAD_REQUEST,
+            "Poll must close after the post is published".to_string(),
+            Some("poll_closes_too_early".to_string()),
+        ));
+    }
+    if closes_at > opens_at + Duration::days(MAX_POLL_DAYS) {
+        return Err(ErrorResponse::new(
+            StatusCode::BAD_REQUEST,
+            format!("Polls can run for at most {} days", MAX_POLL_DAYS),
+            Some("poll_closes_too_late".to_string()),
+        ));
+    }
+    Ok(closes_at)
+}
+
+/// Creates the poll of `post_id` with its options in the given order.
+pub fn create_poll(
+    conn: &mut DbPooled,
+    post_id: i64,
+    options: &[String],
+    closes_at: DateTime<Utc>,
+) -> QueryResult<()> {
+    let poll = diesel::insert_into(polls::table)
+        .values(Poll {
+            post_id,
+            closes_at,
+            ..Default::default()
+        })
+        .get_result::<Poll>(conn)?;
+    let new_options: Vec<_> = options
+        .iter()
+        .enumerate()
+        .map(|(i, label)| PollOption {
+            poll_id: poll.id.unwrap(),
+            position: i as i32,
+            label: label.clone(),
+            ..Default::default()
+        })
+        .collect();
+    diesel::insert_into(poll_options::table)
+        .values(&new_options)
+        .execute(conn)?;
+    Ok(())
+}
+
+/// Polls of the given posts keyed by post id, as seen by `viewer_id`.
+pub fn load_polls(
+    conn: &mut DbPooled,
+    post_ids: &[i64],
+    viewer_id: Option<i64>,
+) -> QueryResult<HashMap<i64, PollEntity>> {
+    let found = polls::table
+        .filter(polls::post_id.eq_any(post_ids))
+        .load::<Poll>(conn)?;
+    if found.is_empty() {
+        return Ok(HashMap::new());
+    }
+    let poll_ids: Vec<i64> = found.iter().map(|p| p.id.unwrap()).collect();
+    let mut options: HashMap<i64, Vec<PollOption>> = HashMap::new();
+    for o in poll_options::table
+        .filter(poll_options::poll_id.eq_any(&poll_ids))
+        .order((poll_options::poll_id, poll_options::position))
+        .load::<PollOption>(conn)?
+    {
+        options.entry(o.poll_id).or_default().push(o);
+    }
+    let counts: HashMap<i64, i64> = poll_votes::table
+        .filter(poll_votes::poll_id.eq_any(&poll_ids))
+        .group_by(poll_votes::poll_option_id)
+        .select((poll_votes::poll_option_id, count_star()))
+        .load::<(i64, i64)>(conn)?
+        .into_iter()
+        .collect();
+    let voted: HashMap<i64, i64> = match viewer_id {
+        Some(v) => poll_votes::table
+            .filter(poll_votes::poll_id.eq_any(&poll_ids))
+            .filter(poll_votes::user_id.eq(v))
+            .select((poll_votes::poll_id, poll_votes::poll_option_id))
+            .load::<(i64, i64)>(conn)?
+            .into_iter()
+            .collect(),
+        None => HashMap::new(),
+    };
+
+    let now = Utc::now();
+    Ok(found
+        .into_iter()
+        .map(|poll| {
+            let poll_id = poll.id.unwrap();
+            let closed = poll.closes_at <= now;
+            let voted_option_id = voted.get(&poll_id).copied();
+            let show_counts = closed || voted_option_id.is_some();
+            let options: Vec<PollOptionEntity> = options
+                .remove(&poll_id)
+                .unwrap_or_default()
+                .into_iter()
+                .map(|o| PollOptionEntity {
+                    votes: show_counts.then(|| counts.get(&o.id.unwrap()).copied().unwrap_or(0)),
+                    id: o.id.unwrap(),
+                    label: o.label,
+                })
+                .collect();
+            let total_votes = show_counts.then(|| options.iter().filter_map(|o| o.votes).sum());
+            (
+                poll.post_id,
+                PollEntity {
+                    closes_at: poll.closes_at,
+                    closed,
+                    total_votes,
+                    options,
+                    voted_option_id,
+                },
+            )
+        })
+        .collect())
+}
+
+/// Votes on the poll of a post. Each user votes once and cannot change their
+/// vote; the response carries the poll with its results.
+#[post("/poll/vote")]
+pub async fn vote_poll(
+    form: web::Json<VoteForm>,
+    data: Data<DbPool>,
+) -> Result<HttpResponse, ErrorResponse> {
+    let mut connection = match data.get() {
+        Ok(conn) => conn,
+        Err(e) => {
+            return Err(ErrorResponse::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to get db connection from pool: {}", e),
+                Some("db_connection_failed".to_string()),
+            ));
+        }
+    };
+    check_not_suspended(&mut connection, form.user_id)?;
+    if posts::table
+        .filter(posts::id.eq(form.post_id))
+        .filter(visible_to(Some(form.user_id)))
+        .first::<Post>(&mut connection)
+        .is_err()
+    {
+        return Err(ErrorResponse::new(
+            StatusCode::NOT_FOUND,
+            "Post not found".to_string(),
+            Some("post_not_found".to_string()),
+        ));
+    }
+    let poll = match polls::table
+        .filter(polls::post_id.eq(form.post_id))
+        .first::<Poll>(&mut connection)
+    {
+        Ok(p) => p,
+        Err(_) => {
+            return Err(ErrorResponse::new(
+                StatusCode::NOT_FOUND,
+                "Post has no poll".to_string(),
+                Some("poll_not_found".to_string()),
+            ));
+        }
+    };
+    if poll.closes_at <= Utc::now() {
+        return Err(ErrorResponse::new(
+            StatusCode::CONFLICT,
+            "Poll is closed".to_string(),
+            Some("poll_closed".to_string()),
+        ));
+    }
+    let poll_id = poll.id.unwrap();
+    if poll_options::table
+        .filter(poll_options::id.eq(form.option_id))
+        .filter(poll_options::poll_id.eq(poll_id))
+        .first::<PollOption>(&mut connection)
+        .is_err()
+    {
+        return Err(ErrorResponse::new(
+            StatusCode::BAD_REQUEST,
+            "Option does not belong to this poll".to_string(),
+            Some("invalid_poll_option".to_string()),
+        ));
+    }
+    let load_failed = |e: diesel::result::Error| {
+        ErrorResponse::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to vote: {}", e),
+            Some("vote_poll_failed".to_string()),
+        )
+    };
+    let inserted = diesel::insert_into(poll_votes::table)
+        .values((
+            poll_votes::poll_id.eq(poll_id),
+            poll_votes::poll_option_id.eq(form.option_id),
+            poll_votes::user_id.eq(form.user_id),
+        ))
+        .on_conflict((poll_votes::poll_id, poll_votes::user_id))
+        .do_nothing()
+        .execute(&mut connection)
+        .map_err(load_failed)?;
+    if inserted == 0 {
+        return Err(ErrorResponse::new(
+            StatusCode::CONFLICT,
+            "You have already voted in this poll".to_string(),
+            Some("already_voted".to_string()),
+        ));
+    }
+    let poll = load_polls(&mut connection, &[form.post_id], Some(form.user_id))
+        .map_err(load_failed)?
+        .remove(&form.post_id);
+    Ok(OkResponse::new(
+        "Vote recorded".to_string(),
+        Some(serde_json::to_value(poll).unwrap()),
+    ))
+}
@@ -1,6 +1,7 @@
 use crate::{
     db::DbPool,
     models::Position,
+    pagination::{self, Cursor, Page},
     response::{ErrorResponse, OkResponse},
 };
 use actix_multipart::form::{text::Text, MultipartForm};
@@ -11,12 +12,14 @@ use actix_web::{
     web::{self, Data, ServiceConfig},
     HttpRequest, HttpResponse, Result,
 };
-use diesel::{ExpressionMethods, QueryDsl, RunQueryDsl};
+use diesel::{BoolExpressionMethods, ExpressionMethods, QueryDsl, RunQueryDsl};
 use serde::Deserialize;
 
 #[derive(Deserialize)]
 struct Query {
     id: Option<i64>,
+    cursor: Option<String>,
+    limit: Option<i64>,
 }
 
 #[get("")]
@@ -50,6 +53,29 @@ async fn get_position(req: HttpRequest, data: Data<DbPool>) -> Result<HttpRespon
             ));
         }
     } else {
+        let limit = pagination::page_size(query.limit);
+        let cursor = pagination::parse_cursor(query.cursor.as_ref())?;
+        let mut list_query = position
+            .order((created_at.desc(), id.desc()))
+            .limit(limit + 1)
+            .into_boxed();
+        if let Some(c) = cursor {
+            list_query = list_query.filter(
+                created_at
+                    .lt(c.created_at)
+                    .or(created_at.eq(c.created_at).and(id.lt(c.id))),
+            );
+        }
+        if let Ok(positions) = list_query.load::<Position>(&mut connection) {
+            let page = Page::new(positions, limit, |p| {
+                Cursor::new(p.created_at.unwrap(), p.id.unwrap())
+            });
+            return Ok(OkResponse::with_meta(
+                "Positions found".to_string(),
+                Some(serde_json::to_value(&page.items).unwrap()),
+                page.meta(),
+            ));
+        }
         return Err(ErrorResponse::new(
             StatusCode::BAD_REQUEST,
             "Invalid query".to_string(),
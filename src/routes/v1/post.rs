@@ -1,10 +1,26 @@
 use crate::{
-    db::DbPool,
-    models::{Post, User},
+    db::{DbPool, DbPooled},
+    filter::{FilterPipeline, Submission, Verdict},
+    markdown,
+    media::{self, MediaInfo},
+    models::{ImpressionKind, Post, PostAttachment, PostKind, PostStatus, User, Visibility},
+    pagination::{self, Cursor, Page},
     response::{ErrorResponse, OkResponse},
-    schema::users::name,
+    routes::v1::{
+        analytics,
+        company::check_company_admin,
+        link_preview::{load_previews, sync_post_links, LinkPreviewEntity},
+        mention::{load_mentions, notify_mentions, sync_post_mentions, MentionEntity},
+        moderation::check_not_suspended,
+        pin::{order_pins, pin_post, pinned_post_ids, unpin_post, with_pinned, PinOwner},
+        poll::{self, load_polls, vote_poll, PollEntity},
+        search::search_posts,
+        tag::sync_post_tags,
+    },
+    schema::{post_attachments, posts, users, users::name},
+    storage::Storage,
 };
-use actix_multipart::form::{text::Text, MultipartForm};
+use actix_multipart::form::{tempfile::TempFile, text::Text, MultipartForm};
 use actix_web::{
     get,
     http::StatusCode,
@@ -12,22 +28,126 @@ use actix_web::{
     web::{self, Data, ServiceConfig},
     HttpRequest, HttpResponse,
 };
-use diesel::{ExpressionMethods, QueryDsl, RunQueryDsl, Table};
+use chrono::{DateTime, Utc};
+use diesel::{
+    dsl::{exists, sql},
+    expression::BoxableExpression,
+    pg::Pg,
+    prelude::AsChangeset,
+    sql_types::{BigInt, Bool},
+    BoolExpressionMethods, Connection, ExpressionMethods, QueryDsl, QueryResult, RunQueryDsl,
+    Table,
+};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
 #[derive(Deserialize)]
 struct PostQuery {
     username: Option<String>,
+    company_id: Option<i64>,
     id: Option<i64>,
+    viewer_id: Option<i64>,
+    cursor: Option<String>,
     limit: Option<i64>,
-    offset: Option<i64>,
 }
 
 #[derive(Serialize)]
-struct PostResult {
-    post: Post,
-    username: String,
-    name: String,
+pub struct PostResult {
+    pub post: Post,
+    pub username: String,
+    pub name: String,
+    /// `post.body` rendered from Markdown and sanitized.
+    pub body_html: String,
+    pub mentions: Vec<MentionEntity>,
+    pub attachments: Vec<PostAttachment>,
+    pub link_previews: Vec<LinkPreviewEntity>,
+    /// Set on posts of kind `poll`.
+    pub poll: Option<PollEntity>,
+    /// Shown first on its profile; only set on `?username=` and `?company_id=` listings.
+    pub pinned: bool,
+}
+
+/// Published posts `viewer_id` may read: public ones, their own, and followers-only
+/// posts of users they follow. Followers-only posts made on behalf of a company
+/// are for the followers of the company instead. Anonymous callers only see
+/// public posts. Posts hidden by a moderator and posts of suspended users are
+/// never shown, nor are posts of users the viewer blocked or was blocked by.
+///
+/// Written as SQL so the same filter works on `posts` alone and on joins with it.
+pub fn visible_to<'a, QS: 'a>(
+    viewer_id: Option<i64>,
+) -> Box<dyn BoxableExpression<QS, Pg, SqlType = Bool> + 'a> {
+    match viewer_id {
+        Some(v) => Box::new(
+            sql::<Bool>(
+                "posts.status = 'published' AND posts.hidden_at IS NULL AND \
+                 NOT EXISTS (SELECT 1 FROM users su WHERE su.id = posts.user_id \
+                     AND su.suspended_at IS NOT NULL) AND \
+                 (posts.visibility = 'public' OR posts.user_id = ",
+            )
+            .bind::<BigInt, _>(v)
+            .sql(
+                " OR (posts.visibility = 'followers' AND posts.company_id IS NULL AND \
+                     posts.user_id IN \
+                     (SELECT followed_user_id FROM follows WHERE following_user_id = ",
+            )
+            .bind::<BigInt, _>(v)
+            .sql(
+                ")) OR (posts.visibility = 'followers' AND posts.company_id IN \
+                     (SELECT company_id FROM company_follows WHERE user_id = ",
+            )
+            .bind::<BigInt, _>(v)
+            .sql(
+                "))) AND NOT EXISTS (SELECT 1 FROM user_blocks ub WHERE \
+                     (ub.blocker_id = ",
+            )
+            .bind::<BigInt, _>(v)
+            .sql(" AND ub.blocked_id = posts.user_id) OR (ub.blocked_id = ")
+            .bind::<BigInt, _>(v)
+            .sql(" AND ub.blocker_id = posts.user_id))"),
+        ),
+        None => Box::new(sql::<Bool>(
+            "posts.status = 'published' AND posts.hidden_at IS NULL AND \
+             NOT EXISTS (SELECT 1 FROM users su WHERE su.id = posts.user_id \
+                 AND su.suspended_at IS NOT NULL) AND \
+             posts.visibility = 'public'",
+        )),
+    }
+}
+
+/// Turns `(post, username, name)` rows into `PostResult`s, loading the entities
+/// of all posts at once. `viewer_id` decides what polls show.
+pub fn to_post_results(
+    conn: &mut DbPooled,
+    rows: Vec<(Post, String, String)>,
+    viewer_id: Option<i64>,
+) -> QueryResult<Vec<PostResult>> {
+    let post_ids: Vec<i64> = rows.iter().map(|(p, _, _)| p.id.unwrap()).collect();
+    let mut mentions = load_mentions(conn, &post_ids)?;
+    let mut link_previews = load_previews(conn, &post_ids)?;
+    let mut polls = load_polls(conn, &post_ids, viewer_id)?;
+    let mut attachments: HashMap<i64, Vec<PostAttachment>> = HashMap::new();
+    for a in post_attachments::table
+        .filter(post_attachments::post_id.eq_any(&post_ids))
+        .order((post_attachments::post_id, post_attachments::position))
+        .load::<PostAttachment>(conn)?
+    {
+        attachments.entry(a.post_id).or_default().push(a);
+    }
+    Ok(rows
+        .into_iter()
+        .map(|(p, u, n)| PostResult {
+            body_html: markdown::render(&p.body),
+            mentions: mentions.remove(&p.id.unwrap()).unwrap_or_default(),
+            attachments: attachments.remove(&p.id.unwrap()).unwrap_or_default(),
+            link_previews: link_previews.remove(&p.id.unwrap()).unwrap_or_default(),
+            poll: polls.remove(&p.id.unwrap()),
+            pinned: false,
+            post: p,
+            username: u,
+            name: n,
+        })
+        .collect())
 }
 
 #[get("")]
@@ -47,11 +167,20 @@ async fn get_posts(req: HttpRequest, data: Data<DbPool>) -> Result<HttpResponse,
     };
     let post_query = web::Query::<PostQuery>::from_query(req.query_string()).unwrap();
     let mut query = posts.into_boxed();
-    let limit = post_query.limit.unwrap_or(20);
-    let offset = post_query.offset.unwrap_or(0);
+    let limit = pagination::page_size(post_query.limit);
+    let cursor = pagination::parse_cursor(post_query.cursor.as_ref())?;
+    let load_failed = |e: diesel::result::Error| {
+        ErrorResponse::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to load posts: {}", e),
+            Some("load_posts_failed".to_string()),
+        )
+    };
     if let Some(i) = post_query.id {
         // Find by id
-        query = query.filter(id.eq(i));
+        query = query
+            .filter(id.eq(i))
+            .filter(visible_to(post_query.viewer_id));
         let results: Vec<Post> = query
             .load::<Post>(&mut connection)
             .expect("Error loading posts");
@@ -65,7 +194,7 @@ async fn get_posts(req: HttpRequest, data: Data<DbPool>) -> Result<HttpResponse,
         let user = users
             .filter(uuser_id.eq(&results[0].user_id))
             .first::<crate::models::User>(&mut connection);
-        if let Err(_) = user {
+        if user.is_err() {
             return Err(ErrorResponse::new(
                 StatusCode::NOT_FOUND,
                 "User not found".to_string(),
@@ -73,24 +202,29 @@ async fn get_posts(req: HttpRequest, data: Data<DbPool>) -> Result<HttpResponse,
             ));
         }
         let user: crate::models::User = user.unwrap();
-        let results: Vec<PostResult> = results
+        analytics::track(
+            &mut connection,
+            &req,
+            &results.iter().collect::<Vec<_>>(),
+            post_query.viewer_id,
+            ImpressionKind::View,
+        );
+        let rows = results
             .into_iter()
-            .map(|p| PostResult {
-                post: p,
-                username: user.username.clone(),
-                name: user.name.clone(),
-            })
+            .map(|p| (p, user.username.clone(), user.name.clone()))
             .collect();
-        return Ok(OkResponse::new(
+        let results =
+            to_post_results(&mut connection, rows, post_query.viewer_id).map_err(load_failed)?;
+        Ok(OkResponse::new(
             "Post found".to_string(),
             Some(serde_json::to_value(results).unwrap()),
-        ));
+        ))
     } else if let Some(u) = &post_query.username {
         // Find by username
         let user = users
             .filter(username.eq(u))
             .first::<crate::models::User>(&mut connection);
-        if let Err(_) = user {
+        if user.is_err() {
             return Err(ErrorResponse::new(
                 StatusCode::NOT_FOUND,
                 "User not found".to_string(),
@@ -98,65 +232,167 @@ async fn get_posts(req: HttpRequest, data: Data<DbPool>) -> Result<HttpResponse,
             ));
         }
         let user: crate::models::User = user.unwrap();
-        let post_results = posts
+        let owner = PinOwner::User(user.id.unwrap());
+        let pinned = pinned_post_ids(&mut connection, owner).map_err(load_failed)?;
+        let mut query = posts
             .filter(user_id.eq(user.id.unwrap()))
-            .limit(limit)
-            .offset(offset)
-            .load::<Post>(&mut connection);
+            .filter(id.ne_all(pinned))
+            .filter(visible_to(post_query.viewer_id))
+            .order((created_at.desc(), id.desc()))
+            .limit(limit + 1)
+            .into_boxed();
+        if let Some(c) = cursor {
+            query = query.filter(
+                created_at
+                    .lt(c.created_at)
+                    .or(created_at.eq(c.created_at).and(id.lt(c.id))),
+            );
+        }
+        let post_results = query.load::<Post>(&mut connection);
         if let Ok(ps) = post_results {
-            let results: Vec<PostResult> = ps
+            let page = Page::new(ps, limit, |p| {
+                Cursor::new(p.created_at.unwrap(), p.id.unwrap())
+            });
+            let meta = page.meta();
+            analytics::track(
+                &mut connection,
+                &req,
+                &page.items.iter().collect::<Vec<_>>(),
+                post_query.viewer_id,
+                ImpressionKind::Impression,
+            );
+            let rows = page
+                .items
                 .into_iter()
-                .map(|p| PostResult {
-                    post: p,
-                    username: u.clone(),
-                    name: user.name.clone(),
-                })
+                .map(|p| (p, u.clone(), user.name.clone()))
                 .collect();
-            return Ok(OkResponse::new(
+            let results = with_pinned(
+                &mut connection,
+                owner,
+                post_query.viewer_id,
+                cursor.is_none(),
+                rows,
+            )
+            .map_err(load_failed)?;
+            Ok(OkResponse::with_meta(
                 "Posts found".to_string(),
                 Some(serde_json::to_value(results).unwrap()),
-            ));
+                meta,
+            ))
         } else {
-            return Err(ErrorResponse::new(
+            Err(ErrorResponse::new(
                 StatusCode::NOT_FOUND,
                 "Posts not found".to_string(),
                 Some("posts_not_found".to_string()),
+            ))
+        }
+    } else if let Some(c) = post_query.company_id {
+        // Company announcements
+        if crate::schema::company::table
+            .find(c)
+            .first::<crate::models::Company>(&mut connection)
+            .is_err()
+        {
+            return Err(ErrorResponse::new(
+                StatusCode::NOT_FOUND,
+                "Company not found".to_string(),
+                Some("company_not_found".to_string()),
             ));
         }
+        let owner = PinOwner::Company(c);
+        let pinned = pinned_post_ids(&mut connection, owner).map_err(load_failed)?;
+        let mut query = posts
+            .inner_join(users)
+            .filter(company_id.eq(c))
+            .filter(id.ne_all(pinned))
+            .filter(visible_to(post_query.viewer_id))
+            .select((posts::all_columns(), username, name))
+            .order((created_at.desc(), id.desc()))
+            .limit(limit + 1)
+            .into_boxed();
+        if let Some(c) = cursor {
+            query = query.filter(
+                created_at
+                    .lt(c.created_at)
+                    .or(created_at.eq(c.created_at).and(id.lt(c.id))),
+            );
+        }
+        let results = query
+            .load::<(Post, String, String)>(&mut connection)
+            .map_err(load_failed)?;
+        let page = Page::new(results, limit, |(p, _, _)| {
+            Cursor::new(p.created_at.unwrap(), p.id.unwrap())
+        });
+        let meta = page.meta();
+        analytics::track(
+            &mut connection,
+            &req,
+            &page.items.iter().map(|(p, _, _)| p).collect::<Vec<_>>(),
+            post_query.viewer_id,
+            ImpressionKind::Impression,
+        );
+        let results = with_pinned(
+            &mut connection,
+            owner,
+            post_query.viewer_id,
+            cursor.is_none(),
+            page.items,
+        )
+        .map_err(load_failed)?;
+        Ok(OkResponse::with_meta(
+            "Posts found".to_string(),
+            Some(serde_json::to_value(results).unwrap()),
+            meta,
+        ))
     } else {
         // Fetch all posts
-        let post_results = posts
+        let mut query = posts
             .inner_join(users)
+            .filter(visible_to(post_query.viewer_id))
             .select((posts::all_columns(), username, name))
-            .limit(limit)
-            .offset(offset)
-            .load::<(Post, String, String)>(&mut connection);
+            .order((created_at.desc(), id.desc()))
+            .limit(limit + 1)
+            .into_boxed();
+        if let Some(c) = cursor {
+            query = query.filter(
+                created_at
+                    .lt(c.created_at)
+                    .or(created_at.eq(c.created_at).and(id.lt(c.id))),
+            );
+        }
+        let post_results = query.load::<(Post, String, String)>(&mut connection);
         if let Ok(results) = post_results {
-            let results: Vec<PostResult> = results
-                .into_iter()
-                .map(|(p, u, n)| PostResult {
-                    post: p,
-                    username: u,
-                    name: n,
-                })
-                .collect();
-            if results.is_empty() {
+            let page = Page::new(results, limit, |(p, _, _)| {
+                Cursor::new(p.created_at.unwrap(), p.id.unwrap())
+            });
+            if page.items.is_empty() {
                 return Err(ErrorResponse::new(
                     StatusCode::NOT_FOUND,
                     "Posts not found".to_string(),
                     Some("posts_not_found".to_string()),
                 ));
             }
-            return Ok(OkResponse::new(
+            let meta = page.meta();
+            analytics::track(
+                &mut connection,
+                &req,
+                &page.items.iter().map(|(p, _, _)| p).collect::<Vec<_>>(),
+                post_query.viewer_id,
+                ImpressionKind::Impression,
+            );
+            let results = to_post_results(&mut connection, page.items, post_query.viewer_id)
+                .map_err(load_failed)?;
+            return Ok(OkResponse::with_meta(
                 "Posts found".to_string(),
                 Some(serde_json::to_value(results).unwrap()),
+                meta,
             ));
         }
-        return Err(ErrorResponse::new(
+        Err(ErrorResponse::new(
             StatusCode::BAD_REQUEST,
             "Invalid query".to_string(),
             Some("invalid_query".to_string()),
-        ));
+        ))
     }
 }
 
@@ -164,13 +400,260 @@ async fn get_posts(req: HttpRequest, data: Data<DbPool>) -> Result<HttpResponse,
 struct PostForm {
     user_id: Option<Text<i64>>,
     body: Option<Text<String>>,
+    visibility: Option<Text<String>>,
+    /// `draft`, `scheduled` or `published` (default, or `scheduled` with `publish_at`).
+    status: Option<Text<String>>,
+    /// RFC 3339 timestamp.
+    publish_at: Option<Text<String>>,
+    /// Posts on behalf of this company; the user must be one of its admins.
+    /// `followers` visibility then means followers of the company.
+    company_id: Option<Text<i64>>,
+    /// `text` (default) or `poll`.
+    kind: Option<Text<String>>,
+    /// Labels of a poll's options, one field per option, in order.
+    poll_options: Vec<Text<String>>,
+    /// RFC 3339 timestamp; defaults to a day after the post is published.
+    poll_closes_at: Option<Text<String>>,
+    #[multipart(limit = "10MiB")]
+    files: Vec<TempFile>,
+}
+
+fn parse_kind(kind: Option<Text<String>>) -> Result<PostKind, ErrorResponse> {
+    match kind {
+        Some(k) => k.parse::<PostKind>().map_err(|_| {
+            ErrorResponse::new(
+                StatusCode::BAD_REQUEST,
+                "Kind must be text or poll".to_string(),
+                Some("invalid_post_kind".to_string()),
+            )
+        }),
+        None => Ok(PostKind::default()),
+    }
+}
+
+fn parse_visibility(visibility: Option<Text<String>>) -> Result<Option<Visibility>, ErrorResponse> {
+    match visibility {
+        Some(v) => match v.parse::<Visibility>() {
+            Ok(v) => Ok(Some(v)),
+            Err(_) => Err(ErrorResponse::new(
+                StatusCode::BAD_REQUEST,
+                "Visibility must be public, followers or private".to_string(),
+                Some("invalid_visibility".to_string()),
+            )),
+        },
+        None => Ok(None),
+    }
+}
+
+/// A body may only be empty when the post has attachments to show instead.
+fn check_body(body: &str, has_attachments: bool) -> Result<(), ErrorResponse> {
+    if body.trim().is_empty() && !has_attachments {
+        return Err(ErrorResponse::new(
+            StatusCode::BAD_REQUEST,
+            "Post body is required".to_string(),
+            Some("post_body_required".to_string()),
+        ));
+    }
+    if body.chars().count() > markdown::MAX_BODY_LENGTH {
+        return Err(ErrorResponse::new(
+            StatusCode::BAD_REQUEST,
+            format!(
+                "Post body must be at most {} characters",
+                markdown::MAX_BODY_LENGTH
+            ),
+            Some("post_body_too_long".to_string()),
+        ));
+    }
+    Ok(())
+}
+
+/// Parses the status a user asks for; `held` is only set by the content filter.
+fn parse_status(status: Option<Text<String>>) -> Result<Option<PostStatus>, ErrorResponse> {
+    match status {
+        Some(s) => match s.parse::<PostStatus>() {
+            Ok(PostStatus::Held) | Err(_) => Err(ErrorResponse::new(
+                StatusCode::BAD_REQUEST,
+                "Status must be draft, scheduled or published".to_string(),
+                Some("invalid_status".to_string()),
+            )),
+            Ok(s) => Ok(Some(s)),
+        },
+        None => Ok(None),
+    }
+}
+
+/// Runs the content filter on a post about to go live. Returns the reason when
+/// the post should be held for review; rejections become errors.
+fn run_filters(
+    conn: &mut DbPooled,
+    filters: &FilterPipeline,
+    submission: &Submission,
+) -> Result<Option<String>, ErrorResponse> {
+    match filters.run(conn, submission) {
+        Ok(Verdict::Allow) => Ok(None),
+        Ok(Verdict::Hold(reason)) => Ok(Some(reason)),
+        Ok(Verdict::Reject(r)) => Err(ErrorResponse::new(
+            r.status,
+            r.message,
+            Some(r.error_code.to_string()),
+        )),
+        Err(e) => Err(ErrorResponse::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to check post content: {}", e),
+            Some("content_filter_failed".to_string()),
+        )),
+    }
+}
+
+fn parse_publish_at(
+    publish_at: Option<Text<String>>,
+) -> Result<Option<DateTime<Utc>>, ErrorResponse> {
+    match publish_at {
+        Some(p) => match DateTime::parse_from_rfc3339(&p) {
+            Ok(p) => Ok(Some(p.with_timezone(&Utc))),
+            Err(_) => Err(ErrorResponse::new(
+                StatusCode::BAD_REQUEST,
+                "Publish time must be an RFC 3339 timestamp".to_string(),
+                Some("invalid_publish_at".to_string()),
+            )),
+        },
+        None => Ok(None),
+    }
+}
+
+fn check_schedule(
+    status: PostStatus,
+    publish_at: Option<DateTime<Utc>>,
+) -> Result<(), ErrorResponse> {
+    if status != PostStatus::Scheduled {
+        return Ok(());
+    }
+    match publish_at {
+        Some(p) if p > Utc::now() => Ok(()),
+        Some(_) => Err(ErrorResponse::new(
+            StatusCode::BAD_REQUEST,
+            "Publish time must be in the future".to_string(),
+            Some("publish_at_in_past".to_string()),
+        )),
+        None => Err(ErrorResponse::new(
+            StatusCode::BAD_REQUEST,
+            "Scheduled posts need a publish time".to_string(),
+            Some("publish_at_required".to_string()),
+        )),
+    }
+}
+
+struct Upload {
+    bytes: Vec<u8>,
+    info: MediaInfo,
+    file_name: Option<String>,
+}
+
+/// Reads and validates every uploaded file before anything is written. Files
+/// are read on the blocking thread pool.
+async fn read_uploads(files: Vec<TempFile>) -> Result<Vec<Upload>, ErrorResponse> {
+    if files.len() > media::MAX_ATTACHMENTS {
+        return Err(ErrorResponse::new(
+            StatusCode::BAD_REQUEST,
+            format!("At most {} attachments are allowed", media::MAX_ATTACHMENTS),
+            Some("too_many_attachments".to_string()),
+        ));
+    }
+    let mut uploads = vec![];
+    for f in files {
+        if f.size > media::MAX_ATTACHMENT_SIZE {
+            return Err(ErrorResponse::new(
+                StatusCode::PAYLOAD_TOO_LARGE,
+                "Attachment is too large".to_string(),
+                Some("attachment_too_large".to_string()),
+            ));
+        }
+        let path = f.file.path().to_path_buf();
+        let bytes = match web::block(move || std::fs::read(path)).await {
+            Ok(Ok(b)) => b,
+            Ok(Err(e)) => return Err(read_attachment_failed(e)),
+            Err(e) => return Err(read_attachment_failed(e)),
+        };
+        let info = match media::inspect(&bytes) {
+            Some(i) => i,
+            None => {
+                return Err(ErrorResponse::new(
+                    StatusCode::UNSUPPORTED_MEDIA_TYPE,
+                    "Only PNG, JPEG, GIF, WebP and PDF attachments are allowed".to_string(),
+                    Some("unsupported_attachment_type".to_string()),
+                ));
+            }
+        };
+        uploads.push(Upload {
+            bytes,
+            info,
+            file_name: f.file_name,
+        });
+    }
+    Ok(uploads)
+}
+
+pub fn read_attachment_failed(e: impl std::fmt::Display) -> ErrorResponse {
+    ErrorResponse::new(
+        StatusCode::INTERNAL_SERVER_ERROR,
+        format!("Failed to read attachment: {}", e),
+        Some("read_attachment_failed".to_string()),
+    )
+}
+
+/// Writes `uploads` to storage under `prefix`. If one of them fails, those
+/// already written are removed again. Blocks on file IO.
+fn store_uploads(
+    storage: &dyn Storage,
+    prefix: &str,
+    uploads: &[Upload],
+) -> Result<Vec<PostAttachment>, std::io::Error> {
+    let mut stored: Vec<PostAttachment> = vec![];
+    for (i, upload) in uploads.iter().enumerate() {
+        let key = format!("{}-{}.{}", prefix, i, upload.info.extension);
+        match storage.put(&key, &upload.bytes) {
+            Ok(url) => stored.push(PostAttachment {
+                position: i as i32,
+                url,
+                storage_key: key,
+                mime_type: upload.info.mime_type.to_string(),
+                size_bytes: upload.bytes.len() as i64,
+                width: upload.info.width,
+                height: upload.info.height,
+                file_name: upload.file_name.clone(),
+                ..Default::default()
+            }),
+            Err(e) => {
+                remove_stored(storage, &stored);
+                return Err(e);
+            }
+        }
+    }
+    Ok(stored)
+}
+
+fn store_attachment_failed(e: impl std::fmt::Display) -> ErrorResponse {
+    ErrorResponse::new(
+        StatusCode::INTERNAL_SERVER_ERROR,
+        format!("Failed to store attachment: {}", e),
+        Some("store_attachment_failed".to_string()),
+    )
+}
+
+fn remove_stored(storage: &dyn Storage, stored: &[PostAttachment]) {
+    stored.iter().for_each(|a| {
+        let _ = storage.delete(&a.storage_key);
+    });
 }
 
 #[post("")]
 async fn add_post(
     MultipartForm(form): MultipartForm<PostForm>,
     data: Data<DbPool>,
+    storage: Data<dyn Storage>,
+    filters: Data<FilterPipeline>,
 ) -> Result<HttpResponse, ErrorResponse> {
+    use crate::schema::post_attachments::dsl::post_attachments;
     use crate::schema::posts::dsl::posts;
     use crate::schema::users::dsl::users;
 
@@ -196,16 +679,11 @@ async fn add_post(
     };
     let body = match form.body {
         Some(b) => b.into_inner(),
-        None => {
-            return Err(ErrorResponse::new(
-                StatusCode::BAD_REQUEST,
-                "Post body is required".to_string(),
-                Some("post_body_required".to_string()),
-            ));
-        }
+        None => String::new(),
     };
+    check_body(&body, !form.files.is_empty())?;
     let user = users.find(user_id).first::<User>(&mut connection);
-    if let Err(_) = user {
+    if user.is_err() {
         return Err(ErrorResponse::new(
             StatusCode::NOT_FOUND,
             "User not found".to_string(),
@@ -213,27 +691,430 @@ async fn add_post(
         ));
     }
     let user: User = user.unwrap();
-    match diesel::insert_into(posts)
-        .values(Post {
-            user_id: user.id.unwrap(),
-            body,
-            ..Default::default()
-        })
-        .execute(&mut connection)
-    {
+    check_not_suspended(&mut connection, user_id)?;
+    let company_id = form.company_id.map(|c| c.into_inner());
+    if let Some(c) = company_id {
+        check_company_admin(&mut connection, c, user_id)?;
+    }
+    let visibility = parse_visibility(form.visibility)?.unwrap_or_default();
+    let publish_at = parse_publish_at(form.publish_at)?;
+    let status = match parse_status(form.status)? {
+        Some(s) => s,
+        None if publish_at.is_some() => PostStatus::Scheduled,
+        None => PostStatus::Published,
+    };
+    check_schedule(status, publish_at)?;
+    let kind = parse_kind(form.kind)?;
+    let poll = match kind {
+        PostKind::Poll => {
+            let options = poll::check_poll_options(
+                form.poll_options
+                    .into_iter()
+                    .map(|o| o.into_inner())
+                    .collect(),
+            )?;
+            let closes_at = match form.poll_closes_at {
+                Some(c) => match DateTime::parse_from_rfc3339(&c) {
+                    Ok(c) => Some(c.with_timezone(&Utc)),
+                    Err(_) => {
+                        return Err(ErrorResponse::new(
+                            StatusCode::BAD_REQUEST,
+                            "Poll closing time must be an RFC 3339 timestamp".to_string(),
+                            Some("invalid_poll_closes_at".to_string()),
+                        ));
+                    }
+                },
+                None => None,
+            };
+            let opens_at = publish_at
+                .filter(|_| status == PostStatus::Scheduled)
+                .unwrap_or_else(Utc::now);
+            Some((options, poll::poll_closes_at(opens_at, closes_at)?))
+        }
+        PostKind::Text if !form.poll_options.is_empty() => {
+            return Err(ErrorResponse::new(
+                StatusCode::BAD_REQUEST,
+                "Poll options are only allowed on posts of kind poll".to_string(),
+                Some("unexpected_poll_options".to_string()),
+            ));
+        }
+        PostKind::Text => None,
+    };
+    // Drafts are only checked for rejections; holds apply once they go live.
+    let held_reason = run_filters(
+        &mut connection,
+        &filters,
+        &Submission {
+            user_id,
+            post_id: None,
+            body: &body,
+        },
+    )?
+    .filter(|_| status != PostStatus::Draft);
+    let status = if held_reason.is_some() {
+        PostStatus::Held
+    } else {
+        status
+    };
+    let uploads = read_uploads(form.files).await?;
+
+    // Files go to storage first; if the post cannot be saved they are removed again.
+    let prefix = format!(
+        "posts/{}/{}",
+        user_id,
+        Utc::now().timestamp_nanos_opt().unwrap_or_default()
+    );
+    let storage = storage.into_inner();
+    let store = {
+        let storage = storage.clone();
+        web::block(move || store_uploads(&*storage, &prefix, &uploads)).await
+    };
+    let mut stored = match store {
+        Ok(Ok(s)) => s,
+        Ok(Err(e)) => return Err(store_attachment_failed(e)),
+        Err(e) => return Err(store_attachment_failed(e)),
+    };
+
+    match connection.transaction(|conn| {
+        let new_post = diesel::insert_into(posts)
+            .values(Post {
+                user_id: user.id.unwrap(),
+                body,
+                visibility,
+                status,
+                publish_at: publish_at.filter(|_| status != PostStatus::Published),
+                company_id,
+                kind,
+                held_reason: held_reason.clone(),
+                ..Default::default()
+            })
+            .get_result::<Post>(conn)?;
+        let new_post_id = new_post.id.unwrap();
+        if let Some((options, closes_at)) = &poll {
+            poll::create_poll(conn, new_post_id, options, *closes_at)?;
+        }
+        sync_post_tags(conn, new_post_id, &new_post.body)?;
+        sync_post_links(conn, new_post_id, &new_post.body)?;
+        sync_post_mentions(
+            conn,
+            new_post_id,
+            new_post.user_id,
+            &new_post.body,
+            status == PostStatus::Published,
+        )?;
+        for a in stored.iter_mut() {
+            a.post_id = new_post_id;
+        }
+        diesel::insert_into(post_attachments)
+            .values(&stored)
+            .execute(conn)
+    }) {
+        Ok(_) if status == PostStatus::Held => {
+            Ok(OkResponse::new("Post held for review".to_string(), None))
+        }
         Ok(_) => Ok(OkResponse::new("Post added".to_string(), None)),
         Err(e) => {
-            return Err(ErrorResponse::new(
+            let _ = web::block(move || remove_stored(&*storage, &stored)).await;
+            Err(ErrorResponse::new(
                 StatusCode::INTERNAL_SERVER_ERROR,
                 format!("Failed to add post: {}", e),
                 Some("add_post_failed".to_string()),
+            ))
+        }
+    }
+}
+
+#[derive(Debug, MultipartForm)]
+struct PostUpdateForm {
+    id: Option<Text<i64>>,
+    user_id: Option<Text<i64>>,
+    body: Option<Text<String>>,
+    visibility: Option<Text<String>>,
+    status: Option<Text<String>>,
+    publish_at: Option<Text<String>>,
+}
+
+#[derive(AsChangeset)]
+#[diesel(table_name = posts)]
+struct PostUpdate {
+    body: Option<String>,
+    visibility: Option<Visibility>,
+    status: Option<PostStatus>,
+    publish_at: Option<DateTime<Utc>>,
+    created_at: Option<DateTime<Utc>>,
+    held_reason: Option<String>,
+}
+
+#[post("/update")]
+async fn update_post(
+    MultipartForm(form): MultipartForm<PostUpdateForm>,
+    data: Data<DbPool>,
+    filters: Data<FilterPipeline>,
+) -> Result<HttpResponse, ErrorResponse> {
+    use crate::schema::posts::dsl::posts;
+
+    let mut connection = match data.get() {
+        Ok(conn) => conn,
+        Err(e) => {
+            return Err(ErrorResponse::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to get db connection from pool: {}", e),
+                Some("db_connection_failed".to_string()),
             ));
         }
+    };
+    let post_id = match form.id {
+        Some(i) => i.into_inner(),
+        None => {
+            return Err(ErrorResponse::new(
+                StatusCode::BAD_REQUEST,
+                "Post id is required".to_string(),
+                Some("post_id_required".to_string()),
+            ));
+        }
+    };
+    let user_id = match form.user_id {
+        Some(u) => u.into_inner(),
+        None => {
+            return Err(ErrorResponse::new(
+                StatusCode::BAD_REQUEST,
+                "User id is required".to_string(),
+                Some("user_id_required".to_string()),
+            ));
+        }
+    };
+    check_not_suspended(&mut connection, user_id)?;
+    let mut post_update = PostUpdate {
+        body: form.body.map(|b| b.into_inner()),
+        visibility: parse_visibility(form.visibility)?,
+        status: parse_status(form.status)?,
+        publish_at: parse_publish_at(form.publish_at)?,
+        created_at: None,
+        held_reason: None,
+    };
+    if post_update.body.is_none()
+        && post_update.visibility.is_none()
+        && post_update.status.is_none()
+        && post_update.publish_at.is_none()
+    {
+        return Err(ErrorResponse::new(
+            StatusCode::BAD_REQUEST,
+            "Nothing to update".to_string(),
+            Some("nothing_to_update".to_string()),
+        ));
     }
+    let existing = match posts.find(post_id).first::<Post>(&mut connection) {
+        Ok(p) => p,
+        Err(_) => {
+            return Err(ErrorResponse::new(
+                StatusCode::NOT_FOUND,
+                "Post not found".to_string(),
+                Some("post_not_found".to_string()),
+            ));
+        }
+    };
+    if existing.user_id != user_id {
+        return Err(ErrorResponse::new(
+            StatusCode::FORBIDDEN,
+            "Only the author can edit this post".to_string(),
+            Some("not_post_owner".to_string()),
+        ));
+    }
+    if existing.status == PostStatus::Held {
+        return Err(ErrorResponse::new(
+            StatusCode::CONFLICT,
+            "Post is waiting for review and cannot be changed".to_string(),
+            Some("post_held_for_review".to_string()),
+        ));
+    }
+    if let Some(b) = &post_update.body {
+        let has_attachments = diesel::select(exists(
+            post_attachments::table.filter(post_attachments::post_id.eq(post_id)),
+        ))
+        .get_result::<bool>(&mut connection)
+        .map_err(|e| {
+            ErrorResponse::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to update post: {}", e),
+                Some("update_post_failed".to_string()),
+            )
+        })?;
+        check_body(b, has_attachments)?;
+    }
+    let was_published = existing.status == PostStatus::Published;
+    if was_published
+        && (post_update.publish_at.is_some()
+            || post_update
+                .status
+                .is_some_and(|s| s != PostStatus::Published))
+    {
+        return Err(ErrorResponse::new(
+            StatusCode::BAD_REQUEST,
+            "Published posts cannot be unpublished or rescheduled".to_string(),
+            Some("post_already_published".to_string()),
+        ));
+    }
+    let mut new_status = post_update.status.unwrap_or(existing.status);
+    check_schedule(new_status, post_update.publish_at.or(existing.publish_at))?;
+    // Content going live, or live content changing, goes through the filter again.
+    if new_status != PostStatus::Draft
+        && (post_update.body.is_some() || new_status != existing.status)
+    {
+        let submission = Submission {
+            user_id,
+            post_id: Some(post_id),
+            body: post_update.body.as_deref().unwrap_or(&existing.body),
+        };
+        if let Some(reason) = run_filters(&mut connection, &filters, &submission)? {
+            new_status = PostStatus::Held;
+            post_update.status = Some(PostStatus::Held);
+            post_update.held_reason = Some(reason);
+        }
+    }
+    let publishing = !was_published && new_status == PostStatus::Published;
+    if publishing {
+        // Feeds are ordered by created_at, so a post enters them when it goes live.
+        post_update.created_at = Some(Utc::now());
+    }
+    match connection.transaction(|conn| {
+        diesel::update(posts.find(post_id))
+            .set(&post_update)
+            .execute(conn)?;
+        if let Some(body) = &post_update.body {
+            sync_post_tags(conn, post_id, body)?;
+            sync_post_links(conn, post_id, body)?;
+            let live = was_published && new_status == PostStatus::Published;
+            sync_post_mentions(conn, post_id, user_id, body, live)?;
+        }
+        if publishing {
+            notify_mentions(conn, post_id, user_id)?;
+        }
+        Ok::<_, diesel::result::Error>(())
+    }) {
+        Ok(_) if new_status == PostStatus::Held => {
+            Ok(OkResponse::new("Post held for review".to_string(), None))
+        }
+        Ok(_) => Ok(OkResponse::new("Post updated".to_string(), None)),
+        Err(e) => Err(ErrorResponse::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to update post: {}", e),
+            Some("update_post_failed".to_string()),
+        )),
+    }
+}
+
+#[derive(Deserialize)]
+struct DraftQuery {
+    user_id: Option<i64>,
+    cursor: Option<String>,
+    limit: Option<i64>,
+}
+
+/// Drafts, scheduled posts and posts held for review of `user_id`, newest first.
+#[get("/drafts")]
+async fn get_drafts(req: HttpRequest, data: Data<DbPool>) -> Result<HttpResponse, ErrorResponse> {
+    let mut connection = match data.get() {
+        Ok(conn) => conn,
+        Err(e) => {
+            return Err(ErrorResponse::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to get db connection from pool: {}", e),
+                Some("db_connection_failed".to_string()),
+            ));
+        }
+    };
+    let draft_query = web::Query::<DraftQuery>::from_query(req.query_string()).map_err(|_| {
+        ErrorResponse::new(
+            StatusCode::BAD_REQUEST,
+            "Invalid query".to_string(),
+            Some("invalid_query".to_string()),
+        )
+    })?;
+    let user_id = match draft_query.user_id {
+        Some(u) => u,
+        None => {
+            return Err(ErrorResponse::new(
+                StatusCode::BAD_REQUEST,
+                "User id is required".to_string(),
+                Some("user_id_required".to_string()),
+            ));
+        }
+    };
+    let limit = pagination::page_size(draft_query.limit);
+    let cursor = pagination::parse_cursor(draft_query.cursor.as_ref())?;
+    let load_failed = |e: diesel::result::Error| {
+        ErrorResponse::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to load drafts: {}", e),
+            Some("load_drafts_failed".to_string()),
+        )
+    };
+
+    let mut query = posts::table
+        .inner_join(users::table)
+        .filter(posts::user_id.eq(user_id))
+        .filter(posts::status.ne(PostStatus::Published))
+        .select((posts::all_columns, users::username, users::name))
+        .order((posts::created_at.desc(), posts::id.desc()))
+        .limit(limit + 1)
+        .into_boxed();
+    if let Some(c) = cursor {
+        query = query.filter(
+            posts::created_at
+                .lt(c.created_at)
+                .or(posts::created_at.eq(c.created_at).and(posts::id.lt(c.id))),
+        );
+    }
+    let results = query
+        .load::<(Post, String, String)>(&mut connection)
+        .map_err(load_failed)?;
+    let page = Page::new(results, limit, |(p, _, _)| {
+        Cursor::new(p.created_at.unwrap(), p.id.unwrap())
+    });
+    let meta = page.meta();
+    let results =
+        to_post_results(&mut connection, page.items, Some(user_id)).map_err(load_failed)?;
+    Ok(OkResponse::with_meta(
+        "Drafts found".to_string(),
+        Some(serde_json::to_value(results).unwrap()),
+        meta,
+    ))
 }
 
-// TODO: Edit post
+/// Publishes scheduled posts whose time has come and notifies their mentions.
+/// Safe to run from several processes: each post is flipped by exactly one UPDATE.
+pub fn publish_due_posts(conn: &mut DbPooled) -> QueryResult<usize> {
+    conn.transaction(|conn| {
+        let now = Utc::now();
+        let published = diesel::update(
+            posts::table
+                .filter(posts::status.eq(PostStatus::Scheduled))
+                .filter(posts::publish_at.le(now)),
+        )
+        .set((
+            posts::status.eq(PostStatus::Published),
+            posts::created_at.eq(now),
+        ))
+        .returning((posts::id, posts::user_id))
+        .get_results::<(i64, i64)>(conn)?;
+        for (p, u) in &published {
+            notify_mentions(conn, *p, *u)?;
+        }
+        Ok(published.len())
+    })
+}
 
 pub fn init(config: &mut ServiceConfig) {
-    config.service(web::scope("/post").service(get_posts).service(add_post));
+    config.service(
+        web::scope("/post")
+            .service(get_drafts)
+            .service(search_posts)
+            .service(pin_post)
+            .service(unpin_post)
+            .service(order_pins)
+            .service(vote_poll)
+            .service(get_posts)
+            .service(add_post)
+            .service(update_post),
+    );
 }
@@ -0,0 +1,163 @@
+use crate::{
+    db::DbPool,
+    models::{Post, Report, ReportReason, ReportStatus, ReportTargetType, User},
+    response::{ErrorResponse, OkResponse},
+    routes::v1::{
+        moderation::{check_not_suspended, check_note},
+        post::visible_to,
+    },
+    schema::{posts, reports, users},
+};
+use actix_web::{
+    http::StatusCode,
+    post,
+    web::{self, Data, ServiceConfig},
+    HttpResponse, Result,
+};
+use diesel::{
+    dsl::{exists, select},
+    ExpressionMethods, QueryDsl, RunQueryDsl,
+};
+use serde::Deserialize;
+
+#[derive(Deserialize, Debug)]
+struct ReportForm {
+    user_id: i64,
+    target_type: ReportTargetType,
+    /// Required when reporting a post.
+    post_id: Option<i64>,
+    /// Required when reporting a user.
+    target_user_id: Option<i64>,
+    reason: ReportReason,
+    details: Option<String>,
+}
+
+/// Reports a post or a user to the moderators. Only posts the reporter can read
+/// can be reported, and each reporter has at most one open report per target.
+#[post("")]
+async fn add_report(
+    form: web::Json<ReportForm>,
+    data: Data<DbPool>,
+) -> Result<HttpResponse, ErrorResponse> {
+    let mut connection = match data.get() {
+        Ok(conn) => conn,
+        Err(e) => {
+            return Err(ErrorResponse::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to get db connection from pool: {}", e),
+                Some("db_connection_failed".to_string()),
+            ));
+        }
+    };
+    if users::table
+        .find(form.user_id)
+        .first::<User>(&mut connection)
+        .is_err()
+    {
+        return Err(ErrorResponse::new(
+            StatusCode::NOT_FOUND,
+            "User not found".to_string(),
+            Some("user_not_found".to_string()),
+        ));
+    }
+    check_not_suspended(&mut connection, form.user_id)?;
+    check_note(form.details.as_ref())?;
+
+    let (target_post_id, target_user_id) =
+        match (form.target_type, form.post_id, form.target_user_id) {
+            (ReportTargetType::Post, Some(p), None) => {
+                let post = posts::table
+                    .filter(posts::id.eq(p))
+                    .filter(visible_to(Some(form.user_id)))
+                    .first::<Post>(&mut connection);
+                match post {
+                    Ok(post) if post.user_id == form.user_id => {
+                        return Err(ErrorResponse::new(
+                            StatusCode::BAD_REQUEST,
+                            "You cannot report your own post".to_string(),
+                            Some("cannot_report_self".to_string()),
+                        ));
+                    }
+                    Ok(_) => (Some(p), None),
+                    Err(_) => {
+                        return Err(ErrorResponse::new(
+                            StatusCode::NOT_FOUND,
+                            "Post not found".to_string(),
+                            Some("post_not_found".to_string()),
+                        ));
+                    }
+                }
+            }
+            (ReportTargetType::User, None, Some(u)) => {
+                if u == form.user_id {
+                    return Err(ErrorResponse::new(
+                        StatusCode::BAD_REQUEST,
+                        "You cannot report yourself".to_string(),
+                        Some("cannot_report_self".to_string()),
+                    ));
+                }
+                if users::table.find(u).first::<User>(&mut connection).is_err() {
+                    return Err(ErrorResponse::new(
+                        StatusCode::NOT_FOUND,
+                        "Reported user not found".to_string(),
+                        Some("target_user_not_found".to_string()),
+                    ));
+                }
+                (None, Some(u))
+            }
+            _ => {
+                return Err(ErrorResponse::new(
+                    StatusCode::BAD_REQUEST,
+                    "Give post_id for post reports or target_user_id for user reports".to_string(),
+                    Some("invalid_report_target".to_string()),
+                ));
+            }
+        };
+
+    let failed = |e: diesel::result::Error| {
+        ErrorResponse::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to add report: {}", e),
+            Some("add_report_failed".to_string()),
+        )
+    };
+    let open = reports::table
+        .filter(reports::reporter_id.eq(form.user_id))
+        .filter(reports::status.eq(ReportStatus::Open))
+        .into_boxed();
+    let open = match (target_post_id, target_user_id) {
+        (Some(p), _) => open.filter(reports::target_post_id.eq(p)),
+        (_, Some(u)) => open.filter(reports::target_user_id.eq(u)),
+        _ => open,
+    };
+    if select(exists(open))
+        .get_result::<bool>(&mut connection)
+        .map_err(failed)?
+    {
+        return Err(ErrorResponse::new(
+            StatusCode::CONFLICT,
+            "You have already reported this".to_string(),
+            Some("already_reported".to_string()),
+        ));
+    }
+    let report = diesel::insert_into(reports::table)
+        .values(Report {
+            reporter_id: form.user_id,
+            target_type: form.target_type,
+            target_post_id,
+            target_user_id,
+            reason: form.reason,
+            details: form.details.clone(),
+            ..Default::default()
+        })
+        .get_result::<Report>(&mut connection)
+        .map_err(failed)?;
+    Ok(OkResponse::new(
+        "Report added".to_string(),
+        Some(serde_json::to_value(report).unwrap()),
+    ))
+}
+
+pub fn init(config: &mut ServiceConfig) {
+    config.service(web::scope("/report").service(add_report));
+}
@@ -0,0 +1,282 @@
+use crate::{
+    db::DbPool,
+    models::Post,
+    pagination::{self, Page, RankCursor},
+    response::{ErrorResponse, OkResponse},
+    routes::v1::post::{to_post_results, visible_to, PostResult},
+    schema::{post_tags, posts, tags, users},
+};
+use actix_web::{get, http::StatusCode, web, web::Data, HttpRequest, HttpResponse};
+use chrono::{DateTime, Utc};
+use diesel::{
+    dsl::sql,
+    sql_types::{Bool, Float, Text},
+    BoolExpressionMethods, ExpressionMethods, QueryDsl, RunQueryDsl,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+const MAX_QUERY_LENGTH: usize = 200;
+
+// Control characters mark the highlighted words so the snippet can be escaped
+// before the markers are turned into `<mark>` tags.
+const HEADLINE_OPTIONS: &str = concat!(
+    "StartSel=\u{2}, StopSel=\u{3}, ",
+    "MaxWords=30, MinWords=10, MaxFragments=2, FragmentDelimiter=\" … \"",
+);
+
+#[derive(Deserialize)]
+struct SearchQuery {
+    q: Option<String>,
+    viewer_id: Option<i64>,
+    author: Option<String>,
+    tag: Option<String>,
+    from: Option<String>,
+    to: Option<String>,
+    cursor: Option<String>,
+    limit: Option<i64>,
+}
+
+#[derive(Serialize)]
+struct SearchResult {
+    #[serde(flatten)]
+    result: PostResult,
+    rank: f32,
+    /// Matching fragments of the body, HTML-escaped, with matches in `<mark>`.
+    headline: String,
+}
+
+/// Turns the user's query into `to_tsquery` syntax. Words are ANDed, `"quoted
+/// words"` must appear next to each other, `word*` matches prefixes and `-word`
+/// excludes. Anything else is dropped, so the result is always a valid tsquery.
+fn build_tsquery(q: &str) -> Option<String> {
+    let mut terms = vec![];
+    let mut rest = q;
+    while let Some(c) = rest.chars().next() {
+        if c.is_whitespace() {
+            rest = &rest[c.len_utf8()..];
+            continue;
+        }
+        let (negated, after) = match rest.strip_prefix('-') {
+            Some(r) => (true, r),
+            None => (false, rest),
+        };
+        let (raw, phrase, next) = match after.strip_prefix('"') {
+            Some(r) => match r.find('"') {
+                Some(end) => (&r[..end], true, &r[end + 1..]),
+                None => (r, true, ""),
+            },
+            None => {
+                let end = after.find(char::is_whitespace).unwrap_or(after.len());
+                (&after[..end], false, &after[end..])
+            }
+        };
+        rest = next;
+
+        let prefix = !phrase && raw.ends_with('*');
+        let words: Vec<String> = raw
+            .split(|c: char| !c.is_alphanumeric() && c != '_')
+            .filter(|w| !w.is_empty())
+            .map(|w| w.to_lowercase())
+            .collect();
+        if words.is_empty() {
+            continue;
+        }
+        let mut term = words.join(" <-> ");
+        if prefix {
+            term.push_str(":*");
+        }
+        if words.len() > 1 {
+            term = format!("({})", term);
+        }
+        if negated {
+            term = format!("!{}", term);
+        }
+        terms.push(term);
+    }
+    if terms.iter().all(|t| t.starts_with('!')) {
+        return None;
+    }
+    Some(terms.join(" & "))
+}
+
+fn parse_time(value: Option<&String>, field: &str) -> Result<Option<DateTime<Utc>>, ErrorResponse> {
+    match value {
+        Some(v) => match DateTime::parse_from_rfc3339(v) {
+            Ok(t) => Ok(Some(t.with_timezone(&Utc))),
+            Err(_) => Err(ErrorResponse::new(
+                StatusCode::BAD_REQUEST,
+                format!("`{}` must be an RFC 3339 timestamp", field),
+                Some(format!("invalid_{}", field)),
+            )),
+        },
+        None => Ok(None),
+    }
+}
+
+/// Escapes a `ts_headline` snippet and turns its markers into `<mark>` tags.
+fn render_headline(headline: &str) -> String {
+    let mut html = String::with_capacity(headline.len());
+    for c in headline.chars() {
+        match c {
+            '&' => html.push_str("&amp;"),
+            '<' => html.push_str("&lt;"),
+            '>' => html.push_str("&gt;"),
+            '"' => html.push_str("&quot;"),
+            '\'' => html.push_str("&#39;"),
+            '\u{2}' => html.push_str("<mark>"),
+            '\u{3}' => html.push_str("</mark>"),
+            _ => html.push(c),
+        }
+    }
+    html
+}
+
+#[get("/search")]
+pub async fn search_posts(
+    req: HttpRequest,
+    data: Data<DbPool>,
+) -> Result<HttpResponse, ErrorResponse> {
+    let mut connection = match data.get() {
+        Ok(conn) => conn,
+        Err(e) => {
+            return Err(ErrorResponse::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to get db connection from pool: {}", e),
+                Some("db_connection_failed".to_string()),
+            ));
+        }
+    };
+    let search_query = match web::Query::<SearchQuery>::from_query(req.query_string()) {
+        Ok(q) => q,
+        Err(e) => {
+            return Err(ErrorResponse::new(
+                StatusCode::BAD_REQUEST,
+                format!("Invalid query: {}", e),
+                Some("invalid_query".to_string()),
+            ));
+        }
+    };
+    let q = search_query.q.as_deref().unwrap_or("").trim();
+    if q.chars().count() > MAX_QUERY_LENGTH {
+        return Err(ErrorResponse::new(
+            StatusCode::BAD_REQUEST,
+            format!(
+                "Search query is longer than {} characters",
+                MAX_QUERY_LENGTH
+            ),
+            Some("search_query_too_long".to_string()),
+        ));
+    }
+    let tsquery = match build_tsquery(q) {
+        Some(t) => t,
+        None => {
+            return Err(ErrorResponse::new(
+                StatusCode::BAD_REQUEST,
+                "Search query must contain at least one word to look for".to_string(),
+                Some("invalid_search_query".to_string()),
+            ));
+        }
+    };
+    let from = parse_time(search_query.from.as_ref(), "from")?;
+    let to = parse_time(search_query.to.as_ref(), "to")?;
+    let limit = pagination::page_size(search_query.limit);
+    let cursor = pagination::parse_page_cursor::<RankCursor>(search_query.cursor.as_ref())?;
+    let load_failed = |e: diesel::result::Error| {
+        ErrorResponse::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to search posts: {}", e),
+            Some("search_posts_failed".to_string()),
+        )
+    };
+
+    let matches = || {
+        sql::<Bool>("posts.search_vector @@ to_tsquery('english', ")
+            .bind::<Text, _>(tsquery.clone())
+            .sql(")")
+    };
+    let rank = || {
+        sql::<Float>("ts_rank_cd(posts.search_vector, to_tsquery('english', ")
+            .bind::<Text, _>(tsquery.clone())
+            .sql("), 32)")
+    };
+    let mut query = posts::table
+        .inner_join(users::table)
+        .filter(matches())
+        .filter(visible_to(search_query.viewer_id))
+        .select((posts::all_columns, users::username, users::name, rank()))
+        .order((rank().desc(), posts::id.desc()))
+        .limit(limit + 1)
+        .into_boxed();
+    if let Some(c) = cursor {
+        query = query.filter(
+            rank()
+                .lt(c.rank)
+                .or(rank().eq(c.rank).and(posts::id.lt(c.id))),
+        );
+    }
+    if let Some(a) = &search_query.author {
+        query = query.filter(users::username.eq(a.clone()));
+    }
+    if let Some(t) = &search_query.tag {
+        let tagged = post_tags::table
+            .inner_join(tags::table)
+            .filter(tags::name.eq(t.trim_start_matches('#').to_lowercase()))
+            .select(post_tags::post_id);
+        query = query.filter(posts::id.eq_any(tagged));
+    }
+    if let Some(f) = from {
+        query = query.filter(posts::created_at.ge(f));
+    }
+    if let Some(t) = to {
+        query = query.filter(posts::created_at.lt(t));
+    }
+    let rows = query
+        .load::<(Post, String, String, f32)>(&mut connection)
+        .map_err(load_failed)?;
+    let page = Page::new(rows, limit, |(p, _, _, rank)| RankCursor {
+        rank: *rank,
+        id: p.id.unwrap(),
+    });
+    let meta = page.meta();
+    let rows = page.items;
+
+    // Highlighting is comparatively slow, so it only runs on the page being returned.
+    let ids: Vec<i64> = rows.iter().map(|(p, _, _, _)| p.id.unwrap()).collect();
+    let mut headlines: HashMap<i64, String> = posts::table
+        .filter(posts::id.eq_any(&ids))
+        .select((
+            posts::id,
+            sql::<Text>("ts_headline('english', posts.body, to_tsquery('english', ")
+                .bind::<Text, _>(tsquery.clone())
+                .sql("), ")
+                .bind::<Text, _>(HEADLINE_OPTIONS)
+                .sql(")"),
+        ))
+        .load::<(i64, String)>(&mut connection)
+        .map_err(load_failed)?
+        .into_iter()
+        .collect();
+
+    let ranks: Vec<f32> = rows.iter().map(|(_, _, _, r)| *r).collect();
+    let results = to_post_results(
+        &mut connection,
+        rows.into_iter().map(|(p, u, n, _)| (p, u, n)).collect(),
+        search_query.viewer_id,
+    )
+    .map_err(load_failed)?;
+    let results: Vec<SearchResult> = results
+        .into_iter()
+        .zip(ranks)
+        .map(|(r, rank)| SearchResult {
+            headline: render_headline(&headlines.remove(&r.post.id.unwrap()).unwrap_or_default()),
+            rank,
+            result: r,
+        })
+        .collect();
+    Ok(OkResponse::with_meta(
+        "Posts found".to_string(),
+        Some(serde_json::to_value(results).unwrap()),
+        meta,
+    ))
+}
@@ -0,0 +1,231 @@
+use crate::{
+    db::{DbPool, DbPooled},
+    models::User,
+    pagination,
+    response::{ErrorResponse, OkResponse},
+    routes::v1::block::blocked_user_ids,
+    schema::{company, company_position, follow_requests, follows, position, users},
+};
+use actix_web::{
+    get,
+    http::StatusCode,
+    web::{self, Data},
+    HttpRequest, HttpResponse, Result,
+};
+use diesel::{dsl::count_star, ExpressionMethods, QueryDsl, QueryResult, RunQueryDsl};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+/// Each source of candidates is cut off at this many users before ranking.
+const MAX_CANDIDATES: i64 = 500;
+const MUTUAL_WEIGHT: i64 = 1;
+const SAME_COMPANY_WEIGHT: i64 = 3;
+const SAME_POSITION_WEIGHT: i64 = 2;
+
+#[derive(Deserialize)]
+struct SuggestionQuery {
+    user_id: i64,
+    limit: Option<i64>,
+}
+
+#[derive(Serialize)]
+struct Suggestion {
+    id: i64,
+    username: String,
+    name: String,
+    profile_picture: Option<String>,
+    /// Users the caller follows who follow this user.
+    mutual_count: i64,
+    /// Why the user is suggested, e.g. "Followed by 5 people you follow" or
+    /// "Works at Acme".
+    reasons: Vec<String>,
+}
+
+#[derive(Default)]
+struct Candidate {
+    mutual_count: i64,
+    company: Option<String>,
+    position: Option<String>,
+}
+
+impl Candidate {
+    fn score(&self) -> i64 {
+        self.mutual_count * MUTUAL_WEIGHT
+            + self.company.as_ref().map_or(0, |_| SAME_COMPANY_WEIGHT)
+            + self.position.as_ref().map_or(0, |_| SAME_POSITION_WEIGHT)
+    }
+
+    fn reasons(&self) -> Vec<String> {
+        let mut reasons = vec![];
+        match self.mutual_count {
+            0 => {}
+            1 => reasons.push("Followed by 1 person you follow".to_string()),
+            n => reasons.push(format!("Followed by {} people you follow", n)),
+        }
+        match (&self.company, &self.position) {
+            (Some(c), Some(p)) => reasons.push(format!("Works at {} as {}", c, p)),
+            (Some(c), None) => reasons.push(format!("Works at {}", c)),
+            _ => {}
+        }
+        reasons
+    }
+}
+
+/// Users never suggested to `user_id`: themselves, whom they follow or have
+/// asked to follow, and users blocked either way.
+fn excluded_users(conn: &mut DbPooled, user_id: i64) -> QueryResult<HashSet<i64>> {
+    let mut excluded: HashSet<i64> = follows::table
+        .filter(follows::following_user_id.eq(user_id))
+        .select(follows::followed_user_id)
+        .load::<i64>(conn)?
+        .into_iter()
+        .collect();
+    excluded.extend(
+        follow_requests::table
+            .filter(follow_requests::requester_id.eq(user_id))
+            .select(follow_requests::target_user_id)
+            .load::<i64>(conn)?,
+    );
+    excluded.extend(blocked_user_ids(conn, user_id)?);
+    excluded.insert(user_id);
+    Ok(excluded)
+}
+
+/// Collects candidates from friends-of-friends, counting how many of the
+/// caller's follows follow each one, and from colleagues at the caller's company.
+fn candidates(
+    conn: &mut DbPooled,
+    user_id: i64,
+    excluded: &HashSet<i64>,
+) -> QueryResult<HashMap<i64, Candidate>> {
+    let mut found: HashMap<i64, Candidate> = HashMap::new();
+    let following: Vec<i64> = follows::table
+        .filter(follows::following_user_id.eq(user_id))
+        .select(follows::followed_user_id)
+        .load(conn)?;
+    let excluded_ids: Vec<i64> = excluded.iter().copied().collect();
+    if !following.is_empty() {
+        for (u, mutual_count) in follows::table
+            .filter(follows::following_user_id.eq_any(&following))
+            .filter(follows::followed_user_id.ne_all(&excluded_ids))
+            .group_by(follows::followed_user_id)
+            .select((follows::followed_user_id, count_star()))
+            .order(count_star().desc())
+            .limit(MAX_CANDIDATES)
+            .load::<(i64, i64)>(conn)?
+        {
+            found.entry(u).or_default().mutual_count = mutual_count;
+        }
+    }
+
+    let company_position_id = users::table
+        .find(user_id)
+        .select(users::company_position_id)
+        .first::<Option<i64>>(conn)?;
+    if let Some(cp) = company_position_id {
+        let (company_id, company_name, position_id, position_name) = company_position::table
+            .inner_join(company::table)
+            .inner_join(position::table)
+            .filter(company_position::id.eq(cp))
+            .select((company::id, company::name, position::id, position::name))
+            .first::<(i64, String, i64, String)>(conn)?;
+        for (u, p) in users::table
+            .inner_join(company_position::table)
+            .filter(company_position::company_id.eq(company_id))
+            .filter(users::id.ne_all(&excluded_ids))
+            .select((users::id, company_position::position_id))
+            .limit(MAX_CANDIDATES)
+            .load::<(i64, i64)>(conn)?
+        {
+            let candidate = found.entry(u).or_default();
+            candidate.company = Some(company_name.clone());
+            if p == position_id {
+                candidate.position = Some(position_name.clone());
+            }
+        }
+    }
+    Ok(found)
+}
+
+/// Suggests users to follow, best first: people followed by many of the users
+/// the caller follows, and colleagues, above all those in the same position.
+/// Suspended users are left out.
+#[get("/suggestions")]
+pub async fn get_suggestions(
+    req: HttpRequest,
+    data: Data<DbPool>,
+) -> Result<HttpResponse, ErrorResponse> {
+    let mut connection = match data.get() {
+        Ok(conn) => conn,
+        Err(e) => {
+            return Err(ErrorResponse::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to get db connection from pool: {}", e),
+                Some("db_connection_failed".to_string()),
+            ));
+        }
+    };
+    let suggestion_query =
+        web::Query::<SuggestionQuery>::from_query(req.query_string()).map_err(|_| {
+            ErrorResponse::new(
+                StatusCode::BAD_REQUEST,
+                "Invalid query".to_string(),
+                Some("invalid_query".to_string()),
+            )
+        })?;
+    let limit = pagination::page_size(suggestion_query.limit) as usize;
+    if users::table
+        .find(suggestion_query.user_id)
+        .first::<User>(&mut connection)
+        .is_err()
+    {
+        return Err(ErrorResponse::new(
+            StatusCode::NOT_FOUND,
+            "User not found".to_string(),
+            Some("user_not_found".to_string()),
+        ));
+    }
+    let load_failed = |e: diesel::result::Error| {
+        ErrorResponse::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to load suggestions: {}", e),
+            Some("load_suggestions_failed".to_string()),
+        )
+    };
+
+    let excluded =
+        excluded_users(&mut connection, suggestion_query.user_id).map_err(load_failed)?;
+    let mut found =
+        candidates(&mut connection, suggestion_query.user_id, &excluded).map_err(load_failed)?;
+    let ids: Vec<i64> = found.keys().copied().collect();
+    let mut ranked: Vec<(Candidate, User)> = users::table
+        .filter(users::id.eq_any(&ids))
+        .filter(users::suspended_at.is_null())
+        .load::<User>(&mut connection)
+        .map_err(load_failed)?
+        .into_iter()
+        .filter_map(|u| found.remove(&u.id.unwrap()).map(|c| (c, u)))
+        .collect();
+    ranked.sort_by(|(a, ua), (b, ub)| {
+        b.score()
+            .cmp(&a.score())
+            .then(b.mutual_count.cmp(&a.mutual_count))
+            .then(ua.id.cmp(&ub.id))
+    });
+    let results: Vec<Suggestion> = ranked
+        .into_iter()
+        .take(limit)
+        .map(|(c, u)| Suggestion {
+            id: u.id.unwrap(),
+            username: u.username,
+            name: u.name,
+            profile_picture: u.profile_picture,
+            mutual_count: c.mutual_count,
+            reasons: c.reasons(),
+        })
+        .collect();
+    Ok(OkResponse::new(
+        "Suggestions found".to_string(),
+        Some(serde_json::to_value(results).unwrap()),
+    ))
+}
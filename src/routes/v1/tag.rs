@@ -0,0 +1,413 @@
+use crate::{
+    db::{DbPool, DbPooled},
+    models::{Post, Tag, User},
+    pagination::{self, Cursor, Page},
+    response::{ErrorResponse, OkResponse},
+    routes::v1::post::{to_post_results, visible_to},
+    schema::{post_tags, posts, tag_follows, tags, users},
+    text,
+};
+use actix_web::{
+    get,
+    http::StatusCode,
+    post,
+    web::{self, Data, ServiceConfig},
+    HttpRequest, HttpResponse, Result,
+};
+use chrono::{DateTime, Duration, Utc};
+use diesel::{
+    dsl::count, BoolExpressionMethods, ExpressionMethods, QueryDsl, QueryResult, RunQueryDsl,
+};
+use serde::{Deserialize, Serialize};
+
+const DEFAULT_TRENDING_HOURS: i64 = 24;
+const MAX_TRENDING_HOURS: i64 = 24 * 7;
+const DEFAULT_TRENDING_LIMIT: i64 = 10;
+const MAX_TRENDING_LIMIT: i64 = 50;
+
+#[derive(Deserialize)]
+struct TagPostQuery {
+    viewer_id: Option<i64>,
+    cursor: Option<String>,
+    limit: Option<i64>,
+}
+
+#[derive(Deserialize)]
+struct TrendingQuery {
+    hours: Option<i64>,
+    limit: Option<i64>,
+}
+
+#[derive(Serialize)]
+struct TrendingTag {
+    name: String,
+    post_count: i64,
+}
+
+#[derive(Deserialize, Debug)]
+struct TagFollowForm {
+    user_id: i64,
+    tag: String,
+}
+
+/// Replaces the tags of a post with the hashtags currently found in `body`.
+/// Meant to be called inside the transaction that writes the post.
+pub fn sync_post_tags(conn: &mut DbPooled, post_id: i64, body: &str) -> QueryResult<()> {
+    let names = text::extract_hashtags(body);
+    diesel::delete(post_tags::table.filter(post_tags::post_id.eq(post_id))).execute(conn)?;
+    if names.is_empty() {
+        return Ok(());
+    }
+    let new_tags: Vec<_> = names.iter().map(|n| tags::name.eq(n)).collect();
+    diesel::insert_into(tags::table)
+        .values(&new_tags)
+        .on_conflict(tags::name)
+        .do_nothing()
+        .execute(conn)?;
+    let tag_ids = tags::table
+        .filter(tags::name.eq_any(&names))
+        .select(tags::id)
+        .load::<i64>(conn)?;
+    let new_post_tags: Vec<_> = tag_ids
+        .into_iter()
+        .map(|t| (post_tags::post_id.eq(post_id), post_tags::tag_id.eq(t)))
+        .collect();
+    diesel::insert_into(post_tags::table)
+        .values(&new_post_tags)
+        .execute(conn)?;
+    Ok(())
+}
+
+/// Tags of posts anyone may read created after `since`, most used first. Counting
+/// only what `visible_to` shows keeps posts a moderator removed from trending.
+fn trending_tags(
+    conn: &mut DbPooled,
+    since: DateTime<Utc>,
+    limit: i64,
+) -> QueryResult<Vec<(String, i64)>> {
+    post_tags::table
+        .inner_join(tags::table)
+        .inner_join(posts::table)
+        .filter(posts::created_at.gt(since))
+        .filter(visible_to(None))
+        .group_by((tags::id, tags::name))
+        .select((tags::name, count(post_tags::id)))
+        .order((count(post_tags::id).desc(), tags::name.asc()))
+        .limit(limit)
+        .load(conn)
+}
+
+#[get("/trending")]
+async fn get_trending(req: HttpRequest, data: Data<DbPool>) -> Result<HttpResponse, ErrorResponse> {
+    let mut connection = match data.get() {
+        Ok(conn) => conn,
+        Err(e) => {
+            return Err(ErrorResponse::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to get db connection from pool: {}", e),
+                Some("db_connection_failed".to_string()),
+            ));
+        }
+    };
+    let trending_query =
+        web::Query::<TrendingQuery>::from_query(req.query_string()).map_err(|_| {
+            ErrorResponse::new(
+                StatusCode::BAD_REQUEST,
+                "Invalid query".to_string(),
+                Some("invalid_query".to_string()),
+            )
+        })?;
+    let hours = trending_query
+        .hours
+        .unwrap_or(DEFAULT_TRENDING_HOURS)
+        .clamp(1, MAX_TRENDING_HOURS);
+    let limit = trending_query
+        .limit
+        .unwrap_or(DEFAULT_TRENDING_LIMIT)
+        .clamp(1, MAX_TRENDING_LIMIT);
+    let since = Utc::now() - Duration::hours(hours);
+
+    let results = trending_tags(&mut connection, since, limit).map_err(|e| {
+        ErrorResponse::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to load trending tags: {}", e),
+            Some("load_trending_failed".to_string()),
+        )
+    })?;
+    let results: Vec<TrendingTag> = results
+        .into_iter()
+        .map(|(n, c)| TrendingTag {
+            name: n,
+            post_count: c,
+        })
+        .collect();
+    Ok(OkResponse::new(
+        "Trending tags found".to_string(),
+        Some(serde_json::to_value(results).unwrap()),
+    ))
+}
+
+#[get("/{name}")]
+async fn get_tag_posts(
+    req: HttpRequest,
+    path: web::Path<String>,
+    data: Data<DbPool>,
+) -> Result<HttpResponse, ErrorResponse> {
+    let mut connection = match data.get() {
+        Ok(conn) => conn,
+        Err(e) => {
+            return Err(ErrorResponse::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to get db connection from pool: {}", e),
+                Some("db_connection_failed".to_string()),
+            ));
+        }
+    };
+    let tag_query = web::Query::<TagPostQuery>::from_query(req.query_string()).map_err(|_| {
+        ErrorResponse::new(
+            StatusCode::BAD_REQUEST,
+            "Invalid query".to_string(),
+            Some("invalid_query".to_string()),
+        )
+    })?;
+    let limit = pagination::page_size(tag_query.limit);
+    let cursor = pagination::parse_cursor(tag_query.cursor.as_ref())?;
+    let tag = match tags::table
+        .filter(tags::name.eq(path.into_inner().to_lowercase()))
+        .first::<Tag>(&mut connection)
+    {
+        Ok(t) => t,
+        Err(_) => {
+            return Err(ErrorResponse::new(
+                StatusCode::NOT_FOUND,
+                "Tag not found".to_string(),
+                Some("tag_not_found".to_string()),
+            ));
+        }
+    };
+
+    let tagged = post_tags::table
+        .filter(post_tags::tag_id.eq(tag.id.unwrap()))
+        .select(post_tags::post_id);
+    let mut query = posts::table
+        .inner_join(users::table)
+        .filter(posts::id.eq_any(tagged))
+        .filter(visible_to(tag_query.viewer_id))
+        .select((posts::all_columns, users::username, users::name))
+        .order((posts::created_at.desc(), posts::id.desc()))
+        .limit(limit + 1)
+        .into_boxed();
+    if let Some(c) = cursor {
+        query = query.filter(
+            posts::created_at
+                .lt(c.created_at)
+                .or(posts::created_at.eq(c.created_at).and(posts::id.lt(c.id))),
+        );
+    }
+    let results = query
+        .load::<(Post, String, String)>(&mut connection)
+        .map_err(|e| {
+            ErrorResponse::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to load posts: {}", e),
+                Some("load_posts_failed".to_string()),
+            )
+        })?;
+    let page = Page::new(results, limit, |(p, _, _)| {
+        Cursor::new(p.created_at.unwrap(), p.id.unwrap())
+    });
+    let meta = page.meta();
+    let results =
+        to_post_results(&mut connection, page.items, tag_query.viewer_id).map_err(|e| {
+            ErrorResponse::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to load posts: {}", e),
+                Some("load_posts_failed".to_string()),
+            )
+        })?;
+    Ok(OkResponse::with_meta(
+        "Posts found".to_string(),
+        Some(serde_json::to_value(results).unwrap()),
+        meta,
+    ))
+}
+
+#[post("/follow")]
+async fn follow_tag(
+    form: web::Json<TagFollowForm>,
+    data: Data<DbPool>,
+) -> Result<HttpResponse, ErrorResponse> {
+    let tag_name = form.tag.trim_start_matches('#').to_lowercase();
+    if !text::is_valid_tag(&tag_name) {
+        return Err(ErrorResponse::new(
+            StatusCode::BAD_REQUEST,
+            "Invalid tag".to_string(),
+            Some("invalid_tag".to_string()),
+        ));
+    }
+
+    let mut connection = match data.get() {
+        Ok(conn) => conn,
+        Err(e) => {
+            return Err(ErrorResponse::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to get db connection from pool: {}", e),
+                Some("db_connection_failed".to_string()),
+            ));
+        }
+    };
+    if users::table
+        .find(form.user_id)
+        .first::<User>(&mut connection)
+        .is_err()
+    {
+        return Err(ErrorResponse::new(
+            StatusCode::NOT_FOUND,
+            "User not found".to_string(),
+            Some("user_not_found".to_string()),
+        ));
+    }
+    diesel::insert_into(tags::table)
+        .values(tags::name.eq(&tag_name))
+        .on_conflict(tags::name)
+        .do_nothing()
+        .execute(&mut connection)
+        .and_then(|_| {
+            tags::table
+                .filter(tags::name.eq(&tag_name))
+                .select(tags::id)
+                .first::<i64>(&mut connection)
+        })
+        .and_then(|t| {
+            diesel::insert_into(tag_follows::table)
+                .values((
+                    tag_follows::user_id.eq(form.user_id),
+                    tag_follows::tag_id.eq(t),
+                ))
+                .on_conflict((tag_follows::user_id, tag_follows::tag_id))
+                .do_nothing()
+                .execute(&mut connection)
+        })
+        .map_err(|e| {
+            ErrorResponse::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to follow tag: {}", e),
+                Some("follow_tag_failed".to_string()),
+            )
+        })?;
+    Ok(OkResponse::new("Tag followed".to_string(), None))
+}
+
+#[post("/unfollow")]
+async fn unfollow_tag(
+    form: web::Json<TagFollowForm>,
+    data: Data<DbPool>,
+) -> Result<HttpResponse, ErrorResponse> {
+    let tag_name = form.tag.trim_start_matches('#').to_lowercase();
+
+    let mut connection = match data.get() {
+        Ok(conn) => conn,
+        Err(e) => {
+            return Err(ErrorResponse::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to get db connection from pool: {}", e),
+                Some("db_connection_failed".to_string()),
+            ));
+        }
+    };
+    let followed_tag = tags::table
+        .filter(tags::name.eq(&tag_name))
+        .select(tags::id);
+    diesel::delete(
+        tag_follows::table
+            .filter(tag_follows::user_id.eq(form.user_id))
+            .filter(tag_follows::tag_id.eq_any(followed_tag)),
+    )
+    .execute(&mut connection)
+    .map_err(|e| {
+        ErrorResponse::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to unfollow tag: {}", e),
+            Some("unfollow_tag_failed".to_string()),
+        )
+    })?;
+    Ok(OkResponse::new("Tag unfollowed".to_string(), None))
+}
+
+pub fn init(config: &mut ServiceConfig) {
+    config.service(
+        web::scope("/tag")
+            .service(get_trending)
+            .service(follow_tag)
+            .service(unfollow_tag)
+            .service(get_tag_posts),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        models::Visibility,
+        schema::users,
+        testing::{insert_post, insert_user, test_connection},
+    };
+
+    #[test]
+    fn stores_unicode_tags() {
+        let Some(mut conn) = test_connection() else {
+            return;
+        };
+        let author = insert_user(&mut conn, "tag_author");
+        let body = "hello #Café #日本 #snake_case #İstanbul";
+        let post_id = insert_post(&mut conn, author, body, Visibility::Public);
+        sync_post_tags(&mut conn, post_id, body).unwrap();
+
+        let mut stored: Vec<String> = post_tags::table
+            .inner_join(tags::table)
+            .filter(post_tags::post_id.eq(post_id))
+            .select(tags::name)
+            .load(&mut conn)
+            .unwrap();
+        stored.sort();
+        assert_eq!(stored, ["café", "snake_case", "日本"]);
+    }
+
+    #[test]
+    fn trending_skips_posts_removed_by_moderators() {
+        let Some(mut conn) = test_connection() else {
+            return;
+        };
+        let author = insert_user(&mut conn, "trend_author");
+        let spammer = insert_user(&mut conn, "trend_spammer");
+        let mut tag = |user_id: i64, body: &str, visibility: Visibility| {
+            let post_id = insert_post(&mut conn, user_id, body, visibility);
+            sync_post_tags(&mut conn, post_id, body).unwrap();
+            post_id
+        };
+        tag(author, "#trendshown", Visibility::Public);
+        tag(author, "#trendfollowers", Visibility::Followers);
+        let hidden = tag(author, "#trendhidden", Visibility::Public);
+        tag(spammer, "#trendsuspended", Visibility::Public);
+        diesel::update(posts::table.find(hidden))
+            .set(posts::hidden_at.eq(Utc::now()))
+            .execute(&mut conn)
+            .unwrap();
+        diesel::update(users::table.find(spammer))
+            .set(users::suspended_at.eq(Utc::now()))
+            .execute(&mut conn)
+            .unwrap();
+
+        let trending: Vec<String> = trending_tags(
+            &mut conn,
+            Utc::now() - Duration::hours(1),
+            MAX_TRENDING_LIMIT,
+        )
+        .unwrap()
+        .into_iter()
+        .map(|(name, _)| name)
+        .filter(|name| name.starts_with("trend"))
+        .collect();
+        assert_eq!(trending, ["trendshown"]);
+    }
+}
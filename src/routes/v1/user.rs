@@ -2,7 +2,12 @@ use crate::{
     db::DbPool,
     models::User,
     response::{ErrorResponse, OkResponse},
-    routes::v1::company::get_company,
+    routes::v1::{
+        company::get_company,
+        connection::get_connections,
+        follow::{accept_all_follow_requests, get_followers, get_following},
+        suggestion::get_suggestions,
+    },
     schema::users,
 };
 use actix_multipart::form::{text::Text, MultipartForm};
@@ -14,7 +19,8 @@ use actix_web::{
     HttpRequest, HttpResponse, Result,
 };
 use diesel::{
-    prelude::AsChangeset, BoolExpressionMethods, ExpressionMethods, QueryDsl, RunQueryDsl,
+    prelude::AsChangeset, BoolExpressionMethods, Connection, ExpressionMethods, QueryDsl,
+    RunQueryDsl,
 };
 use serde::Deserialize;
 
@@ -27,7 +33,6 @@ struct UserQuery {
 
 #[get("")]
 async fn get_user(req: HttpRequest, data: Data<DbPool>) -> Result<HttpResponse, ErrorResponse> {
-    use crate::schema::follows::dsl::{followed_user_id, follows};
     use crate::schema::users::dsl::*;
 
     let mut connection = match data.get() {
@@ -75,11 +80,6 @@ async fn get_user(req: HttpRequest, data: Data<DbPool>) -> Result<HttpResponse,
         ));
     }
     let uuser = &results[0];
-    // Get follower count
-    let follow_count = follows
-        .filter(followed_user_id.eq(uuser.id.unwrap()))
-        .count()
-        .get_result::<i64>(&mut connection);
     // Get company data
     let company_data = match uuser.company_position_id {
         Some(c) => {
@@ -93,7 +93,8 @@ async fn get_user(req: HttpRequest, data: Data<DbPool>) -> Result<HttpResponse,
     };
     let mut result = serde_json::to_value(uuser).unwrap();
     let result = result.as_object_mut().unwrap();
-    result.insert("follow_count".to_string(), follow_count.unwrap().into());
+    // Older clients read the follower count under this name.
+    result.insert("follow_count".to_string(), uuser.follower_count.into());
     if let Some(c) = company_data {
         result.insert("company".to_string(), c);
     } else {
@@ -193,6 +194,8 @@ struct UserForm {
     email: Option<Text<String>>,
     username: Option<Text<String>>,
     password: Option<Text<String>>,
+    /// `true` or `false`. Making an account public approves its pending follow requests.
+    is_private: Option<Text<bool>>,
     // #[multipart(limit = "10MB")]
     // file: Option<TempFile>,
 }
@@ -202,6 +205,7 @@ struct UserUpdate {
     email: Option<String>,
     username: Option<String>,
     password: Option<String>,
+    is_private: Option<bool>,
 }
 #[post("/update")]
 async fn update_user(
@@ -232,12 +236,22 @@ async fn update_user(
             Some(p) => Some(p.into_inner()),
             None => None,
         },
+        is_private: form.is_private.map(|p| p.into_inner()),
     };
-    match diesel::update(users)
-        .filter(email.eq(form.old_email.into_inner()))
-        .set(user_update)
-        .execute(&mut connection)
-    {
+    let made_public = user_update.is_private == Some(false);
+    match connection.transaction::<_, diesel::result::Error, _>(|conn| {
+        let updated = diesel::update(users)
+            .filter(email.eq(form.old_email.into_inner()))
+            .set(user_update)
+            .returning(id)
+            .get_results::<i64>(conn)?;
+        if made_public {
+            for u in updated {
+                accept_all_follow_requests(conn, u)?;
+            }
+        }
+        Ok(())
+    }) {
         Ok(_) => Ok(OkResponse::new("User updated".to_string(), None)),
         Err(err) => Err(ErrorResponse::new(
             StatusCode::INTERNAL_SERVER_ERROR,
@@ -251,6 +265,10 @@ pub fn init(config: &mut ServiceConfig) {
     config.service(
         web::scope("/user")
             .service(get_user)
+            .service(get_suggestions)
+            .service(get_followers)
+            .service(get_following)
+            .service(get_connections)
             .service(register)
             .service(update_user),
     );
@@ -0,0 +1,81 @@
+use crate::{db::DbPool, preview::PreviewFetcher, routes};
+use std::{env, sync::Arc, thread, time::Duration};
+
+/// Reads a worker interval in seconds. Zero would spin the worker, so it falls
+/// back to `default` like a missing or malformed value.
+fn interval_from_env(key: &str, default: u64) -> u64 {
+    env::var(key)
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .filter(|v| *v > 0)
+        .unwrap_or(default)
+}
+
+/// Starts the worker that publishes scheduled posts. The schedule lives in the
+/// database, so posts that came due while the server was down go out on the first tick.
+pub fn spawn_publisher(pool: DbPool) {
+    let interval = interval_from_env("PUBLISH_INTERVAL_SECS", 30);
+    thread::spawn(move || loop {
+        match pool.get() {
+            Ok(mut conn) => match routes::publish_due_posts(&mut conn) {
+                Ok(0) => {}
+                Ok(n) => info!("Published {} scheduled post(s)", n),
+                Err(e) => error!("Failed to publish scheduled posts: {}", e),
+            },
+            Err(e) => error!("Failed to get db connection from pool: {}", e),
+        }
+        thread::sleep(Duration::from_secs(interval));
+    });
+}
+
+/// Starts the worker that fetches link previews queued by new and edited posts.
+/// The connection goes back to the pool while pages are fetched.
+pub fn spawn_link_previews(pool: DbPool, fetcher: Arc<dyn PreviewFetcher>) {
+    let interval = interval_from_env("LINK_PREVIEW_INTERVAL_SECS", 5);
+    thread::spawn(move || loop {
+        let claimed = match pool.get() {
+            Ok(mut conn) => routes::claim_link_previews(&mut conn).unwrap_or_else(|e| {
+                error!("Failed to claim link previews: {}", e);
+                vec![]
+            }),
+            Err(e) => {
+                error!("Failed to get db connection from pool: {}", e);
+                vec![]
+            }
+        };
+        if !claimed.is_empty() {
+            let fetched: Vec<_> = claimed
+                .into_iter()
+                .map(|c| {
+                    let result = fetcher.fetch(&c.url);
+                    (c, result)
+                })
+                .collect();
+            match pool.get() {
+                Ok(mut conn) => match routes::store_link_previews(&mut conn, fetched) {
+                    Ok(0) => {}
+                    Ok(n) => info!("Fetched {} link preview(s)", n),
+                    Err(e) => error!("Failed to save link previews: {}", e),
+                },
+                Err(e) => error!("Failed to get db connection from pool: {}", e),
+            }
+        }
+        thread::sleep(Duration::from_secs(interval));
+    });
+}
+
+/// Starts the worker that rolls raw post impressions up into daily stats.
+pub fn spawn_analytics_rollup(pool: DbPool) {
+    let interval = interval_from_env("ANALYTICS_ROLLUP_INTERVAL_SECS", 300);
+    thread::spawn(move || loop {
+        match pool.get() {
+            Ok(mut conn) => {
+                if let Err(e) = routes::rollup_post_stats(&mut conn) {
+                    error!("Failed to roll up post stats: {}", e);
+                }
+            }
+            Err(e) => error!("Failed to get db connection from pool: {}", e),
+        }
+        thread::sleep(Duration::from_secs(interval));
+    });
+}
@@ -1,10 +1,39 @@
 // @generated automatically by Diesel CLI.
 
+diesel::table! {
+    bookmarks (id) {
+        id -> Int8,
+        created_at -> Timestamptz,
+        user_id -> Int8,
+        post_id -> Int8,
+    }
+}
+
 diesel::table! {
     company (id) {
         id -> Int8,
         created_at -> Timestamptz,
         name -> Varchar,
+        follower_count -> Int8,
+        post_count -> Int8,
+    }
+}
+
+diesel::table! {
+    company_admins (id) {
+        id -> Int8,
+        created_at -> Timestamptz,
+        company_id -> Int8,
+        user_id -> Int8,
+    }
+}
+
+diesel::table! {
+    company_follows (id) {
+        id -> Int8,
+        created_at -> Timestamptz,
+        user_id -> Int8,
+        company_id -> Int8,
     }
 }
 
@@ -17,6 +46,44 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    connection_invitations (id) {
+        id -> Int8,
+        created_at -> Timestamptz,
+        sender_id -> Int8,
+        recipient_id -> Int8,
+        note -> Nullable<Varchar>,
+    }
+}
+
+diesel::table! {
+    connections (id) {
+        id -> Int8,
+        created_at -> Timestamptz,
+        user_id -> Int8,
+        connected_user_id -> Int8,
+    }
+}
+
+diesel::table! {
+    follow_events (id) {
+        id -> Int8,
+        created_at -> Timestamptz,
+        follower_id -> Int8,
+        followed_user_id -> Int8,
+        kind -> Varchar,
+    }
+}
+
+diesel::table! {
+    follow_requests (id) {
+        id -> Int8,
+        created_at -> Timestamptz,
+        requester_id -> Int8,
+        target_user_id -> Int8,
+    }
+}
+
 diesel::table! {
     follows (id) {
         id -> Int8,
@@ -26,6 +93,105 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    link_previews (id) {
+        id -> Int8,
+        created_at -> Timestamptz,
+        url -> Varchar,
+        status -> Varchar,
+        title -> Nullable<Varchar>,
+        description -> Nullable<Varchar>,
+        image_url -> Nullable<Varchar>,
+        site_name -> Nullable<Varchar>,
+        attempts -> Int4,
+        fetched_at -> Nullable<Timestamptz>,
+    }
+}
+
+diesel::table! {
+    mentions (id) {
+        id -> Int8,
+        created_at -> Timestamptz,
+        post_id -> Int8,
+        user_id -> Int8,
+        start_offset -> Int4,
+        end_offset -> Int4,
+    }
+}
+
+diesel::table! {
+    moderation_actions (id) {
+        id -> Int8,
+        created_at -> Timestamptz,
+        moderator_id -> Nullable<Int8>,
+        action -> Varchar,
+        report_id -> Nullable<Int8>,
+        target_post_id -> Nullable<Int8>,
+        target_user_id -> Nullable<Int8>,
+        note -> Nullable<Varchar>,
+    }
+}
+
+diesel::table! {
+    moderators (id) {
+        id -> Int8,
+        created_at -> Timestamptz,
+        user_id -> Int8,
+    }
+}
+
+diesel::table! {
+    notifications (id) {
+        id -> Int8,
+        created_at -> Timestamptz,
+        user_id -> Int8,
+        actor_user_id -> Int8,
+        kind -> Varchar,
+        post_id -> Nullable<Int8>,
+        is_read -> Bool,
+    }
+}
+
+diesel::table! {
+    pinned_posts (id) {
+        id -> Int8,
+        created_at -> Timestamptz,
+        post_id -> Int8,
+        user_id -> Nullable<Int8>,
+        position -> Int4,
+        company_id -> Nullable<Int8>,
+    }
+}
+
+diesel::table! {
+    poll_options (id) {
+        id -> Int8,
+        created_at -> Timestamptz,
+        poll_id -> Int8,
+        position -> Int4,
+        label -> Varchar,
+    }
+}
+
+diesel::table! {
+    poll_votes (id) {
+        id -> Int8,
+        created_at -> Timestamptz,
+        poll_id -> Int8,
+        poll_option_id -> Int8,
+        user_id -> Int8,
+    }
+}
+
+diesel::table! {
+    polls (id) {
+        id -> Int8,
+        created_at -> Timestamptz,
+        post_id -> Int8,
+        closes_at -> Timestamptz,
+    }
+}
+
 diesel::table! {
     position (id) {
         id -> Int8,
@@ -34,12 +200,126 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    post_attachments (id) {
+        id -> Int8,
+        created_at -> Timestamptz,
+        post_id -> Int8,
+        position -> Int4,
+        url -> Varchar,
+        storage_key -> Varchar,
+        mime_type -> Varchar,
+        size_bytes -> Int8,
+        width -> Nullable<Int4>,
+        height -> Nullable<Int4>,
+        file_name -> Nullable<Varchar>,
+    }
+}
+
+diesel::table! {
+    post_impressions (id) {
+        id -> Int8,
+        created_at -> Timestamptz,
+        post_id -> Int8,
+        kind -> Varchar,
+        viewer_key -> Varchar,
+        window_start -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    post_links (id) {
+        id -> Int8,
+        created_at -> Timestamptz,
+        post_id -> Int8,
+        link_preview_id -> Int8,
+        position -> Int4,
+    }
+}
+
+diesel::table! {
+    post_tags (id) {
+        id -> Int8,
+        created_at -> Timestamptz,
+        post_id -> Int8,
+        tag_id -> Int8,
+    }
+}
+
+diesel::table! {
+    post_stats_daily (post_id, day) {
+        post_id -> Int8,
+        day -> Date,
+        views -> Int8,
+        unique_viewers -> Int8,
+        impressions -> Int8,
+    }
+}
+
 diesel::table! {
     posts (id) {
         id -> Int8,
         created_at -> Timestamptz,
         body -> Text,
         user_id -> Int8,
+        visibility -> Varchar,
+        status -> Varchar,
+        publish_at -> Nullable<Timestamptz>,
+        kind -> Varchar,
+        hidden_at -> Nullable<Timestamptz>,
+        held_reason -> Nullable<Varchar>,
+        company_id -> Nullable<Int8>,
+    }
+}
+
+diesel::table! {
+    reports (id) {
+        id -> Int8,
+        created_at -> Timestamptz,
+        reporter_id -> Int8,
+        target_type -> Varchar,
+        target_post_id -> Nullable<Int8>,
+        target_user_id -> Nullable<Int8>,
+        reason -> Varchar,
+        details -> Nullable<Varchar>,
+        status -> Varchar,
+        resolved_by -> Nullable<Int8>,
+        resolved_at -> Nullable<Timestamptz>,
+    }
+}
+
+diesel::table! {
+    tag_follows (id) {
+        id -> Int8,
+        created_at -> Timestamptz,
+        user_id -> Int8,
+        tag_id -> Int8,
+    }
+}
+
+diesel::table! {
+    tags (id) {
+        id -> Int8,
+        created_at -> Timestamptz,
+        name -> Varchar,
+    }
+}
+
+diesel::table! {
+    user_blocks (id) {
+        id -> Int8,
+        created_at -> Timestamptz,
+        blocker_id -> Int8,
+        blocked_id -> Int8,
+    }
+}
+
+diesel::table! {
+    user_mutes (id) {
+        id -> Int8,
+        created_at -> Timestamptz,
+        muter_id -> Int8,
+        muted_id -> Int8,
     }
 }
 
@@ -54,19 +334,81 @@ diesel::table! {
         password -> Varchar,
         company_position_id -> Nullable<Int8>,
         role -> Int8,
+        suspended_at -> Nullable<Timestamptz>,
+        is_private -> Bool,
+        follower_count -> Int8,
+        following_count -> Int8,
+        post_count -> Int8,
     }
 }
 
+diesel::joinable!(bookmarks -> posts (post_id));
+diesel::joinable!(bookmarks -> users (user_id));
+diesel::joinable!(company_admins -> company (company_id));
+diesel::joinable!(company_admins -> users (user_id));
+diesel::joinable!(company_follows -> company (company_id));
+diesel::joinable!(company_follows -> users (user_id));
 diesel::joinable!(company_position -> company (company_id));
 diesel::joinable!(company_position -> position (position_id));
+diesel::joinable!(mentions -> posts (post_id));
+diesel::joinable!(mentions -> users (user_id));
+diesel::joinable!(moderation_actions -> posts (target_post_id));
+diesel::joinable!(moderation_actions -> reports (report_id));
+diesel::joinable!(moderators -> users (user_id));
+diesel::joinable!(notifications -> posts (post_id));
+diesel::joinable!(pinned_posts -> company (company_id));
+diesel::joinable!(pinned_posts -> posts (post_id));
+diesel::joinable!(pinned_posts -> users (user_id));
+diesel::joinable!(poll_options -> polls (poll_id));
+diesel::joinable!(poll_votes -> poll_options (poll_option_id));
+diesel::joinable!(poll_votes -> polls (poll_id));
+diesel::joinable!(poll_votes -> users (user_id));
+diesel::joinable!(polls -> posts (post_id));
+diesel::joinable!(post_attachments -> posts (post_id));
+diesel::joinable!(post_impressions -> posts (post_id));
+diesel::joinable!(post_links -> link_previews (link_preview_id));
+diesel::joinable!(post_links -> posts (post_id));
+diesel::joinable!(post_stats_daily -> posts (post_id));
+diesel::joinable!(post_tags -> posts (post_id));
+diesel::joinable!(post_tags -> tags (tag_id));
+diesel::joinable!(posts -> company (company_id));
 diesel::joinable!(posts -> users (user_id));
+diesel::joinable!(reports -> posts (target_post_id));
+diesel::joinable!(tag_follows -> tags (tag_id));
+diesel::joinable!(tag_follows -> users (user_id));
 diesel::joinable!(users -> company_position (company_position_id));
 
 diesel::allow_tables_to_appear_in_same_query!(
+    bookmarks,
     company,
+    company_admins,
+    company_follows,
     company_position,
+    connection_invitations,
+    connections,
+    follow_events,
+    follow_requests,
     follows,
+    link_previews,
+    mentions,
+    moderation_actions,
+    moderators,
+    notifications,
+    pinned_posts,
+    poll_options,
+    poll_votes,
+    polls,
     position,
+    post_attachments,
+    post_impressions,
+    post_links,
+    post_stats_daily,
+    post_tags,
     posts,
+    reports,
+    tag_follows,
+    tags,
+    user_blocks,
+    user_mutes,
     users,
 );
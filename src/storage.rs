@@ -0,0 +1,66 @@
+use std::{
+    env, fs, io,
+    path::{Component, Path, PathBuf},
+};
+
+/// Where uploaded files end up. Handlers only see this trait so the local disk
+/// backend can be swapped for an object store.
+pub trait Storage: Send + Sync {
+    /// Stores `bytes` under `key` and returns the public URL of the file.
+    fn put(&self, key: &str, bytes: &[u8]) -> io::Result<String>;
+    fn get(&self, key: &str) -> io::Result<Vec<u8>>;
+    fn delete(&self, key: &str) -> io::Result<()>;
+}
+
+pub struct LocalStorage {
+    pub root: PathBuf,
+    pub base_url: String,
+}
+
+impl LocalStorage {
+    /// Reads `UPLOAD_DIR` (default `uploads`) and `UPLOAD_BASE_URL` (default `/uploads`).
+    /// Files are not served from the directory directly: `/uploads` goes through
+    /// `get_attachment`, which checks the post is visible to the viewer.
+    pub fn from_env() -> io::Result<Self> {
+        let root = PathBuf::from(env::var("UPLOAD_DIR").unwrap_or_else(|_| "uploads".to_string()));
+        let base_url = env::var("UPLOAD_BASE_URL").unwrap_or_else(|_| "/uploads".to_string());
+        fs::create_dir_all(&root)?;
+        Ok(LocalStorage {
+            root,
+            base_url: base_url.trim_end_matches('/').to_string(),
+        })
+    }
+
+    fn path_of(&self, key: &str) -> io::Result<PathBuf> {
+        let key = Path::new(key);
+        if !key.components().all(|c| matches!(c, Component::Normal(_))) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "storage key must be a relative path",
+            ));
+        }
+        Ok(self.root.join(key))
+    }
+}
+
+impl Storage for LocalStorage {
+    fn put(&self, key: &str, bytes: &[u8]) -> io::Result<String> {
+        let path = self.path_of(key)?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, bytes)?;
+        Ok(format!("{}/{}", self.base_url, key))
+    }
+
+    fn get(&self, key: &str) -> io::Result<Vec<u8>> {
+        fs::read(self.path_of(key)?)
+    }
+
+    fn delete(&self, key: &str) -> io::Result<()> {
+        match fs::remove_file(self.path_of(key)?) {
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+            r => r,
+        }
+    }
+}
@@ -0,0 +1,59 @@
+//! Helpers for tests that need Postgres.
+
+use crate::{
+    db::DbPooled,
+    models::Visibility,
+    schema::{posts, users},
+};
+use diesel::{
+    pg::PgConnection,
+    r2d2::{ConnectionManager, Pool},
+    Connection, ExpressionMethods, RunQueryDsl,
+};
+use dotenv::dotenv;
+use std::env;
+
+/// A connection to `TEST_DATABASE_URL`, which must have the migrations applied.
+/// Everything runs in a transaction that is rolled back when the connection is
+/// dropped. `None` if the variable is not set, so these tests pass trivially
+/// without a database.
+pub fn test_connection() -> Option<DbPooled> {
+    dotenv().ok();
+    let database_url = env::var("TEST_DATABASE_URL").ok()?;
+    let pool = Pool::builder()
+        .max_size(1)
+        .build(ConnectionManager::<PgConnection>::new(database_url))
+        .expect("Failed to create test pool.");
+    let mut conn = pool.get().expect("Failed to connect to the test database.");
+    conn.begin_test_transaction()
+        .expect("Failed to begin test transaction.");
+    Some(conn)
+}
+
+/// Inserts a user named after `username` and returns their id.
+pub fn insert_user(conn: &mut DbPooled, username: &str) -> i64 {
+    diesel::insert_into(users::table)
+        .values((
+            users::name.eq(username),
+            users::email.eq(format!("{}@example.com", username)),
+            users::username.eq(username),
+            users::password.eq(""),
+            users::role.eq(0),
+        ))
+        .returning(users::id)
+        .get_result(conn)
+        .unwrap()
+}
+
+/// Inserts a published post and returns its id.
+pub fn insert_post(conn: &mut DbPooled, user_id: i64, body: &str, visibility: Visibility) -> i64 {
+    diesel::insert_into(posts::table)
+        .values((
+            posts::user_id.eq(user_id),
+            posts::body.eq(body),
+            posts::visibility.eq(visibility),
+        ))
+        .returning(posts::id)
+        .get_result(conn)
+        .unwrap()
+}
@@ -0,0 +1,174 @@
+use linkify::{LinkFinder, LinkKind};
+
+pub const MAX_TAG_LENGTH: usize = 64;
+pub const MAX_USERNAME_LENGTH: usize = 30;
+
+/// An `@username` occurrence; offsets are in characters, `end` is exclusive and
+/// the range includes the `@`.
+#[derive(Debug, Clone)]
+pub struct Mention {
+    pub start: usize,
+    pub end: usize,
+    pub username: String,
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// Collects the words following `sigil` that are not glued to a preceding word,
+/// so `a#b` or `mail@example` are not picked up. Returns `(char offset, word)`.
+fn extract_sigil_words(body: &str, sigil: char) -> Vec<(usize, String)> {
+    let chars: Vec<char> = body.chars().collect();
+    let mut words = vec![];
+    let mut i = 0;
+    while i < chars.len() {
+        if chars[i] == sigil && (i == 0 || !is_word_char(chars[i - 1])) {
+            let start = i + 1;
+            let mut end = start;
+            while end < chars.len() && is_word_char(chars[end]) {
+                end += 1;
+            }
+            if end > start {
+                words.push((i, chars[start..end].iter().collect()));
+            }
+            i = end.max(start);
+        } else {
+            i += 1;
+        }
+    }
+    words
+}
+
+/// Whether `name` (without the leading `#`) can be stored as a tag.
+pub fn is_valid_tag(name: &str) -> bool {
+    !name.is_empty()
+        && name.chars().count() <= MAX_TAG_LENGTH
+        && name.chars().all(is_word_char)
+        && !name.chars().all(|c| c.is_ascii_digit())
+}
+
+/// Hashtags in `body`, lowercased and deduplicated in order of appearance.
+/// Purely numeric tags (`#1`) are ignored.
+pub fn extract_hashtags(body: &str) -> Vec<String> {
+    let mut tags: Vec<String> = vec![];
+    for (_, word) in extract_sigil_words(body, '#') {
+        // Lowercasing can change characters and length, e.g. `İ`, so the stored
+        // form is what gets validated.
+        let tag = word.to_lowercase();
+        if !is_valid_tag(&tag) {
+            continue;
+        }
+        if !tags.contains(&tag) {
+            tags.push(tag);
+        }
+    }
+    tags
+}
+
+/// `@username` occurrences in `body`, in order of appearance.
+pub fn extract_mentions(body: &str) -> Vec<Mention> {
+    extract_sigil_words(body, '@')
+        .into_iter()
+        .filter(|(_, word)| word.chars().count() <= MAX_USERNAME_LENGTH)
+        .map(|(start, word)| Mention {
+            start,
+            end: start + 1 + word.chars().count(),
+            username: word,
+        })
+        .collect()
+}
+
+/// Bare links in `body`, as linked when rendering: with or without a scheme.
+pub fn extract_urls(body: &str) -> Vec<String> {
+    let mut finder = LinkFinder::new();
+    finder.kinds(&[LinkKind::Url]).url_must_have_scheme(false);
+    finder.links(body).map(|l| l.as_str().to_string()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn usernames(body: &str) -> Vec<String> {
+        extract_mentions(body)
+            .into_iter()
+            .map(|m| m.username)
+            .collect()
+    }
+
+    #[test]
+    fn hashtags() {
+        let cases: &[(&str, &[&str])] = &[
+            ("#rust is fun", &["rust"]),
+            ("Learning #Rust and #rust again", &["rust"]),
+            (
+                "trailing punctuation #one, #two. (#three)",
+                &["one", "two", "three"],
+            ),
+            ("glued a#b and issue #1 and #2024", &[]),
+            ("##double and # alone", &["double"]),
+            ("#snake_case_tag", &["snake_case_tag"]),
+            ("unicode #Café and #日本", &["café", "日本"]),
+            ("", &[]),
+        ];
+        for (body, expected) in cases {
+            assert_eq!(extract_hashtags(body), *expected, "body: {:?}", body);
+        }
+    }
+
+    #[test]
+    fn hashtags_too_long_are_ignored() {
+        let long = "a".repeat(MAX_TAG_LENGTH + 1);
+        assert!(extract_hashtags(&format!("#{}", long)).is_empty());
+        let max = "a".repeat(MAX_TAG_LENGTH);
+        assert_eq!(extract_hashtags(&format!("#{}", max)), vec![max]);
+    }
+
+    #[test]
+    fn mentions() {
+        let cases: &[(&str, &[&str])] = &[
+            ("hi @ann and @bob_2!", &["ann", "bob_2"]),
+            ("mail ann@example.com is not a mention", &[]),
+            ("@ann, @ann again", &["ann", "ann"]),
+            ("(@cat) @ alone", &["cat"]),
+            ("@zoë", &["zoë"]),
+        ];
+        for (body, expected) in cases {
+            assert_eq!(usernames(body), *expected, "body: {:?}", body);
+        }
+        let long = "a".repeat(MAX_USERNAME_LENGTH + 1);
+        assert!(usernames(&format!("@{}", long)).is_empty());
+    }
+
+    #[test]
+    fn mention_offsets_are_in_chars() {
+        let mentions = extract_mentions("héllo @ann!");
+        assert_eq!(mentions.len(), 1);
+        assert_eq!((mentions[0].start, mentions[0].end), (6, 10));
+        let chars: Vec<char> = "héllo @ann!".chars().collect();
+        let text: String = chars[mentions[0].start..mentions[0].end].iter().collect();
+        assert_eq!(text, "@ann");
+    }
+
+    #[test]
+    fn urls() {
+        let cases: &[(&str, &[&str])] = &[
+            (
+                "see https://example.com/a?b=c",
+                &["https://example.com/a?b=c"],
+            ),
+            ("bare example.com/page works", &["example.com/page"]),
+            (
+                "ends a sentence https://example.com.",
+                &["https://example.com"],
+            ),
+            ("(https://example.com/x)", &["https://example.com/x"]),
+            ("mail ann@example.com", &[]),
+            ("no links here", &[]),
+        ];
+        for (body, expected) in cases {
+            assert_eq!(extract_urls(body), *expected, "body: {:?}", body);
+        }
+    }
+}